@@ -1,12 +1,18 @@
 use aster::AstBuilder;
 use quasi::ExtParseUtils;
-use syntax::ast::{self, MetaItem, MetaItem_, StructField_, Lit_};
+use syntax::ast::{self, MetaItem, MetaItem_, Lit_};
 use syntax::codemap::{Span, Spanned};
 use syntax::ext::base::{Annotatable, ExtCtxt};
 use syntax::ptr::P;
 use syntax::attr;
-
-fn derive_type<'a>(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &'a Annotatable) ->
+use syntax::print::pprust;
+
+/// Extracts the item being derived, with its generics bounded for the impl.
+///
+/// Type parameters used by fields must be `Pod`, unless a `bound` attribute under `meta_name`
+/// replaces those bounds with its own where clause. Parameters that only appear in
+/// `PhantomData` or `skip_bound` fields are left unbounded.
+fn derive_type<'a>(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &'a Annotatable, meta_name: Option<&'static str>) ->
     Option<(AstBuilder, &'a P<ast::Item>, ast::Generics, P<ast::Ty>, ast::Path)> {
     let item = match annotatable {
         &Annotatable::Item(ref item) => item,
@@ -27,10 +33,18 @@ fn derive_type<'a>(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatab
 
     let builder = AstBuilder::new().span(span);
 
-    let generics = builder.from_generics(generics.clone())
-    .add_ty_param_bound(
-        builder.path().global().ids(&["pod", "Pod"]).build()
-    ).build();
+    let predicates = match meta_name.and_then(|meta_name| item_bound(cx, &item.attrs, meta_name)) {
+        Some(predicates) => predicates,
+        None => {
+            let fields = item_fields(&builder, item);
+            let bounds = generics.ty_params.iter().filter(|param| fields.iter().any(|field|
+                !type_name_is(field.ty, "PhantomData") && !skips_bound(field.attrs) && type_mentions(field.ty, param.ident)
+            )).map(|param| format!("{}: ::pod::Pod", param.ident)).collect::<Vec<_>>();
+            parse_where(cx, &bounds.join(", "))
+        },
+    };
+    let mut generics = generics.clone();
+    generics.where_clause.predicates.extend(predicates);
 
     let ty_path = builder.path().segment(item.ident).with_generics(generics.clone()).build().build();
     let ty = builder.ty().build_path(ty_path.clone());
@@ -47,10 +61,21 @@ fn expand_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable
         }
     };
 
+    let align = if let Some(align) = packed_align(cx, meta_item) {
+        align
+    } else {
+        return Annotatable::Item(item)
+    };
+
     let builder = AstBuilder::new().span(span);
 
-    let repr = builder.attr().list("repr").words(["C"].iter()).build();
-    let packed = builder.attr().word("__nue_packed");
+    let (repr, packed) = if align == 1 {
+        (builder.attr().list("repr").words(["C"].iter()).build(), builder.attr().word("__nue_packed"))
+    } else {
+        let align_lit = builder.lit().int(align as i64);
+        let align_str = builder.lit().str(&*align.to_string());
+        (quote_attr!(cx, #[repr(C, packed($align_lit))]), quote_attr!(cx, #[__nue_packed(align = $align_str)]))
+    };
     let derive_packed = builder.attr().word("derive_Packed");
 
     Annotatable::Item(item.map(|mut item| {
@@ -62,6 +87,35 @@ fn expand_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable
     }))
 }
 
+fn packed_align(cx: &mut ExtCtxt, meta_item: &MetaItem) -> Option<usize> {
+    match meta_item.node {
+        MetaItem_::MetaWord(_) => Some(1),
+        MetaItem_::MetaList(_, ref items) => {
+            let mut align = 1;
+            for item in items {
+                match item.node {
+                    MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. }) if *name == "align" => match value.parse::<usize>() {
+                        Ok(value) if value.is_power_of_two() => align = value,
+                        _ => {
+                            cx.span_err(item.span, "packed alignment must be a power of two");
+                            return None
+                        },
+                    },
+                    _ => {
+                        cx.span_err(item.span, "invalid attribute");
+                        return None
+                    },
+                }
+            }
+            Some(align)
+        },
+        _ => {
+            cx.span_err(meta_item.span, "invalid attribute");
+            None
+        },
+    }
+}
+
 fn expand_derive_pod_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: Annotatable) -> Annotatable {
     let item = match annotatable {
         Annotatable::Item(item) => item,
@@ -91,26 +145,82 @@ fn expr_is_false(expr: &P<ast::Expr>) -> bool {
 }
 
 fn expand_derive_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
-    let (_, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable) {
+    let (builder, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, None) {
         ret
     } else {
         return
     };
 
-    if !item.attrs.iter().any(|a| match &a.node.value.node {
-        &MetaItem_::MetaWord(ref name) if *name == "__nue_packed" || *name == "packed" => true,
-        _ => false,
-    }) {
-        cx.span_err(meta_item.span, "packed types require #[packed]");
-        return;
+    let packed = item.attrs.iter().filter_map(|a| match &a.node.value.node {
+        &MetaItem_::MetaWord(ref name) | &MetaItem_::MetaList(ref name, _) if *name == "__nue_packed" || *name == "packed" => Some(&a.node.value),
+        _ => None,
+    }).next();
+
+    let align = match packed {
+        Some(packed) => if let Some(align) = packed_align(cx, packed) {
+            align
+        } else {
+            return
+        },
+        None => {
+            cx.span_err(meta_item.span, "packed types require #[packed]");
+            return;
+        },
+    };
+
+    let where_clause = &generics.where_clause;
+
+    if align > 1 {
+        let align = builder.expr().usize(align);
+        // Fields that depend on the item's parameters can't be named outside of its impls
+        let assertions = match item.node {
+            ast::ItemStruct(ref struct_def, ref item_generics) => {
+                struct_def.fields.iter().filter(|field| !type_is_generic(&field.node.ty, item_generics)).map(|field| {
+                    let ty = &field.node.ty;
+                    quote_stmt!(cx,
+                        assert!(::std::mem::align_of::<$ty>() <= $align, concat!(stringify!($ty), " is not packed"));
+                    ).unwrap()
+                }).collect::<Vec<_>>()
+            },
+            _ => {
+                cx.span_err(meta_item.span, "packed types must be structs");
+                return
+            },
+        };
+
+        let impl_item = quote_item!(cx,
+            const _: () = {
+                $assertions
+            };
+        ).unwrap();
+        push(Annotatable::Item(impl_item));
+
+        let impl_item = quote_item!(cx,
+            #[automatically_derived]
+            unsafe impl $generics ::nue::PackedAlign for $ty $where_clause {
+                const ALIGN: usize = $align;
+            }
+        ).unwrap();
+        push(Annotatable::Item(impl_item));
+
+        return
     }
 
-    let assertions = match item.node {
-        ast::ItemStruct(ref struct_def, _) => {
-            struct_def.fields.iter().map(|field| {
+    // Fields that depend on the item's parameters can't be named outside of its impls,
+    // so they're only checked to be `Unaligned`
+    let (assertions, align_assertions) = match item.node {
+        ast::ItemStruct(ref struct_def, ref item_generics) => {
+            let assertions = struct_def.fields.iter().map(|field| {
                 let ty = &field.node.ty;
                 quote_stmt!(cx, assert::<$ty>();).unwrap()
-            }).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+            let align_assertions = struct_def.fields.iter().filter(|field| !type_is_generic(&field.node.ty, item_generics)).map(|field| {
+                let ty = &field.node.ty;
+                quote_stmt!(cx,
+                    assert!(::std::mem::align_of::<$ty>() == 1, concat!(stringify!($ty), " is not unaligned"));
+                ).unwrap()
+            }).collect::<Vec<_>>();
+            (assertions, align_assertions)
         },
         _ => {
             cx.span_err(meta_item.span, "packed types must be structs");
@@ -118,7 +228,13 @@ fn expand_derive_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
         },
     };
 
-    let where_clause = &generics.where_clause;
+    // Fieldless enums can be `Unaligned` without having an alignment of `1`
+    let impl_item = quote_item!(cx,
+        const _: () = {
+            $align_assertions
+        };
+    ).unwrap();
+    push(Annotatable::Item(impl_item));
 
     let impl_item = quote_item!(cx,
         #[automatically_derived]
@@ -140,127 +256,203 @@ fn expand_derive_packed(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
 }
 
 fn expand_derive_pod(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
-    let (_, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable) {
+    let (builder, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, None) {
         ret
     } else {
         return
     };
 
-    if !item.attrs.iter().any(|a| match &a.node.value.node {
-        &MetaItem_::MetaWord(ref name) if *name == "__nue_packed" || *name == "packed" => true,
-        _ => false,
-    }) {
-        cx.span_err(meta_item.span, "POD types require #[packed]");
-        return;
-    }
+    let where_clause = &generics.where_clause;
 
-    let assertions = match item.node {
+    let impl_item = match item.node {
         ast::ItemStruct(ref struct_def, _) => {
-            struct_def.fields.iter().map(|field| {
+            if !item.attrs.iter().any(|a| match &a.node.value.node {
+                &MetaItem_::MetaWord(ref name) if *name == "__nue_packed" || *name == "packed" => true,
+                _ => false,
+            }) {
+                cx.span_err(meta_item.span, "POD types require #[packed]");
+                return;
+            }
+
+            let assertions = struct_def.fields.iter().map(|field| {
                 let ty = &field.node.ty;
                 quote_stmt!(cx, assert::<$ty>();).unwrap()
-            }).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+            quote_item!(cx,
+                #[automatically_derived]
+                unsafe impl $generics ::pod::Pod for $ty $where_clause {
+                    fn __assert_pod() {
+                        fn assert<T: ::pod::Pod>() { }
+
+                        $assertions
+                    }
+                }
+            ).unwrap()
+        },
+        ast::ItemEnum(ref enum_def, _) => {
+            let repr = match enum_repr(item) {
+                Some(repr) => repr,
+                None => {
+                    cx.span_err(meta_item.span, "POD enums require a primitive #[repr(..)]");
+                    return
+                },
+            };
+
+            // Single byte enums may be members of packed types
+            let unaligned = repr == "u8" || repr == "i8";
+            let repr = builder.ty().id(&*repr);
+            let mut valid = quote_expr!(cx, false);
+            for variant in enum_def.variants.iter() {
+                match variant.node.kind {
+                    ast::TupleVariantKind(ref args) if args.is_empty() => (),
+                    _ => {
+                        cx.span_err(variant.span, "POD enums must not have fields");
+                        return
+                    },
+                }
+
+                let variant = cx.parse_expr(format!("{}::{}", item.ident, variant.node.name));
+                valid = quote_expr!(cx, $valid || __disc == $variant as $repr);
+            }
+
+            if unaligned {
+                push(Annotatable::Item(quote_item!(cx,
+                    #[automatically_derived]
+                    unsafe impl $generics ::pod::packed::Unaligned for $ty $where_clause { }
+                ).unwrap()));
+            }
+
+            // Not every discriminant is valid, so enums are only `CheckedPod`
+            quote_item!(cx,
+                #[automatically_derived]
+                unsafe impl $generics ::pod::CheckedPod for $ty $where_clause {
+                    fn valid_bytes(__bytes: &[u8]) -> bool {
+                        let __disc = <$repr as ::pod::Pod>::copy_from(__bytes);
+                        $valid
+                    }
+                }
+            ).unwrap()
         },
         _ => {
-            cx.span_err(meta_item.span, "POD types must be structs");
+            cx.span_err(meta_item.span, "POD types must be structs or enums");
             return
         },
     };
 
-    let where_clause = &generics.where_clause;
-
-    let impl_item = quote_item!(cx,
-        #[automatically_derived]
-        unsafe impl $generics ::pod::Pod for $ty $where_clause {
-            fn __assert_pod() {
-                fn assert<T: ::pod::Pod>() { }
-
-                $assertions
-            }
-        }
-    ).unwrap();
-
     push(Annotatable::Item(impl_item))
 }
 
 fn expand_derive_encode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
-    let (builder, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable) {
+    let (builder, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, Some("nue_enc")) {
         ret
     } else {
         return
     };
 
     let mut needs_seek = false;
+    let attrs = item_attrs(cx, &item.attrs, "nue_enc");
+    let defaults = Defaults::new(&attrs);
 
-    let encoders = match item.node {
+    let (mut encoders, options) = match item.node {
         ast::ItemStruct(ref struct_def, _) => {
-            struct_def.fields.iter().enumerate().map(|(i, field)| {
-                let field = &field.node;
-                let expr = match field.kind {
-                    ast::NamedField(name, _) => quote_expr!(cx, &self.$name),
-                    ast::UnnamedField(_) => builder.expr().addr_of().tup_field(i).build(builder.expr().self_()),
-                };
-
-                let mut cond = None;
-
-                let statement = quote_stmt!(cx,
-                    let _ = try!(::nue::Encode::encode($expr, __w));
-                ).unwrap();
-                let mut statement = vec![statement];
-
-                for attr in field_attrs(cx, field, "nue_enc", false) {
-                    match attr {
-                        FieldAttribute::Cond(expr) => cond = Some(expr),
-                        FieldAttribute::Default(_) => (),
-                        FieldAttribute::Align(expr) => {
-                            needs_seek = true;
-                            statement.insert(0, quote_stmt!(cx, let _ = try!(::nue::SeekAlignExt::align_to(__w, $expr)); ).unwrap());
-                        },
-                        FieldAttribute::Skip(expr) => {
-                            needs_seek = true;
-                            statement.insert(0, quote_stmt!(cx,
-                                let _ = try!(::nue::SeekForward::seek_forward(__w, $expr));
-                            ).unwrap());
-                        },
-                        FieldAttribute::Limit(expr) => statement.insert(0, quote_stmt!(cx, let __w = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__w), $expr); ).unwrap()),
-                        FieldAttribute::Consume(expr) => statement.push(quote_stmt!(cx,
-                            if $expr {
-                                let _ = try!(match ::std::io::copy(&mut ::std::io::repeat(0), __w) {
-                                    ::std::result::Result::Err(ref err) if err.kind() == ::std::io::ErrorKind::WriteZero => Ok(0),
-                                    res => res,
-                                });
-                            }
-                        ).unwrap()),
-                        FieldAttribute::Assert(expr) => statement.insert(0, quote_stmt!(cx,
-                            if !$expr {
-                                return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidInput, concat!("assertion ", stringify!($expr), " failed")));
-                            }
-                        ).unwrap()),
-                    }
-                }
+            let fields = struct_fields(&builder, &struct_def.fields);
+            let mut encoders = encode_fields(cx, &builder, &fields, &defaults, false, &mut needs_seek);
+            let options = match options_struct(cx, &builder, item, &attrs, "EncodeOptions") {
+                Some((options_item, options)) => {
+                    push(Annotatable::Item(options_item));
+                    encoders.insert(0, bind_options(cx));
+                    options
+                },
+                None => quote_ty!(cx, ()),
+            };
 
-                if let Some(cond) = cond {
-                    if expr_is_false(&cond) {
-                        quote_stmt!(cx, {}).unwrap()
-                    } else {
-                        quote_stmt!(cx,
-                            if $cond {
-                                $statement
-                            }
-                        ).unwrap()
-                    }
+            (encoders, options)
+        },
+        ast::ItemEnum(ref enum_def, _) => {
+            enum_options_unsupported(cx, meta_item, &attrs);
+            let tag_type = enum_tag_type(&builder, item, &attrs);
+            let variant_attrs = enum_def.variants.iter().map(|variant| variant_attrs(cx, &variant.node.attrs, "nue_enc")).collect::<Vec<_>>();
+            let other = other_variant(cx, &enum_def.variants, &variant_attrs);
+            let arms = enum_def.variants.iter().zip(discriminants(cx, &enum_def.variants, &variant_attrs)).zip(&variant_attrs).enumerate().map(|(i, ((variant, disc), attrs))| {
+                let all_fields = variant_fields(&builder, &variant.node);
+                let pat = variant_pat(span, &builder, item.ident, &variant.node, &all_fields);
+                // The `other` variant encodes its first field as the tag
+                let (tag, fields) = if other == Some(i) {
+                    let tag = all_fields[0].let_name;
+                    (quote_expr!(cx, ((*$tag) as $tag_type)), &all_fields[1..])
                 } else {
-                    quote_stmt!(cx, { $statement }).unwrap()
+                    (quote_expr!(cx, (($disc) as $tag_type)), &all_fields[..])
+                };
+                let mut encoders = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => Vec::new(),
+                    Some(cond) => {
+                        let encoders = encode_fields(cx, &builder, fields, &defaults, true, &mut needs_seek);
+                        vec![quote_stmt!(cx, if $cond { $encoders }).unwrap()]
+                    },
+                    None => encode_fields(cx, &builder, fields, &defaults, true, &mut needs_seek),
+                };
+                if let Some(align) = variant_align(attrs) {
+                    needs_seek = true;
+                    encoders.insert(0, quote_stmt!(cx, let _ = try!(::nue::SeekAlignExt::align_to(__w, $align));).unwrap());
                 }
-            }).collect::<Vec<_>>()
+                let encode_tag = encode_value(cx, quote_expr!(cx, &$tag), &tag_type, endian_wrapper(cx, defaults.endian, &tag_type), None);
+
+                quote_arm!(cx,
+                    $pat => {
+                        match __options.tag {
+                            Some(__tag) => if __tag != $tag {
+                                return Err(::std::convert::From::from(::nue::Error::Validation {
+                                    field: None,
+                                    message: format!("variant does not match the external tag {:?}", __tag),
+                                }));
+                            },
+                            None => {
+                                let _ = try!($encode_tag);
+                            },
+                        }
+                        $encoders
+                    }
+                )
+            }).collect::<Vec<_>>();
+
+            (vec![quote_stmt!(cx, match *self { $arms }).unwrap()], quote_ty!(cx, ::nue::TagOptions<$tag_type>))
         },
-        ast::ItemEnum(..) => unimplemented!(),
         _ => {
             cx.span_err(meta_item.span, "`derive` must be used on structs and enums");
             return;
         },
     };
 
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::Magic(ref magic) => encoders.insert(0, encode_magic(cx, magic)),
+            _ => (),
+        }
+    }
+
+    // Hooks run before anything is written
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::BeforeEncode(ref hook) => encoders.insert(0, quote_stmt!(cx, let _ = try!(($hook)(self));).unwrap()),
+            _ => (),
+        }
+    }
+
+    if let Some(pad) = item_pad_to(&attrs) {
+        needs_seek = true;
+        encoders.push(quote_stmt!(cx,
+            {
+                let __end = try!(::nue::Tell::tell(__w));
+                let __pad_to = ($pad) as u64;
+                if __end > __pad_to {
+                    return Err(::std::convert::From::from(::nue::Error::Limit { limit: __pad_to }));
+                }
+                let _ = try!(::nue::SeekForward::seek_forward(__w, __pad_to - __end));
+            }
+        ).unwrap());
+    }
+
     let needs_seek = if needs_seek {
         quote_stmt!(cx,
             let __w = &mut ::nue::ReadWriteTell::new(::nue::SeekForwardWrite::new(::nue::SeekAll::new(__w)));
@@ -274,9 +466,14 @@ fn expand_derive_encode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
     let impl_item = quote_item!(cx,
         #[automatically_derived]
         impl $generics ::nue::Encode for $ty $where_clause {
-            type Options = ();
+            type Options = $options;
 
             fn encode<__W: ::std::io::Write>(&self, __w: &mut __W) -> ::std::io::Result<()> {
+                ::nue::Encode::encode_options(self, __w, ::std::default::Default::default())
+            }
+
+            fn encode_options<__W: ::std::io::Write>(&self, __w: &mut __W, __options: Self::Options) -> ::std::io::Result<()> {
+                let _ = __options;
                 $needs_seek
                 $encoders
 
@@ -288,90 +485,221 @@ fn expand_derive_encode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
     push(Annotatable::Item(impl_item));
 }
 
-fn expand_derive_decode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
-    let (builder, item, generics, ty, ty_path) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable) {
+fn expand_derive_encoded_size(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
+    let (builder, item, generics, ty, _) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, Some("nue_enc")) {
         ret
     } else {
         return
     };
 
-    let mut needs_seek = false;
-    let mut tuple_struct = false;
+    let attrs = item_attrs(cx, &item.attrs, "nue_enc");
 
-    let (decoders, decoder_fields) = match item.node {
+    let mut sizes = match item.node {
         ast::ItemStruct(ref struct_def, _) => {
-            struct_def.fields.iter().enumerate().map(|(i, field)| {
-                let field = &field.node;
-                let (let_name, field_name) = match field.kind {
-                    ast::NamedField(name, _) => (builder.id(format!("__self_0{}", name)), Some(name)),
-                    ast::UnnamedField(_) => {
-                        tuple_struct = true;
-                        (builder.id(format!("__self_0{}", i)), None)
+            let fields = struct_fields(&builder, &struct_def.fields);
+            let mut sizes = size_fields(cx, &builder, &fields, false);
+            if attrs.iter().any(|attr| match *attr { ItemAttribute::Option(..) => true, _ => false }) {
+                sizes.insert(0, bind_options(cx));
+            }
+            sizes
+        },
+        ast::ItemEnum(ref enum_def, _) => {
+            let tag_type = enum_tag_type(&builder, item, &attrs);
+            let variant_attrs = enum_def.variants.iter().map(|variant| variant_attrs(cx, &variant.node.attrs, "nue_enc")).collect::<Vec<_>>();
+            let other = other_variant(cx, &enum_def.variants, &variant_attrs);
+            let arms = enum_def.variants.iter().zip(&variant_attrs).enumerate().map(|(i, (variant, attrs))| {
+                let all_fields = variant_fields(&builder, &variant.node);
+                let pat = variant_pat(span, &builder, item.ident, &variant.node, &all_fields);
+                // The first field of the `other` variant is counted as the tag
+                let fields = if other == Some(i) { &all_fields[1..] } else { &all_fields[..] };
+                let mut sizes = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => Vec::new(),
+                    Some(cond) => {
+                        let sizes = size_fields(cx, &builder, fields, true);
+                        vec![quote_stmt!(cx, if $cond { $sizes }).unwrap()]
                     },
+                    None => size_fields(cx, &builder, fields, true),
                 };
+                if let Some(align) = variant_align(attrs) {
+                    sizes.insert(0, quote_stmt!(cx,
+                        __size += (($align) as u64 - __size % ($align) as u64) % ($align) as u64;
+                    ).unwrap());
+                }
 
-                let (mut cond, mut cond_default) = (None, None);
-                let field_type = &field.ty;
-
-                let statement = quote_stmt!(cx,
-                    let $let_name: $field_type = try!(::nue::Decode::decode(__r));
-                ).unwrap();
-                let mut statement = vec![statement];
-
-                for attr in field_attrs(cx, field, "nue_dec", true) {
-                    match attr {
-                        FieldAttribute::Cond(expr) => cond = Some(expr),
-                        FieldAttribute::Default(expr) => cond_default = Some(expr),
-                        FieldAttribute::Align(expr) => {
-                            needs_seek = true;
-                            statement.insert(0, quote_stmt!(cx, let _ = try!(::nue::SeekAlignExt::align_to(__r, $expr)); ).unwrap());
-                        },
-                        FieldAttribute::Skip(expr) => {
-                            needs_seek = true;
-                            statement.insert(0, quote_stmt!(cx,
-                                let _ = try!(::nue::SeekForward::seek_forward(__r, $expr));
-                            ).unwrap());
-                        },
-                        FieldAttribute::Limit(expr) => statement.insert(0, quote_stmt!(cx, let __r = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__r), $expr); ).unwrap()),
-                        FieldAttribute::Consume(expr) => statement.push(quote_stmt!(cx,
-                            if $expr {
-                                let _ = try!(::std::io::copy(__r, &mut ::std::io::sink()));
-                            }
-                        ).unwrap()),
-                        FieldAttribute::Assert(expr) => statement.push(quote_stmt!(cx,
-                            if !$expr {
-                                return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidInput, concat!("assertion ", stringify!($expr), " failed")));
-                            }
-                        ).unwrap()),
-
+                quote_arm!(cx,
+                    $pat => {
+                        if __options.tag.is_none() {
+                            __size += ::std::mem::size_of::<$tag_type>() as u64;
+                        }
+                        $sizes
                     }
+                )
+            }).collect::<Vec<_>>();
+
+            vec![quote_stmt!(cx, match *self { $arms }).unwrap()]
+        },
+        _ => {
+            cx.span_err(meta_item.span, "`derive` must be used on structs and enums");
+            return;
+        },
+    };
+
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::Magic(ref magic) => sizes.insert(0, quote_stmt!(cx, __size += ($magic).len() as u64;).unwrap()),
+            _ => (),
+        }
+    }
+
+    if let Some(pad) = item_pad_to(&attrs) {
+        sizes.push(quote_stmt!(cx, __size = ::std::cmp::max(__size, ($pad) as u64);).unwrap());
+    }
+
+    let where_clause = &generics.where_clause;
+
+    let impl_item = quote_item!(cx,
+        #[automatically_derived]
+        impl $generics ::nue::EncodedSize for $ty $where_clause {
+            fn encoded_size(&self) -> u64 {
+                ::nue::EncodedSize::encoded_size_options(self, ::std::default::Default::default())
+            }
+
+            #[allow(unused_mut)]
+            fn encoded_size_options(&self, __options: <Self as ::nue::Encode>::Options) -> u64 {
+                let _ = __options;
+                let mut __size = 0u64;
+                $sizes
+
+                __size
+            }
+        }
+    ).unwrap();
+
+    push(Annotatable::Item(impl_item));
+}
+
+fn expand_derive_decode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
+    let (builder, item, generics, ty, ty_path) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, Some("nue_dec")) {
+        ret
+    } else {
+        return
+    };
+
+    let mut seeking = Seeking::None;
+    let attrs = item_attrs(cx, &item.attrs, "nue_dec");
+    let defaults = Defaults::new(&attrs);
+
+    // Field types can't be named in an array length if they may depend on type parameters.
+    let coalesce = generics.ty_params.is_empty();
+
+    let (mut decoders, result, options) = match item.node {
+        ast::ItemStruct(ref struct_def, _) => {
+            let fields = struct_fields(&builder, &struct_def.fields);
+            let mut decoders = decode_fields(cx, &builder, &item.ident.name.as_str(), &fields, &defaults, coalesce, &mut seeking);
+            let options = match options_struct(cx, &builder, item, &attrs, "DecodeOptions") {
+                Some((options_item, options)) => {
+                    push(Annotatable::Item(options_item));
+                    decoders.insert(0, bind_options(cx));
+                    options
+                },
+                None => quote_ty!(cx, ()),
+            };
+
+            (decoders, construct(&builder, ty_path, &fields, false), options)
+        },
+        ast::ItemEnum(ref enum_def, _) => {
+            enum_options_unsupported(cx, meta_item, &attrs);
+            let tag_type = enum_tag_type(&builder, item, &attrs);
+            let variant_attrs = enum_def.variants.iter().map(|variant| variant_attrs(cx, &variant.node.attrs, "nue_dec")).collect::<Vec<_>>();
+            let other = other_variant(cx, &enum_def.variants, &variant_attrs);
+            let variants = enum_def.variants.iter().zip(discriminants(cx, &enum_def.variants, &variant_attrs)).zip(&variant_attrs).collect::<Vec<_>>();
+
+            let (mut arms, mut fallback) = (Vec::new(), None);
+            for (i, ((variant, disc), attrs)) in variants.into_iter().enumerate() {
+                let all_fields = variant_fields(&builder, &variant.node);
+                let type_name = format!("{}::{}", item.ident.name.as_str(), variant.node.name.name.as_str());
+                let path = builder.path().ids(&[item.ident, variant.node.name]).build();
+                let unit = match variant.node.kind {
+                    ast::TupleVariantKind(ref args) => args.is_empty(),
+                    ast::StructVariantKind(..) => false,
+                };
+                let ctor = construct(&builder, path, &all_fields, unit);
+
+                // The `other` variant takes any unknown tag as its first field
+                let (tag, fields) = if other == Some(i) {
+                    let (tag, field_type) = (all_fields[0].let_name, all_fields[0].ty);
+                    (vec![quote_stmt!(cx, let $tag: $field_type = __tag as $field_type;).unwrap()], &all_fields[1..])
+                } else {
+                    (Vec::new(), &all_fields[..])
+                };
+
+                // The payload of a variant whose condition isn't met is left to `Default::default()`
+                let defaulted = fields.iter().map(|field| {
+                    let (let_name, field_type) = (field.let_name, field.ty);
+                    quote_stmt!(cx, let $let_name: $field_type = ::std::default::Default::default();).unwrap()
+                }).collect::<Vec<_>>();
+                let mut payload = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => quote_expr!(cx, { $defaulted $ctor }),
+                    Some(cond) => {
+                        let decoders = decode_fields(cx, &builder, &type_name, fields, &defaults, coalesce, &mut seeking);
+                        quote_expr!(cx,
+                            if $cond {
+                                $decoders
+                                $ctor
+                            } else {
+                                $defaulted
+                                $ctor
+                            }
+                        )
+                    },
+                    None => {
+                        let decoders = decode_fields(cx, &builder, &type_name, fields, &defaults, coalesce, &mut seeking);
+                        quote_expr!(cx, { $decoders $ctor })
+                    },
+                };
+                if let Some(align) = variant_align(attrs) {
+                    seeking.require(Seeking::Forward);
+                    payload = quote_expr!(cx, {
+                        let _ = try!(::nue::SeekAlignExt::align_to(__r, $align));
+                        $payload
+                    });
                 }
+                let payload = quote_expr!(cx, {
+                    $tag
+                    $payload
+                });
 
-                let statement = if let Some(cond) = cond {
-                    let default = cond_default.unwrap_or_else(|| quote_expr!(cx, ::std::default::Default::default()));
+                if other == Some(i) {
+                    fallback = Some(payload);
+                } else {
+                    arms.push((disc, payload));
+                }
+            }
 
-                    if expr_is_false(&cond) {
-                        quote_stmt!(cx, let $let_name = $default;).unwrap()
+            let mut result = fallback.unwrap_or_else(|| quote_expr!(cx,
+                return Err(::std::convert::From::from(::nue::Error::Validation {
+                    field: None,
+                    message: format!("unknown discriminant {:?}", __tag),
+                }))
+            ));
+            for (disc, payload) in arms.into_iter().rev() {
+                result = quote_expr!(cx,
+                    if __tag == (($disc) as $tag_type) {
+                        $payload
                     } else {
-                        quote_stmt!(cx,
-                            let $let_name = if $cond {
-                                $statement;
-                                $let_name
-                            } else {
-                                $default
-                            };
-                        ).unwrap()
+                        $result
                     }
-                } else {
-                    quote_stmt!(cx, let $let_name = { $statement; $let_name };).unwrap()
-                };
+                );
+            }
 
-                (statement, (let_name, field_name))
-            }).unzip::<_, _, Vec<_>, Vec<_>>()
-        },
-        ast::ItemEnum(..) => {
-            cx.span_err(meta_item.span, "enums cannot be decoded");
-            return;
+            let decode_tag = decode_value(cx, &tag_type, endian_wrapper(cx, defaults.endian, &tag_type), None);
+            let decoders = vec![quote_stmt!(cx,
+                let __tag: $tag_type = match __options.tag {
+                    Some(__tag) => __tag,
+                    None => try!($decode_tag),
+                };
+            ).unwrap()];
+            (decoders, result, quote_ty!(cx, ::nue::TagOptions<$tag_type>))
         },
         _ => {
             cx.span_err(meta_item.span, "`derive` must be used on structs and enums");
@@ -379,31 +707,63 @@ fn expand_derive_decode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
         },
     };
 
-    let needs_seek = if needs_seek {
-        quote_stmt!(cx,
-            let __r = &mut ::nue::ReadWriteTell::new(::nue::SeekForwardRead::new(::nue::SeekAll::new(__r)));
-        )
-    } else {
-        quote_stmt!(cx, let __r = &mut ::nue::SeekAll::new(__r);)
-    }.unwrap();
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::Magic(ref magic) => decoders.insert(0, decode_magic(cx, magic)),
+            _ => (),
+        }
+    }
 
-    let result = if tuple_struct {
-        builder.expr().call().build_path(ty_path).with_args(decoder_fields.into_iter().map(|(let_name, _)| builder.expr().id(let_name))).build()
-    } else {
-        builder.expr().struct_path(ty_path).with_id_exprs(decoder_fields.into_iter().map(|(let_name, field_name)| (field_name.unwrap(), builder.expr().id(let_name)))).build()
+    let mut finish = match item_pad_to(&attrs) {
+        Some(pad) => {
+            seeking.require(Seeking::Forward);
+            vec![quote_stmt!(cx,
+                {
+                    let __end = try!(::nue::Tell::tell(__r));
+                    let __pad_to = ($pad) as u64;
+                    if __end > __pad_to {
+                        return Err(::std::convert::From::from(::nue::Error::Limit { limit: __pad_to }));
+                    }
+                    let _ = try!(::nue::SeekForward::seek_forward(__r, __pad_to - __end));
+                }
+            ).unwrap()]
+        },
+        None => Vec::new(),
     };
 
+    for attr in &attrs {
+        match *attr {
+            ItemAttribute::AfterDecode(ref hook) => finish.push(quote_stmt!(cx, let _ = try!(($hook)(&mut __result));).unwrap()),
+            _ => (),
+        }
+    }
+
+    let needs_seek = match seeking {
+        Seeking::Rewind => quote_stmt!(cx, let __r = &mut ::nue::RewindableReader::new(__r);),
+        Seeking::Forward => quote_stmt!(cx,
+            let __r = &mut ::nue::ReadWriteTell::new(::nue::SeekForwardRead::new(::nue::SeekAll::new(__r)));
+        ),
+        Seeking::None => quote_stmt!(cx, let __r = &mut ::nue::ReadWriteTell::new(::nue::SeekAll::new(__r));),
+    }.unwrap();
+
     let where_clause = &generics.where_clause;
 
     let impl_item = quote_item!(cx,
         #[automatically_derived]
         impl $generics ::nue::Decode for $ty $where_clause {
-            type Options = ();
+            type Options = $options;
 
             fn decode<__R: ::std::io::Read>(__r: &mut __R) -> ::std::io::Result<Self> {
+                ::nue::Decode::decode_options(__r, ::std::default::Default::default())
+            }
+
+            fn decode_options<__R: ::std::io::Read>(__r: &mut __R, __options: Self::Options) -> ::std::io::Result<Self> {
+                let _ = __options;
                 $needs_seek
                 $decoders
-                let __result = $result;
+                #[allow(unused_mut)]
+                let mut __result = $result;
+                $finish
 
                 let _ = try!(::nue::Decode::validate(&__result));
 
@@ -415,59 +775,1841 @@ fn expand_derive_decode(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, anno
     push(Annotatable::Item(impl_item));
 }
 
-fn field_attrs(cx: &mut ExtCtxt, field: &StructField_, meta_name: &'static str, replace_self: bool) -> Vec<FieldAttribute> {
-    fn attr_expr(cx: &mut ExtCtxt, replace_self: bool, value: &str) -> P<ast::Expr> {
-        let value = if replace_self {
-            value.replace("self.", "__self_0")
-        } else {
-            value.into()
-        };
-        cx.parse_expr(value)
-    }
+fn expand_derive_decode_ref(cx: &mut ExtCtxt, span: Span, meta_item: &MetaItem, annotatable: &Annotatable, push: &mut FnMut(Annotatable)) {
+    let (builder, item, generics, ty, ty_path) = if let Some(ret) = derive_type(cx, span, meta_item, annotatable, Some("nue_dec")) {
+        ret
+    } else {
+        return
+    };
 
-    let attr = field.attrs.iter().filter_map(|v| match &v.node.value.node {
-        &MetaItem_::MetaList(ref name, ref attrs) if *name == meta_name || *name == "nue" => {
-            attr::mark_used(v);
+    let lifetime = match generics.lifetimes.first() {
+        Some(def) => def.lifetime,
+        None => {
+            cx.span_err(meta_item.span, "`NueDecodeRef` requires a lifetime parameter to borrow from the slice with");
+            return
+        },
+    };
 
-            Some(attrs)
+    let fields = match item.node {
+        ast::ItemStruct(ref struct_def, _) => struct_fields(&builder, &struct_def.fields),
+        _ => {
+            cx.span_err(meta_item.span, "`NueDecodeRef` must be used on structs");
+            return
         },
-        _ => None,
-    });
+    };
 
+    let attrs = item_attrs(cx, &item.attrs, "nue_dec");
+    let defaults = Defaults::new(&attrs);
+    let mut decoders = decode_ref_fields(cx, &builder, &item.ident.name.as_str(), &fields, &defaults);
+    let result = construct(&builder, ty_path, &fields, false);
+
+    let mut finish = Vec::new();
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::Magic(ref magic) => decoders.insert(0, decode_magic(cx, magic)),
+            ItemAttribute::AfterDecode(ref hook) => finish.insert(0, quote_stmt!(cx, let _ = try!(($hook)(&mut __result));).unwrap()),
+            ItemAttribute::PadTo(_) | ItemAttribute::Option(..) => cx.span_err(meta_item.span, "attribute is not supported by `NueDecodeRef`"),
+            _ => (),
+        }
+    }
 
-    let mut attrs = Vec::new();
-    for attr in attr {
-        for attr in attr.iter() {
-            match &attr.node {
-                &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. } ) => match &**name {
-                    "assert" => attrs.push(FieldAttribute::Assert(attr_expr(cx, replace_self, &value))),
-                    "align" => attrs.push(FieldAttribute::Align(attr_expr(cx, replace_self, &value))),
-                    "skip" => attrs.push(FieldAttribute::Skip(attr_expr(cx, replace_self, &value))),
-                    "limit" => attrs.push(FieldAttribute::Limit(attr_expr(cx, replace_self, &value))),
-                    "cond" => attrs.push(FieldAttribute::Cond(attr_expr(cx, replace_self, &value))),
-                    "default" => attrs.push(FieldAttribute::Default(attr_expr(cx, replace_self, &value))),
-                    "consume" => attrs.push(FieldAttribute::Consume(attr_expr(cx, replace_self, &value))),
-                    _ => {
-                        cx.span_err(attr.span, "invalid attribute key");
-                        break
-                    },
-                },
-                _ => {
-                    cx.span_err(attr.span, "invalid attribute");
-                    break
-                },
+    let where_clause = &generics.where_clause;
+
+    let impl_item = quote_item!(cx,
+        #[automatically_derived]
+        impl $generics ::nue::DecodeRef<$lifetime> for $ty $where_clause {
+            type Options = ();
+
+            #[allow(unused_variables)]
+            fn decode_ref_options(__r: &mut &$lifetime [u8], __options: Self::Options) -> ::std::io::Result<Self> {
+                let __start = __r.len();
+                $decoders
+                #[allow(unused_mut)]
+                let mut __result = $result;
+                $finish
+
+                Ok(__result)
             }
         }
-    }
-    attrs
+    ).unwrap();
+
+    push(Annotatable::Item(impl_item));
 }
 
-enum FieldAttribute {
-    Cond(P<ast::Expr>),
-    Default(P<ast::Expr>),
-    Align(P<ast::Expr>),
-    Limit(P<ast::Expr>),
-    Skip(P<ast::Expr>),
-    Consume(P<ast::Expr>),
+/// Generates statements that decode each field from the slice `__r`, borrowing
+/// from it for field types that have a lifetime.
+///
+/// Only the attributes that don't require seeking are supported.
+fn decode_ref_fields(cx: &mut ExtCtxt, builder: &AstBuilder, type_name: &str, fields: &[Field], defaults: &Defaults) -> Vec<P<ast::Stmt>> {
+    let mut attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_dec", &SelfAccess::Locals)).collect::<Vec<_>>();
+    count_of_fields(cx, builder, fields, &mut attrs);
+    let type_str = builder.expr().str(type_name);
+    let byte_order = defaults.byte_order.as_ref().map(|value| attr_expr(cx, &SelfAccess::Locals, value));
+
+    fields.iter().zip(attrs).map(|(field, attrs)| {
+        let let_name = field.let_name;
+        let field_str = builder.expr().str(&*field.label());
+        let field_type = field.ty;
+        let (mut cond, mut cond_default) = (None, None);
+
+        let count = find_attr(&attrs, |attr| match *attr { FieldAttribute::Count(ref expr) => Some(expr), _ => None });
+        let position = attrs.iter().any(|attr| match *attr { FieldAttribute::Position => true, _ => false });
+        let value = if position {
+            quote_expr!(cx, Ok::<_, ::std::io::Error>((__start - __r.len()) as $field_type))
+        } else if type_borrows(field_type) {
+            let options = match count {
+                Some(count) if type_is_str_ref(field_type) => quote_expr!(cx, ::nue::StringDecodeOptions { len: Some(($count) as usize) }),
+                Some(count) => quote_expr!(cx, ::nue::VecDecodeOptions { len: Some(($count) as usize), options: () }),
+                None => quote_expr!(cx, ::std::default::Default::default()),
+            };
+            quote_expr!(cx, ::nue::DecodeRef::decode_ref_options(__r, $options))
+        } else {
+            match decode_options_expr(cx, &attrs, field_type) {
+                Some(options) => quote_expr!(cx, ::nue::Decode::decode_options(__r, $options)),
+                None => {
+                    let (wrapper, order) = (field_wrapper(cx, &attrs, defaults, field_type), field_byte_order(cx, &attrs, byte_order.as_ref(), field_type));
+                    decode_value(cx, field_type, wrapper, order)
+                },
+            }
+        };
+
+        let mut statement = vec![
+            quote_stmt!(cx, let __offset = (__start - __r.len()) as u64;).unwrap(),
+            quote_stmt!(cx,
+                let $let_name: $field_type = match $value {
+                    Ok(__value) => __value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field($type_str, $field_str, Some(__offset)))),
+                };
+            ).unwrap(),
+        ];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, decode_magic(cx, &magic));
+        }
+
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Default(expr) => cond_default = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::CountOf(..) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::Position |
+                FieldAttribute::Value(_) | FieldAttribute::Varint => (),
+                FieldAttribute::Skip(expr) => statement.insert(0, quote_stmt!(cx,
+                    {
+                        let __skip = ($expr) as usize;
+                        if __r.len() < __skip {
+                            return Err(::std::convert::From::from(::nue::Error::UnexpectedEof { needed: Some((__skip - __r.len()) as u64) }));
+                        }
+                        let __data = *__r;
+                        *__r = &__data[__skip..];
+                    }
+                ).unwrap()),
+                FieldAttribute::Assert(expr) => statement.push(quote_stmt!(cx,
+                    if !$expr {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some($field_str),
+                            message: concat!("assertion ", stringify!($expr), " failed").to_owned(),
+                        }));
+                    }
+                ).unwrap()),
+                FieldAttribute::Align(_) | FieldAttribute::Limit(_) | FieldAttribute::Consume(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) |
+                FieldAttribute::RestorePosition | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) | FieldAttribute::IfEof(_) | FieldAttribute::Checksum(_) | FieldAttribute::Over(_) |
+                FieldAttribute::Bits(_) | FieldAttribute::Through(_) =>
+                    cx.span_err(field.ty.span, "attribute is not supported by `NueDecodeRef`"),
+            }
+        }
+
+        match cond {
+            Some(ref cond) if expr_is_false(cond) => {
+                let default = cond_default.unwrap_or_else(|| quote_expr!(cx, ::std::default::Default::default()));
+                quote_stmt!(cx, let $let_name = $default;).unwrap()
+            },
+            Some(cond) => {
+                let default = cond_default.unwrap_or_else(|| quote_expr!(cx, ::std::default::Default::default()));
+                quote_stmt!(cx,
+                    let $let_name = if $cond {
+                        $statement;
+                        $let_name
+                    } else {
+                        $default
+                    };
+                ).unwrap()
+            },
+            None => quote_stmt!(cx, let $let_name = { $statement; $let_name };).unwrap(),
+        }
+    }).collect()
+}
+
+/// Whether a type borrows with a lifetime anywhere within it.
+fn type_borrows(ty: &ast::Ty) -> bool {
+    match ty.node {
+        ast::TyRptr(..) => true,
+        _ => pprust::ty_to_string(ty).contains('\''),
+    }
+}
+
+/// Whether a type is a `&str`.
+fn type_is_str_ref(ty: &ast::Ty) -> bool {
+    match ty.node {
+        ast::TyRptr(_, ast::MutTy { ref ty, .. }) => type_name_is(ty, "str"),
+        _ => false,
+    }
+}
+
+/// A field of a struct or enum variant.
+struct Field<'a> {
+    /// The local that holds the field's value while decoding, or its reference while encoding a variant
+    let_name: ast::Ident,
+    /// The field's name, or `None` for tuple fields
+    name: Option<ast::Ident>,
+    index: usize,
+    ty: &'a P<ast::Ty>,
+    attrs: &'a [ast::Attribute],
+}
+
+impl<'a> Field<'a> {
+    /// The name used to refer to the field in error messages
+    fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+}
+
+fn struct_fields<'a>(builder: &AstBuilder, fields: &'a [ast::StructField]) -> Vec<Field<'a>> {
+    fields.iter().enumerate().map(|(i, field)| {
+        let field = &field.node;
+        let name = match field.kind {
+            ast::NamedField(name, _) => Some(name),
+            ast::UnnamedField(_) => None,
+        };
+
+        Field {
+            let_name: match name {
+                Some(name) => builder.id(format!("__self_0{}", name)),
+                None => builder.id(format!("__self_0{}", i)),
+            },
+            name: name,
+            index: i,
+            ty: &field.ty,
+            attrs: &field.attrs,
+        }
+    }).collect()
+}
+
+/// The fields of a struct, or of every variant of an enum.
+fn item_fields<'a>(builder: &AstBuilder, item: &'a ast::Item) -> Vec<Field<'a>> {
+    match item.node {
+        ast::ItemStruct(ref struct_def, _) => struct_fields(builder, &struct_def.fields),
+        ast::ItemEnum(ref enum_def, _) => enum_def.variants.iter().flat_map(|variant| variant_fields(builder, &variant.node)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn variant_fields<'a>(builder: &AstBuilder, variant: &'a ast::Variant_) -> Vec<Field<'a>> {
+    match variant.kind {
+        ast::TupleVariantKind(ref args) => args.iter().enumerate().map(|(i, arg)| Field {
+            let_name: builder.id(format!("__self_0{}", i)),
+            name: None,
+            index: i,
+            ty: &arg.ty,
+            attrs: &[],
+        }).collect(),
+        ast::StructVariantKind(ref struct_def) => struct_fields(builder, &struct_def.fields),
+    }
+}
+
+/// Builds a pattern that matches the variant, binding each field by reference to its `let_name`.
+fn variant_pat(span: Span, builder: &AstBuilder, enum_name: ast::Ident, variant: &ast::Variant_, fields: &[Field]) -> P<ast::Pat> {
+    fn pat(span: Span, node: ast::Pat_) -> P<ast::Pat> {
+        P(ast::Pat {
+            id: ast::DUMMY_NODE_ID,
+            node: node,
+            span: span,
+        })
+    }
+
+    let path = builder.path().ids(&[enum_name, variant.name]).build();
+    let ref_pat = |field: &Field| pat(span, ast::PatIdent(ast::BindByRef(ast::MutImmutable), Spanned { node: field.let_name, span: span }, None));
+
+    pat(span, match variant.kind {
+        ast::TupleVariantKind(..) => ast::PatEnum(path, Some(fields.iter().map(&ref_pat).collect())),
+        ast::StructVariantKind(..) => ast::PatStruct(path, fields.iter().map(|field| Spanned {
+            node: ast::FieldPat {
+                ident: field.name.unwrap(),
+                pat: ref_pat(field),
+                is_shorthand: false,
+            },
+            span: span,
+        }).collect(), false),
+    })
+}
+
+/// Builds the expression that constructs `path` from the decoded fields.
+fn construct(builder: &AstBuilder, path: ast::Path, fields: &[Field], unit: bool) -> P<ast::Expr> {
+    if unit {
+        builder.expr().build_path(path)
+    } else if fields.iter().any(|field| field.name.is_none()) {
+        builder.expr().call().build_path(path).with_args(fields.iter().map(|field| builder.expr().id(field.let_name))).build()
+    } else {
+        builder.expr().struct_path(path).with_id_exprs(fields.iter().map(|field| (field.name.unwrap(), builder.expr().id(field.let_name)))).build()
+    }
+}
+
+/// The discriminant of each variant, following the implicit numbering rules of Rust enums.
+///
+/// A `discriminant` attribute overrides the variant's own, and later variants are numbered from it.
+fn discriminants(cx: &mut ExtCtxt, variants: &[P<ast::Variant>], attrs: &[Vec<VariantAttribute>]) -> Vec<P<ast::Expr>> {
+    let mut next = quote_expr!(cx, 0);
+    variants.iter().zip(attrs).map(|(variant, attrs)| {
+        let disc = variant_discriminant(attrs).or_else(|| variant.node.disr_expr.clone()).unwrap_or(next.clone());
+        next = quote_expr!(cx, $disc + 1);
+        disc
+    }).collect()
+}
+
+const TAG_TYPES: &'static [&'static str] = &["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"];
+
+/// The integer type of an enum's `#[repr(..)]`, if any.
+fn enum_repr(item: &ast::Item) -> Option<String> {
+    item.attrs.iter().filter_map(|a| match &a.node.value.node {
+        &MetaItem_::MetaList(ref name, ref items) if *name == "repr" => items.iter().filter_map(|item| match item.node {
+            MetaItem_::MetaWord(ref name) if TAG_TYPES.contains(&&**name) => Some(name.to_string()),
+            _ => None,
+        }).next(),
+        _ => None,
+    }).next()
+}
+
+/// The integer type of an enum's encoded discriminant.
+///
+/// Taken from `#[nue(tag_type = "..")]`, or the enum's `#[repr(..)]`, and otherwise `u8`.
+fn enum_tag_type(builder: &AstBuilder, item: &ast::Item, attrs: &[ItemAttribute]) -> P<ast::Ty> {
+    let mut tag_type = enum_repr(item);
+
+    for attr in attrs {
+        match *attr {
+            ItemAttribute::TagType(ref ty) => tag_type = Some(ty.clone()),
+            _ => (),
+        }
+    }
+
+    builder.ty().id(tag_type.as_ref().map(|ty| &**ty).unwrap_or("u8"))
+}
+
+/// Generates statements that encode each field in turn.
+///
+/// Struct fields are read through `self`, while `bound` variant fields have
+/// been bound by reference to their `let_name`.
+fn encode_fields(cx: &mut ExtCtxt, builder: &AstBuilder, fields: &[Field], defaults: &Defaults, bound: bool, needs_seek: &mut bool) -> Vec<P<ast::Stmt>> {
+    let access = if bound { SelfAccess::refs(fields) } else { SelfAccess::Self_ };
+    let attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_enc", &access)).collect::<Vec<_>>();
+    let checksum = checksum_range(cx, fields, &attrs);
+    let groups = limit_groups(cx, fields, &attrs);
+    let byte_order = defaults.byte_order.as_ref().map(|value| attr_expr(cx, &access, value));
+
+    let mut encoders = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        if let Some(bits) = field_bits(cx, field, &attrs) {
+            let expr = encoded_value(cx, builder, field, &attrs, bound);
+            let bits = builder.expr().u32(bits);
+            let write = if type_is_signed(field.ty) {
+                quote_stmt!(cx, let _ = try!(__bits.write_signed_bits(*($expr) as i64, $bits));).unwrap()
+            } else {
+                quote_stmt!(cx, let _ = try!(__bits.write_bits(*($expr) as u64, $bits));).unwrap()
+            };
+            return (quote_stmt!(cx, {}).unwrap(), Some(write))
+        }
+
+        let field_str = builder.expr().str(&*field.label());
+
+        let mut cond = None;
+
+        let field_type = field.ty;
+        let expr = encoded_value(cx, builder, field, &attrs, bound);
+        let (wrapper, order) = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None }) {
+            Some(_) => (None, None),
+            None => (field_wrapper(cx, &attrs, defaults, field_type), field_byte_order(cx, &attrs, byte_order.as_ref(), field_type)),
+        };
+
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        // Decoding reads at least one element, so there must be a last one
+        let until = find_attr(&attrs, |attr| match *attr { FieldAttribute::Until(ref expr) => Some(expr), _ => None }).map(|until| quote_stmt!(cx, {
+            if ($expr).is_empty() {
+                return Err(::std::convert::From::from(::nue::Error::Validation {
+                    field: Some($field_str),
+                    message: concat!("the last element must satisfy ", stringify!($until)).to_owned(),
+                }));
+            }
+            for (__i, last) in ($expr).iter().enumerate() {
+                if ($until) != (__i + 1 == ($expr).len()) {
+                    return Err(::std::convert::From::from(::nue::Error::Validation {
+                        field: Some($field_str),
+                        message: concat!("only the last element may satisfy ", stringify!($until)).to_owned(),
+                    }));
+                }
+            }
+        }).unwrap());
+        let is_checksum = attrs.iter().any(|attr| match *attr { FieldAttribute::Checksum(..) => true, _ => false });
+        let statement = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None }) {
+            _ if is_checksum => {
+                let encode = encode_value(cx, quote_expr!(cx, &__digest), field_type, wrapper, order);
+                quote_stmt!(cx,
+                    let _ = {
+                        let __digest: $field_type = __w.digest();
+                        try!($encode)
+                    };
+                )
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                let (wrapper, order) = (field_wrapper(cx, &attrs, defaults, &item), field_byte_order(cx, &attrs, byte_order.as_ref(), &item));
+                let encode_item = encode_value(cx, quote_expr!(cx, __item), &item, wrapper.clone(), order);
+                let encode_terminator = encode_value(cx, quote_expr!(cx, &__terminator), &item, wrapper, order);
+                quote_stmt!(cx,
+                    let _ = {
+                        let __terminator: $item = $terminator;
+                        for __item in ($expr).iter() {
+                            if *__item == __terminator {
+                                return Err(::std::convert::From::from(::nue::Error::Validation {
+                                    field: Some($field_str),
+                                    message: "an element equals the terminator".to_owned(),
+                                }));
+                            }
+                            let _ = try!($encode_item);
+                        }
+                        try!($encode_terminator)
+                    };
+                )
+            },
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote_stmt!(cx, let _ = try!($with($expr, __w));)
+            },
+            Some(tag) => quote_stmt!(cx,
+                let _ = try!(::nue::Encode::encode_options($expr, __w, ::nue::TagOptions { tag: Some(($tag) as _) }));
+            ),
+            None => {
+                let encode = encode_value(cx, expr, field_type, wrapper, order);
+                quote_stmt!(cx, let _ = try!($encode);)
+            },
+        }.unwrap();
+        let mut statement = until.into_iter().chain(Some(statement)).collect::<Vec<_>>();
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, encode_magic(cx, &magic));
+        }
+
+        let through = attrs.iter().any(|attr| match *attr { FieldAttribute::Through(_) => true, _ => false });
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) |
+                FieldAttribute::Checksum(_) | FieldAttribute::Over(_) | FieldAttribute::Bits(_) | FieldAttribute::Through(_) | FieldAttribute::Value(_) |
+                FieldAttribute::Varint => (),
+                FieldAttribute::Default(_) | FieldAttribute::RestorePosition | FieldAttribute::IfEof(_) | FieldAttribute::Position => (),
+                // The count is otherwise truncated by `encoded_value`
+                FieldAttribute::CountOf(target, len) => {
+                    let target = builder.expr().str(&*target);
+                    statement.insert(0, quote_stmt!(cx,
+                        if <$field_type as ::std::convert::TryFrom<_>>::try_from($len).is_err() {
+                            return Err(::std::convert::From::from(::nue::Error::Validation {
+                                field: Some($field_str),
+                                message: format!("the length of {} doesn't fit in {}", $target, stringify!($field_type)),
+                            }));
+                        }
+                    ).unwrap())
+                },
+                FieldAttribute::Align(expr) => {
+                    *needs_seek = true;
+                    statement.insert(0, quote_stmt!(cx, let _ = try!(::nue::SeekAlignExt::align_to(__w, $expr)); ).unwrap());
+                },
+                FieldAttribute::Skip(expr) => {
+                    *needs_seek = true;
+                    statement.insert(0, quote_stmt!(cx,
+                        let _ = try!(::nue::SeekForward::seek_forward(__w, $expr));
+                    ).unwrap());
+                },
+                // The limit of a group applies to all of its fields
+                FieldAttribute::Limit(_) if through => (),
+                FieldAttribute::Limit(expr) => statement.insert(0, quote_stmt!(cx, let __w = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__w), $expr); ).unwrap()),
+                FieldAttribute::Consume(expr) => statement.push(quote_stmt!(cx,
+                    if $expr {
+                        let _ = try!(match ::std::io::copy(&mut ::std::io::repeat(0), __w) {
+                            ::std::result::Result::Err(ref err) if err.kind() == ::std::io::ErrorKind::WriteZero => Ok(0),
+                            res => res,
+                        });
+                    }
+                ).unwrap()),
+                FieldAttribute::Assert(expr) => statement.insert(0, quote_stmt!(cx,
+                    if !$expr {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some($field_str),
+                            message: concat!("assertion ", stringify!($expr), " failed").to_owned(),
+                        }));
+                    }
+                ).unwrap()),
+            }
+        }
+
+        let statement = if let Some(cond) = cond {
+            if expr_is_false(&cond) {
+                quote_stmt!(cx, {}).unwrap()
+            } else {
+                quote_stmt!(cx,
+                    if $cond {
+                        $statement
+                    }
+                ).unwrap()
+            }
+        } else {
+            quote_stmt!(cx, { $statement }).unwrap()
+        };
+
+        // Fields of a group each take what remains of its limit
+        let statement = match field_group(&groups, i) {
+            Some(last) => {
+                let remaining = if last { None } else { Some(quote_stmt!(cx, __group_limit = ::nue::Take::limit(__w);).unwrap()) };
+                quote_stmt!(cx, {
+                    let __w = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__w), __group_limit);
+                    $statement
+                    $remaining
+                }).unwrap()
+            },
+            None => statement,
+        };
+
+        (statement, None)
+    }).collect::<Vec<_>>();
+
+    let mut scopes = groups.into_iter().map(|(start, _, limit)| (start, quote_stmt!(cx, let mut __group_limit = ($limit) as u64;).unwrap())).collect::<Vec<_>>();
+    if let Some((start, hasher)) = checksum {
+        *needs_seek = true;
+        scopes.push((start, quote_stmt!(cx, let __w = &mut ::nue::HashWrite::new(__w, $hasher);).unwrap()));
+    }
+    scopes.sort_by(|a, b| b.0.cmp(&a.0));
+    for (start, scope) in scopes {
+        encoders.insert(start, (scope, None));
+    }
+
+    encode_bit_fields(cx, encoders)
+}
+
+/// Replaces each run of adjacent `bits` fields with a single statement that writes
+/// them through a `BitWriter`, padding the final byte with zero bits.
+fn encode_bit_fields(cx: &mut ExtCtxt, fields: Vec<(P<ast::Stmt>, Option<P<ast::Stmt>>)>) -> Vec<P<ast::Stmt>> {
+    fn flush(cx: &mut ExtCtxt, stmts: &mut Vec<P<ast::Stmt>>, run: &mut Vec<P<ast::Stmt>>) {
+        if run.is_empty() {
+            return
+        }
+
+        let writes = run.drain(..).collect::<Vec<_>>();
+        stmts.push(quote_stmt!(cx,
+            {
+                let mut __bits = ::nue::BitWriter::new(&mut *__w);
+                $writes
+                let _ = try!(__bits.align());
+            }
+        ).unwrap());
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, bits) in fields {
+        match bits {
+            Some(bits) => run.push(bits),
+            None => {
+                flush(cx, &mut stmts, &mut run);
+                stmts.push(stmt);
+            },
+        }
+    }
+    flush(cx, &mut stmts, &mut run);
+
+    stmts
+}
+
+/// The width of a `bits` field, which can't be combined with other attributes.
+fn field_bits(cx: &mut ExtCtxt, field: &Field, attrs: &[FieldAttribute]) -> Option<u32> {
+    let bits = attrs.iter().filter_map(|attr| match *attr {
+        FieldAttribute::Bits(bits) => Some(bits),
+        _ => None,
+    }).last();
+
+    if bits.is_some() && attrs.len() > 1 {
+        cx.span_err(field.ty.span, "bits can't be combined with other attributes");
+    }
+
+    bits
+}
+
+/// A reference to the value encoded for a field, after `value`, `count_of` and `unmap`.
+fn encoded_value(cx: &mut ExtCtxt, builder: &AstBuilder, field: &Field, attrs: &[FieldAttribute], bound: bool) -> P<ast::Expr> {
+    let field_type = field.ty;
+    let expr = match find_attr(attrs, |attr| match *attr { FieldAttribute::Value(ref expr) => Some(expr), _ => None }) {
+        Some(value) => quote_expr!(cx, &{ let __value: $field_type = $value; __value }),
+        None if bound => builder.expr().id(field.let_name),
+        None => match field.name {
+            Some(name) => quote_expr!(cx, &self.$name),
+            None => builder.expr().addr_of().tup_field(field.index).build(builder.expr().self_()),
+        },
+    };
+
+    let expr = match find_attr(attrs, |attr| match *attr { FieldAttribute::CountOf(_, ref len) => Some(len), _ => None }) {
+        Some(len) => quote_expr!(cx, &(($len) as $field_type)),
+        None => expr,
+    };
+
+    match find_attr(attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None }) {
+        Some(unmap) => quote_expr!(cx, &(($unmap)($expr))),
+        None => expr,
+    }
+}
+
+/// Generates statements that add the encoded size of each field to `__size`.
+fn size_fields(cx: &mut ExtCtxt, builder: &AstBuilder, fields: &[Field], bound: bool) -> Vec<P<ast::Stmt>> {
+    let access = if bound { SelfAccess::refs(fields) } else { SelfAccess::Self_ };
+
+    let attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_enc", &access)).collect::<Vec<_>>();
+    let groups = limit_groups(cx, fields, &attrs);
+
+    // Each run of `bits` fields is counted once it ends, rounded up to whole bytes
+    let mut bits = 0u64;
+    let mut sizes = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        let run = match field_bits(cx, field, &attrs) {
+            Some(width) => {
+                bits += width as u64;
+                return quote_stmt!(cx, {}).unwrap()
+            },
+            None => ::std::mem::replace(&mut bits, 0),
+        };
+        let expr = encoded_value(cx, builder, field, &attrs, bound);
+        let field_type = field.ty;
+
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        let varint = attrs.iter().any(|attr| match *attr { FieldAttribute::Varint => true, _ => false });
+        let size = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None }) {
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote_expr!(cx, {
+                    let mut __null = ::nue::Null::new();
+                    let _ = $with($expr, &mut __null);
+                    __null.len()
+                })
+            },
+            _ if terminator.is_some() && varint => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                quote_expr!(cx, {
+                    let __terminator: $item = $terminator;
+                    ($expr).iter().map(|__item| ::nue::EncodedSize::encoded_size(&::nue::Varint(*__item))).sum::<u64>() +
+                        ::nue::EncodedSize::encoded_size(&::nue::Varint(__terminator))
+                })
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                quote_expr!(cx, {
+                    let __terminator: $item = $terminator;
+                    ::nue::EncodedSize::encoded_size($expr) + ::nue::EncodedSize::encoded_size(&__terminator)
+                })
+            },
+            Some(tag) => quote_expr!(cx, ::nue::EncodedSize::encoded_size_options($expr, ::nue::TagOptions { tag: Some(($tag) as _) })),
+            None if varint => quote_expr!(cx, ::nue::EncodedSize::encoded_size(&::nue::Varint(*$expr))),
+            None => quote_expr!(cx, ::nue::EncodedSize::encoded_size($expr)),
+        };
+        let mut statement = vec![quote_stmt!(cx, __size += $size;).unwrap()];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, quote_stmt!(cx, __size += ($magic).len() as u64;).unwrap());
+        }
+
+        let mut cond = None;
+        let group = field_group(&groups, i);
+        let through = attrs.iter().any(|attr| match *attr { FieldAttribute::Through(_) => true, _ => false });
+        let limit = !through && attrs.iter().any(|attr| match *attr { FieldAttribute::Limit(_) => true, _ => false });
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Align(expr) => statement.insert(0, quote_stmt!(cx,
+                    __size += (($expr) as u64 - __size % ($expr) as u64) % ($expr) as u64;
+                ).unwrap()),
+                FieldAttribute::Skip(expr) => statement.insert(0, quote_stmt!(cx, __size += ($expr) as u64;).unwrap()),
+                FieldAttribute::Limit(expr) => if limit {
+                    statement.insert(0, quote_stmt!(cx, let __limit = __size + ($expr) as u64;).unwrap())
+                },
+                // Padding is written up to the limit, and without one only stops at the end of the stream
+                FieldAttribute::Consume(expr) => if limit {
+                    statement.push(quote_stmt!(cx, if $expr { __size = __limit; }).unwrap())
+                } else if group.is_some() {
+                    statement.push(quote_stmt!(cx, if $expr { __size = __group_end; }).unwrap())
+                },
+                _ => (),
+            }
+        }
+
+        if limit {
+            statement.push(quote_stmt!(cx, __size = ::std::cmp::min(__size, __limit);).unwrap());
+        }
+
+        let statement = match cond {
+            Some(ref cond) if expr_is_false(cond) => quote_stmt!(cx, {}).unwrap(),
+            Some(cond) => quote_stmt!(cx,
+                if $cond {
+                    $statement
+                }
+            ).unwrap(),
+            None => quote_stmt!(cx, { $statement }).unwrap(),
+        };
+
+        let statement = match group {
+            Some(true) => quote_stmt!(cx, {
+                $statement
+                __size = ::std::cmp::min(__size, __group_end);
+            }).unwrap(),
+            _ => statement,
+        };
+
+        if run > 0 {
+            let run = builder.expr().u64((run + 7) / 8);
+            quote_stmt!(cx, { __size += $run; $statement }).unwrap()
+        } else {
+            statement
+        }
+    }).collect::<Vec<_>>();
+
+    if bits > 0 {
+        let run = builder.expr().u64((bits + 7) / 8);
+        sizes.push(quote_stmt!(cx, __size += $run;).unwrap());
+    }
+
+    for (start, _, limit) in groups.into_iter().rev() {
+        sizes.insert(start, quote_stmt!(cx, let __group_end = __size + ($limit) as u64;).unwrap());
+    }
+
+    sizes
+}
+
+/// Gives fields counted by a `count_of` field a `count` of its decoded value.
+fn count_of_fields(cx: &mut ExtCtxt, builder: &AstBuilder, fields: &[Field], attrs: &mut [Vec<FieldAttribute>]) {
+    let counts = fields.iter().zip(attrs.iter()).flat_map(|(field, attrs)| attrs.iter().filter_map(move |attr| match *attr {
+        FieldAttribute::CountOf(ref target, _) => Some((target.clone(), field.let_name)),
+        _ => None,
+    })).collect::<Vec<_>>();
+    for (target, count) in counts {
+        match fields.iter().position(|field| field.label() == target) {
+            Some(i) => if !attrs[i].iter().any(|attr| match *attr { FieldAttribute::Count(..) => true, _ => false }) {
+                attrs[i].push(FieldAttribute::Count(builder.expr().id(count)))
+            },
+            None => cx.span_err(fields[0].ty.span, &format!("count_of refers to unknown field `{}`", target)),
+        }
+    }
+}
+
+/// Generates statements that decode each field into its `let_name`.
+///
+/// Errors are attributed to the failing field of `type_name`, along with its offset from the start of the value.
+fn decode_fields(cx: &mut ExtCtxt, builder: &AstBuilder, type_name: &str, fields: &[Field], defaults: &Defaults, coalesce: bool, seeking: &mut Seeking) -> Vec<P<ast::Stmt>> {
+    let mut attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_dec", &SelfAccess::Locals)).collect::<Vec<_>>();
+    count_of_fields(cx, builder, fields, &mut attrs);
+
+    let checksum = checksum_range(cx, fields, &attrs);
+    let groups = limit_groups(cx, fields, &attrs);
+    let byte_order = defaults.byte_order.as_ref().map(|value| attr_expr(cx, &SelfAccess::Locals, value));
+    let type_str = builder.expr().str(type_name);
+
+    let mut decoders = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        let let_name = field.let_name;
+        let field_str = builder.expr().str(&*field.label());
+        if let Some(bits) = field_bits(cx, field, &attrs) {
+            return (quote_stmt!(cx, {}).unwrap(), None, Some((let_name, field_str, field.ty.clone(), bits)))
+        }
+
+        let (mut cond, mut cond_default, mut restore, mut if_eof) = (None, None, false, None);
+        let field_type = field.ty;
+
+        let wrapper = field_wrapper(cx, &attrs, defaults, field_type);
+        let order = field_byte_order(cx, &attrs, byte_order.as_ref(), field_type);
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let map = find_attr(&attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        let until = find_attr(&attrs, |attr| match *attr { FieldAttribute::Until(ref expr) => Some(expr), _ => None });
+        let is_checksum = attrs.iter().any(|attr| match *attr { FieldAttribute::Checksum(..) => true, _ => false });
+        let position = attrs.iter().any(|attr| match *attr { FieldAttribute::Position => true, _ => false });
+        let value = match decode_options_expr(cx, &attrs, field_type) {
+            _ if position => quote_expr!(cx, ::nue::Tell::tell(__r).map(|__pos| __pos as $field_type)),
+            _ if until.is_some() => {
+                let (until, item) = (until.unwrap(), vec_item_type(cx, field_type));
+                let (wrapper, order) = (field_wrapper(cx, &attrs, defaults, &item), field_byte_order(cx, &attrs, byte_order.as_ref(), &item));
+                let decode_item = decode_value(cx, &item, wrapper, order);
+                quote_expr!(cx, {
+                    let mut __vec = ::std::vec::Vec::new();
+                    loop {
+                        let __item: $item = try!($decode_item);
+                        __vec.push(__item);
+                        let last = &__vec[__vec.len() - 1];
+                        if $until {
+                            break
+                        }
+                    }
+                    Ok::<_, ::std::io::Error>(__vec)
+                })
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                let (wrapper, order) = (field_wrapper(cx, &attrs, defaults, &item), field_byte_order(cx, &attrs, byte_order.as_ref(), &item));
+                let decode_item = decode_value(cx, &item, wrapper, order);
+                quote_expr!(cx, {
+                    let __terminator: $item = $terminator;
+                    let mut __vec = ::std::vec::Vec::new();
+                    loop {
+                        let __item: $item = try!($decode_item);
+                        if __item == __terminator {
+                            break
+                        }
+                        __vec.push(__item);
+                    }
+                    Ok::<_, ::std::io::Error>(__vec)
+                })
+            },
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote_expr!(cx, $with(__r))
+            },
+            Some(options) => quote_expr!(cx, ::nue::Decode::decode_options(__r, $options)),
+            // The wire type of a mapped field is inferred from its conversion
+            None if map.is_some() => quote_expr!(cx, ::nue::Decode::decode(__r)),
+            None => decode_value(cx, field_type, wrapper.clone(), order),
+        };
+        let statement = match map {
+            Some(map) => quote_stmt!(cx, let $let_name: $field_type = ($map)(try!($value));),
+            None => quote_stmt!(cx, let $let_name: $field_type = try!($value);),
+        }.unwrap();
+        let mut statement = vec![statement];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, decode_magic(cx, &magic));
+        }
+        if is_checksum {
+            let index = statement.len() - 1;
+            statement.insert(index, quote_stmt!(cx, let __digest = __r.digest();).unwrap());
+            statement.push(quote_stmt!(cx,
+                if $let_name != __digest {
+                    return Err(::std::convert::From::from(::nue::Error::Validation {
+                        field: Some($field_str),
+                        message: "checksum mismatch".to_owned(),
+                    }));
+                }
+            ).unwrap());
+        }
+
+        let plain = attrs.iter().all(|attr| match *attr { FieldAttribute::Endian(_) => true, _ => false });
+        let group = field_group(&groups, i);
+        let pod = if coalesce && plain && group.is_none() && order.is_none() && is_pod_type(field_type) {
+            Some((let_name, field_str.clone(), field_type.clone(), wrapper))
+        } else {
+            None
+        };
+
+        let through = attrs.iter().any(|attr| match *attr { FieldAttribute::Through(_) => true, _ => false });
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Default(expr) => cond_default = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::CountOf(..) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) |
+                FieldAttribute::Checksum(_) | FieldAttribute::Over(_) | FieldAttribute::Position | FieldAttribute::Bits(_) | FieldAttribute::Through(_) |
+                FieldAttribute::Value(_) | FieldAttribute::Varint => (),
+                FieldAttribute::RestorePosition => {
+                    seeking.require(Seeking::Rewind);
+                    restore = true;
+                },
+                FieldAttribute::IfEof(expr) => {
+                    seeking.require(Seeking::Rewind);
+                    if_eof = Some(expr);
+                },
+                FieldAttribute::Align(expr) => {
+                    seeking.require(Seeking::Forward);
+                    statement.insert(0, quote_stmt!(cx, let _ = try!(::nue::SeekAlignExt::align_to(__r, $expr)); ).unwrap());
+                },
+                FieldAttribute::Skip(expr) => {
+                    seeking.require(Seeking::Forward);
+                    statement.insert(0, quote_stmt!(cx,
+                        let _ = try!(::nue::SeekForward::seek_forward(__r, $expr));
+                    ).unwrap());
+                },
+                // The limit of a group applies to all of its fields
+                FieldAttribute::Limit(_) if through => (),
+                FieldAttribute::Limit(expr) => statement.insert(0, quote_stmt!(cx, let __r = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__r), $expr); ).unwrap()),
+                FieldAttribute::Consume(expr) => statement.push(quote_stmt!(cx,
+                    if $expr {
+                        let _ = try!(::std::io::copy(__r, &mut ::std::io::sink()));
+                    }
+                ).unwrap()),
+                FieldAttribute::Assert(expr) => statement.push(quote_stmt!(cx,
+                    if !$expr {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some($field_str),
+                            message: concat!("assertion ", stringify!($expr), " failed").to_owned(),
+                        }));
+                    }
+                ).unwrap()),
+            }
+        }
+
+        let statement = if let Some(cond) = cond {
+            let default = cond_default.unwrap_or_else(|| quote_expr!(cx, ::std::default::Default::default()));
+
+            if expr_is_false(&cond) {
+                quote_stmt!(cx, let $let_name = $default;).unwrap()
+            } else {
+                quote_stmt!(cx,
+                    let $let_name = if $cond {
+                        $statement;
+                        $let_name
+                    } else {
+                        $default
+                    };
+                ).unwrap()
+            }
+        } else {
+            quote_stmt!(cx, let $let_name = { $statement; $let_name };).unwrap()
+        };
+
+        let statement = if restore {
+            quote_stmt!(cx,
+                let $let_name = {
+                    let __restore = try!(::nue::Tell::tell(__r));
+                    $statement;
+                    let _ = try!(::nue::SeekAbsolute::seek_absolute(__r, __restore));
+                    $let_name
+                };
+            ).unwrap()
+        } else {
+            statement
+        };
+
+        let statement = match if_eof {
+            Some(default) => quote_stmt!(cx,
+                let $let_name = {
+                    let __pos = try!(::nue::Tell::tell(__r));
+                    if try!(::nue::ReadExactExt::read_exact_eof(__r, &mut [0u8; 1])) == 0 {
+                        $default
+                    } else {
+                        let _ = try!(::nue::SeekAbsolute::seek_absolute(__r, __pos));
+                        $statement;
+                        $let_name
+                    }
+                };
+            ).unwrap(),
+            None => statement,
+        };
+
+        let statement = quote_stmt!(cx,
+            let $let_name = {
+                let __offset = ::nue::Tell::tell(__r).ok();
+                let __result = (|| -> ::std::io::Result<$field_type> { $statement; Ok($let_name) })();
+                match __result {
+                    Ok(__value) => __value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field($type_str, $field_str, __offset))),
+                }
+            };
+        ).unwrap();
+
+        // Fields of a group each take what remains of its limit
+        let statement = match group {
+            Some(last) => {
+                let remaining = if last { None } else { Some(quote_stmt!(cx, __group_limit = ::nue::Take::limit(__r);).unwrap()) };
+                quote_stmt!(cx,
+                    let $let_name = {
+                        let __r = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__r), __group_limit);
+                        $statement
+                        $remaining
+                        $let_name
+                    };
+                ).unwrap()
+            },
+            None => statement,
+        };
+
+        (statement, pod, None)
+    }).collect::<Vec<_>>();
+
+    let mut scopes = groups.into_iter().map(|(start, _, limit)| (start, quote_stmt!(cx, let mut __group_limit = ($limit) as u64;).unwrap())).collect::<Vec<_>>();
+    if let Some((start, hasher)) = checksum {
+        seeking.require(Seeking::Forward);
+        scopes.push((start, quote_stmt!(cx, let __r = &mut ::nue::HashRead::new(__r, $hasher);).unwrap()));
+    }
+    scopes.sort_by(|a, b| b.0.cmp(&a.0));
+    for (start, scope) in scopes {
+        decoders.insert(start, (scope, None, None));
+    }
+
+    let decoders = decode_bit_fields(cx, builder, &type_str, decoders);
+    coalesce_pod_fields(cx, &type_str, decoders)
+}
+
+/// Replaces each run of adjacent `bits` fields with a single statement that reads
+/// them through a `BitReader`, skipping any bits left over in the final byte.
+fn decode_bit_fields(cx: &mut ExtCtxt, builder: &AstBuilder, type_str: &P<ast::Expr>,
+    fields: Vec<(P<ast::Stmt>, Option<(ast::Ident, P<ast::Expr>, P<ast::Ty>, Option<P<ast::Ty>>)>, Option<(ast::Ident, P<ast::Expr>, P<ast::Ty>, u32)>)>) ->
+    Vec<(P<ast::Stmt>, Option<(ast::Ident, P<ast::Expr>, P<ast::Ty>, Option<P<ast::Ty>>)>)> {
+    fn flush(cx: &mut ExtCtxt, builder: &AstBuilder, type_str: &P<ast::Expr>,
+        stmts: &mut Vec<(P<ast::Stmt>, Option<(ast::Ident, P<ast::Expr>, P<ast::Ty>, Option<P<ast::Ty>>)>)>, run: &mut Vec<(ast::Ident, P<ast::Expr>, P<ast::Ty>, u32)>) {
+        if run.is_empty() {
+            return
+        }
+
+        let mut offset = 0;
+        let reads = run.iter().map(|&(let_name, ref field_str, ref ty, bits)| {
+            let value = if type_name_is(ty, "bool") {
+                quote_expr!(cx, __value != 0)
+            } else {
+                quote_expr!(cx, __value as $ty)
+            };
+            let read = if type_is_signed(ty) { builder.id("read_signed_bits") } else { builder.id("read_bits") };
+            let byte = builder.expr().u64(offset / 8);
+            let bits_expr = builder.expr().u32(bits);
+            offset += bits as u64;
+
+            quote_stmt!(cx,
+                let $let_name: $ty = match __bits.$read($bits_expr) {
+                    Ok(__value) => $value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field($type_str, $field_str, __offset.map(|__offset| __offset + $byte)))),
+                };
+            ).unwrap()
+        }).collect::<Vec<_>>();
+        let lets = run.drain(..).map(|(let_name, _, _, _)| let_name).collect::<Vec<_>>();
+        let pat = builder.pat().tuple().with_pats(lets.iter().map(|&let_name| builder.pat().id(let_name))).build();
+        let tuple = builder.expr().tuple().with_exprs(lets.iter().map(|&let_name| builder.expr().id(let_name))).build();
+
+        stmts.push((quote_stmt!(cx,
+            let $pat = {
+                let __offset = ::nue::Tell::tell(__r).ok();
+                let mut __bits = ::nue::BitReader::new(&mut *__r);
+                $reads
+                $tuple
+            };
+        ).unwrap(), None));
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, pod, bits) in fields {
+        match bits {
+            Some(bits) => run.push(bits),
+            None => {
+                flush(cx, builder, type_str, &mut stmts, &mut run);
+                stmts.push((stmt, pod));
+            },
+        }
+    }
+    flush(cx, builder, type_str, &mut stmts, &mut run);
+
+    stmts
+}
+
+/// Whether the last segment of a path type is `name`.
+fn type_name_is(ty: &ast::Ty, name: &str) -> bool {
+    match ty.node {
+        ast::TyPath(None, ref path) => path.segments.last().map(|segment| *segment.identifier.name.as_str() == *name).unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Whether a type is a primitive signed integer, whose bit fields are sign extended.
+fn type_is_signed(ty: &ast::Ty) -> bool {
+    ["i8", "i16", "i32", "i64", "isize"].iter().any(|name| type_name_is(ty, name))
+}
+
+/// The element type of a `Vec<T>` field.
+fn vec_item_type(cx: &mut ExtCtxt, ty: &P<ast::Ty>) -> P<ast::Ty> {
+    let item = match ty.node {
+        ast::TyPath(None, ref path) => match path.segments.last() {
+            Some(&ast::PathSegment { ref identifier, parameters: ast::AngleBracketedParameters(ref data) }) if *identifier.name.as_str() == *"Vec" =>
+                data.types.first().cloned(),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    item.unwrap_or_else(|| {
+        cx.span_err(ty.span, "expected a `Vec<T>` field");
+        quote_ty!(cx, ())
+    })
+}
+
+/// Whether a field type is syntactically known to be `Pod`, and so decodes as its raw bytes.
+fn is_pod_type(ty: &ast::Ty) -> bool {
+    match ty.node {
+        ast::TyPath(None, ref path) => match path.segments.last() {
+            Some(segment) => match &*segment.identifier.name.as_str() {
+                "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128" |
+                "usize" | "isize" | "f32" | "f64" | "Le" | "Be" | "Native" => true,
+                _ => false,
+            },
+            None => false,
+        },
+        ast::TyFixedLengthVec(ref ty, _) | ast::TyParen(ref ty) => is_pod_type(ty),
+        _ => false,
+    }
+}
+
+/// The seeking that decoding requires of the stream, from least to most capable.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+enum Seeking {
+    None,
+    Forward,
+    /// Seeking backward, through a buffer of what has been read
+    Rewind,
+}
+
+impl Seeking {
+    fn require(&mut self, seeking: Seeking) {
+        if seeking > *self {
+            *self = seeking
+        }
+    }
+}
+
+/// Item-level settings that apply to every field.
+struct Defaults {
+    /// The endian wrapper used to code primitive fields
+    endian: Option<&'static str>,
+    /// The `byte_order` expression, rewritten for the fields' access to `self` by each derive
+    byte_order: Option<String>,
+}
+
+impl Defaults {
+    fn new(attrs: &[ItemAttribute]) -> Self {
+        let mut defaults = Defaults {
+            endian: None,
+            byte_order: None,
+        };
+
+        for attr in attrs {
+            match *attr {
+                ItemAttribute::Endian(endian) => defaults.endian = Some(endian),
+                ItemAttribute::ByteOrder(ref value) => defaults.byte_order = Some(value.clone()),
+                _ => (),
+            }
+        }
+
+        defaults
+    }
+}
+
+/// Parses a byte order into the name of its endian wrapper type.
+fn endian_name(value: &str) -> Option<&'static str> {
+    match value {
+        "little" | "le" => Some("Le"),
+        "big" | "be" => Some("Be"),
+        "native" => Some("Native"),
+        _ => None,
+    }
+}
+
+/// The endian wrapper for a primitive type coded in the given byte order, if it needs one.
+fn endian_wrapper(cx: &mut ExtCtxt, endian: Option<&'static str>, ty: &P<ast::Ty>) -> Option<P<ast::Ty>> {
+    const PRIMITIVES: &'static [&'static str] = &["u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "f32", "f64"];
+
+    match endian {
+        Some(endian) if PRIMITIVES.iter().any(|name| type_name_is(ty, name)) => {
+            let endian = AstBuilder::new().id(endian);
+            Some(quote_ty!(cx, ::nue::$endian<$ty>))
+        },
+        _ => None,
+    }
+}
+
+/// An expression that encodes the referenced value to `__w`, through an endian wrapper if provided,
+/// or in the byte order selected by `order` at runtime.
+fn encode_value(cx: &mut ExtCtxt, value: P<ast::Expr>, ty: &P<ast::Ty>, wrapper: Option<P<ast::Ty>>, order: Option<&P<ast::Expr>>) -> P<ast::Expr> {
+    if let Some(order) = order {
+        let (little, big) = (endian_wrapper(cx, Some("Le"), ty), endian_wrapper(cx, Some("Be"), ty));
+        let little = encode_value(cx, value.clone(), ty, little, None);
+        let big = encode_value(cx, value, ty, big, None);
+        return byte_order_match(cx, order, little, big)
+    }
+
+    match wrapper {
+        Some(wrapper) => quote_expr!(cx, ::nue::Encode::encode(&<$wrapper as ::std::convert::From<$ty>>::from(*$value), __w)),
+        None => quote_expr!(cx, ::nue::Encode::encode($value, __w)),
+    }
+}
+
+/// An expression that decodes a value from `__r`, through an endian wrapper if provided,
+/// or in the byte order selected by `order` at runtime.
+fn decode_value(cx: &mut ExtCtxt, ty: &P<ast::Ty>, wrapper: Option<P<ast::Ty>>, order: Option<&P<ast::Expr>>) -> P<ast::Expr> {
+    if let Some(order) = order {
+        let (little, big) = (endian_wrapper(cx, Some("Le"), ty), endian_wrapper(cx, Some("Be"), ty));
+        let little = decode_value(cx, ty, little, None);
+        let big = decode_value(cx, ty, big, None);
+        return byte_order_match(cx, order, little, big)
+    }
+
+    match wrapper {
+        Some(wrapper) => quote_expr!(cx, <$wrapper as ::nue::Decode>::decode(__r).map(|__value| __value.get())),
+        None => quote_expr!(cx, ::nue::Decode::decode(__r)),
+    }
+}
+
+/// Selects between the little and big endian forms of an expression by a runtime `Endianness`.
+fn byte_order_match(cx: &mut ExtCtxt, order: &P<ast::Expr>, little: P<ast::Expr>, big: P<ast::Expr>) -> P<ast::Expr> {
+    quote_expr!(cx, match $order {
+        ::nue::Endianness::Little => $little,
+        ::nue::Endianness::Big => $big,
+    })
+}
+
+/// Replaces each run of adjacent plain `Pod` fields with a single `read_exact`
+/// into a stack buffer that is then split per field.
+///
+/// Fields coded in a specific byte order are copied out as their endian wrapper type.
+/// A short read is attributed to the field that the stream ended in.
+fn coalesce_pod_fields(cx: &mut ExtCtxt, type_str: &P<ast::Expr>, fields: Vec<(P<ast::Stmt>, Option<(ast::Ident, P<ast::Expr>, P<ast::Ty>, Option<P<ast::Ty>>)>)>) -> Vec<P<ast::Stmt>> {
+    fn flush(cx: &mut ExtCtxt, type_str: &P<ast::Expr>, stmts: &mut Vec<P<ast::Stmt>>, run: &mut Vec<(P<ast::Stmt>, (ast::Ident, P<ast::Expr>, P<ast::Ty>, Option<P<ast::Ty>>))>) {
+        if run.len() < 2 {
+            stmts.extend(run.drain(..).map(|(stmt, _)| stmt));
+            return
+        }
+
+        let first_str = (run[0].1).1.clone();
+        let mut offset = quote_expr!(cx, 0);
+        let mut fields = Vec::new();
+        let mut ranges = Vec::new();
+        for (_, (let_name, field_str, ty, wrapper)) in run.drain(..) {
+            let end = quote_expr!(cx, $offset + ::std::mem::size_of::<$ty>());
+            ranges.push((field_str, offset.clone(), end.clone()));
+            fields.push(match wrapper {
+                Some(wrapper) => quote_stmt!(cx,
+                    let $let_name: $ty = <$wrapper as ::nue::Pod>::copy_from(&__pod_buf[$offset..$end]).get();
+                ),
+                None => quote_stmt!(cx,
+                    let $let_name: $ty = ::nue::Pod::copy_from(&__pod_buf[$offset..$end]);
+                ),
+            }.unwrap());
+            offset = end;
+        }
+
+        let (last_str, last_start, _) = ranges.pop().unwrap();
+        let short_field = ranges.into_iter().rev().fold(quote_expr!(cx, ($last_str, $last_start)), |rest, (field_str, start, end)| quote_expr!(cx,
+            if __read < $end { ($field_str, $start) } else { $rest }
+        ));
+
+        stmts.push(quote_stmt!(cx, let mut __pod_buf = [0u8; $offset];).unwrap());
+        stmts.push(quote_stmt!(cx, let __offset = ::nue::Tell::tell(__r).ok();).unwrap());
+        stmts.push(quote_stmt!(cx,
+            let __read = match ::nue::ReadExactExt::read_exact_eof(__r, &mut __pod_buf) {
+                Ok(__read) => __read,
+                Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field($type_str, $first_str, __offset))),
+            };
+        ).unwrap());
+        stmts.push(quote_stmt!(cx,
+            if __read < __pod_buf.len() {
+                let (__field, __start) = $short_field;
+                let __err = ::nue::Error::UnexpectedEof { needed: Some((__pod_buf.len() - __read) as u64) };
+                return Err(::std::convert::From::from(__err.in_field($type_str, __field, __offset.map(|__offset| __offset + __start as u64))));
+            }
+        ).unwrap());
+        stmts.extend(fields);
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, pod) in fields {
+        match pod {
+            Some(pod) => run.push((stmt, pod)),
+            None => {
+                flush(cx, type_str, &mut stmts, &mut run);
+                stmts.push(stmt);
+            },
+        }
+    }
+    flush(cx, type_str, &mut stmts, &mut run);
+
+    stmts
+}
+
+/// How `self.field` in attribute expressions is rewritten.
+enum SelfAccess {
+    /// Left as is, for struct fields read through `self`
+    Self_,
+    /// Replaced with the local holding the decoded field
+    Locals,
+    /// Replaced with a dereference of the local bound to each of the named variant fields
+    Refs(Vec<String>),
+}
+
+impl SelfAccess {
+    fn refs(fields: &[Field]) -> Self {
+        let mut labels = fields.iter().map(|field| field.label()).collect::<Vec<_>>();
+        // Longer names first, so `self.ab` isn't rewritten as `self.a` followed by `b`
+        labels.sort_by(|a, b| b.len().cmp(&a.len()));
+        SelfAccess::Refs(labels)
+    }
+}
+
+/// Parses an attribute expression, rewriting its accesses to `self` as described by `access`.
+fn attr_expr(cx: &mut ExtCtxt, access: &SelfAccess, value: &str) -> P<ast::Expr> {
+    let value = match *access {
+        SelfAccess::Self_ => value.into(),
+        SelfAccess::Locals => value.replace("self.", "__self_0"),
+        SelfAccess::Refs(ref labels) => labels.iter().fold(value.to_owned(), |value, label|
+            value.replace(&format!("self.{}", label), &format!("(*__self_0{})", label))
+        ),
+    };
+    cx.parse_expr(value)
+}
+
+fn field_attrs(cx: &mut ExtCtxt, attrs: &[ast::Attribute], meta_name: &'static str, access: &SelfAccess) -> Vec<FieldAttribute> {
+    let attr = attrs.iter().filter_map(|v| match &v.node.value.node {
+        &MetaItem_::MetaList(ref name, ref attrs) if *name == meta_name || *name == "nue" => {
+            attr::mark_used(v);
+
+            Some(attrs)
+        },
+        _ => None,
+    });
+
+
+    let mut attrs = Vec::new();
+    let mut since = None;
+    for attr in attr {
+        for attr in attr.iter() {
+            match &attr.node {
+                &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. } ) => match &**name {
+                    "assert" => attrs.push(FieldAttribute::Assert(attr_expr(cx, access, &value))),
+                    "align" => attrs.push(FieldAttribute::Align(attr_expr(cx, access, &value))),
+                    "skip" => attrs.push(FieldAttribute::Skip(attr_expr(cx, access, &value))),
+                    "limit" => attrs.push(FieldAttribute::Limit(attr_expr(cx, access, &value))),
+                    "cond" => attrs.push(FieldAttribute::Cond(attr_expr(cx, access, &value))),
+                    "since" => since = Some(attr_expr(cx, access, &value)),
+                    "default" => attrs.push(FieldAttribute::Default(attr_expr(cx, access, &value))),
+                    "value" => attrs.push(FieldAttribute::Value(attr_expr(cx, access, &value))),
+                    "ignore" => {
+                        attrs.push(FieldAttribute::Cond(quote_expr!(cx, false)));
+                        attrs.push(FieldAttribute::Default(attr_expr(cx, access, &value)));
+                    },
+                    "consume" => attrs.push(FieldAttribute::Consume(attr_expr(cx, access, &value))),
+                    "tag" => attrs.push(FieldAttribute::Tag(attr_expr(cx, access, &value))),
+                    "magic" => attrs.push(FieldAttribute::Magic(cx.parse_expr(value.to_string()))),
+                    "with" => {
+                        let function = if meta_name == "nue_enc" { "encode" } else { "decode" };
+                        attrs.push(FieldAttribute::With(cx.parse_expr(format!("{}::{}", value, function))))
+                    },
+                    "map" => if meta_name != "nue_enc" {
+                        attrs.push(FieldAttribute::Map(attr_expr(cx, access, &value)))
+                    },
+                    "unmap" => if meta_name == "nue_enc" {
+                        attrs.push(FieldAttribute::Map(attr_expr(cx, access, &value)))
+                    },
+                    "endian" => match endian_name(value) {
+                        Some(endian) => attrs.push(FieldAttribute::Endian(endian)),
+                        None => {
+                            cx.span_err(attr.span, "endian must be \"little\", \"big\", or \"native\"");
+                            break
+                        },
+                    },
+                    "count" => attrs.push(FieldAttribute::Count(attr_expr(cx, access, &value))),
+                    "terminator" => attrs.push(FieldAttribute::Terminator(attr_expr(cx, access, &value))),
+                    "until" => attrs.push(FieldAttribute::Until(attr_expr(cx, access, &value))),
+                    "if_eof" => attrs.push(FieldAttribute::IfEof(attr_expr(cx, access, &value))),
+                    "checksum" => attrs.push(FieldAttribute::Checksum(match &**value {
+                        "crc32" => quote_expr!(cx, ::nue::Crc32::new()),
+                        ty => cx.parse_expr(format!("<{} as ::std::default::Default>::default()", ty)),
+                    })),
+                    "over" => attrs.push(FieldAttribute::Over(value.to_string())),
+                    "through" => attrs.push(FieldAttribute::Through(value.to_string())),
+                    "bits" => match bit_width(value.parse().ok()) {
+                        Some(bits) => attrs.push(FieldAttribute::Bits(bits)),
+                        None => {
+                            cx.span_err(attr.span, "bits must be a width from 1 to 64");
+                            break
+                        },
+                    },
+                    "count_of" => attrs.push(FieldAttribute::CountOf(value.to_string(), attr_expr(cx, access, &format!("self.{}.len()", value)))),
+                    _ => {
+                        cx.span_err(attr.span, "invalid attribute key");
+                        break
+                    },
+                },
+                &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitInt(value, _), .. }) if *name == "bits" => match bit_width(if value <= 64 { Some(value as u32) } else { None }) {
+                    Some(bits) => attrs.push(FieldAttribute::Bits(bits)),
+                    None => {
+                        cx.span_err(attr.span, "bits must be a width from 1 to 64");
+                        break
+                    },
+                },
+                &MetaItem_::MetaWord(ref name) => match &**name {
+                    "restore_position" => attrs.push(FieldAttribute::RestorePosition),
+                    "varint" => attrs.push(FieldAttribute::Varint),
+                    // A peeked field's bytes belong to the fields that follow it
+                    "peek" => attrs.push(if meta_name == "nue_enc" {
+                        FieldAttribute::Cond(quote_expr!(cx, false))
+                    } else {
+                        FieldAttribute::RestorePosition
+                    }),
+                    "ignore" => attrs.push(FieldAttribute::Cond(quote_expr!(cx, false))),
+                    // The position is only read when decoding
+                    "position" => attrs.push(if meta_name == "nue_enc" {
+                        FieldAttribute::Cond(quote_expr!(cx, false))
+                    } else {
+                        FieldAttribute::Position
+                    }),
+                    // Read by `derive_type` through `skips_bound`
+                    "skip_bound" => (),
+                    _ => {
+                        cx.span_err(attr.span, "invalid attribute key");
+                        break
+                    },
+                },
+                _ => {
+                    cx.span_err(attr.span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+
+    // A version predicate must hold alongside any `cond`
+    if let Some(since) = since {
+        match attrs.iter().rposition(|attr| match *attr { FieldAttribute::Cond(_) => true, _ => false }) {
+            Some(i) => {
+                let cond = match attrs[i] { FieldAttribute::Cond(ref cond) => cond.clone(), _ => unreachable!() };
+                attrs[i] = FieldAttribute::Cond(quote_expr!(cx, ($cond) && ($since)));
+            },
+            None => attrs.push(FieldAttribute::Cond(since)),
+        }
+    }
+
+    attrs
+}
+
+/// A valid `bits` width.
+fn bit_width(bits: Option<u32>) -> Option<u32> {
+    bits.and_then(|bits| if bits >= 1 && bits <= 64 { Some(bits) } else { None })
+}
+
+/// The where predicates of the last `bound` attribute, if any.
+fn item_bound(cx: &mut ExtCtxt, attrs: &[ast::Attribute], meta_name: &'static str) -> Option<Vec<ast::WherePredicate>> {
+    let bound = attrs.iter().filter_map(|v| match &v.node.value.node {
+        &MetaItem_::MetaList(ref name, ref attrs) if *name == meta_name || *name == "nue" => Some(attrs),
+        _ => None,
+    }).flat_map(|attrs| attrs.iter()).filter_map(|attr| match &attr.node {
+        &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. }) if *name == "bound" => Some(value.to_string()),
+        _ => None,
+    }).last();
+
+    bound.map(|bound| parse_where(cx, &bound))
+}
+
+/// Parses the predicates of a where clause.
+fn parse_where(cx: &mut ExtCtxt, predicates: &str) -> Vec<ast::WherePredicate> {
+    if predicates.trim().is_empty() {
+        return Vec::new()
+    }
+
+    // Parsed as the where clause of a unit struct, which is then discarded
+    match cx.parse_item(format!("struct __NueBound where {};", predicates)).node {
+        ast::ItemStruct(_, ref generics) => generics.where_clause.predicates.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Whether a field is marked with `skip_bound` or `ignore`.
+fn skips_bound(attrs: &[ast::Attribute]) -> bool {
+    attrs.iter().filter_map(|v| match &v.node.value.node {
+        &MetaItem_::MetaList(ref name, ref attrs) if *name == "nue" || *name == "nue_enc" || *name == "nue_dec" => Some(attrs),
+        _ => None,
+    }).flat_map(|attrs| attrs.iter()).any(|attr| match attr.node {
+        MetaItem_::MetaWord(ref name) => *name == "skip_bound" || *name == "ignore",
+        MetaItem_::MetaNameValue(ref name, _) => *name == "ignore",
+        _ => false,
+    })
+}
+
+/// Whether a type refers to the named type parameter anywhere within it.
+fn type_mentions(ty: &ast::Ty, ident: ast::Ident) -> bool {
+    let name = ident.name.as_str();
+    pprust::ty_to_string(ty).split(|c: char| !(c.is_alphanumeric() || c == '_')).any(|word| word == &*name)
+}
+
+/// Whether a type depends on any of the parameters of `generics`.
+fn type_is_generic(ty: &ast::Ty, generics: &ast::Generics) -> bool {
+    let ty_str = pprust::ty_to_string(ty);
+    generics.ty_params.iter().any(|param| type_mentions(ty, param.ident)) ||
+        generics.lifetimes.iter().any(|def| ty_str.contains(&*def.lifetime.name.as_str()))
+}
+
+fn item_attrs(cx: &mut ExtCtxt, attrs: &[ast::Attribute], meta_name: &'static str) -> Vec<ItemAttribute> {
+    let attr = attrs.iter().filter_map(|v| match &v.node.value.node {
+        &MetaItem_::MetaList(ref name, ref attrs) if *name == meta_name || *name == "nue" => {
+            attr::mark_used(v);
+
+            Some(attrs)
+        },
+        _ => None,
+    });
+
+    let mut attrs = Vec::new();
+    for attr in attr {
+        for attr in attr.iter() {
+            match &attr.node {
+                &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. } ) => match &**name {
+                    "magic" => attrs.push(ItemAttribute::Magic(cx.parse_expr(value.to_string()))),
+                    "pad_to" => attrs.push(ItemAttribute::PadTo(cx.parse_expr(value.to_string()))),
+                    "before_encode" => attrs.push(ItemAttribute::BeforeEncode(cx.parse_expr(value.to_string()))),
+                    "after_decode" => attrs.push(ItemAttribute::AfterDecode(cx.parse_expr(value.to_string()))),
+                    // Parsed by each derive once it knows how fields are accessed
+                    "byte_order" => attrs.push(ItemAttribute::ByteOrder(value.to_string())),
+                    "endian" => match endian_name(value) {
+                        Some(endian) => attrs.push(ItemAttribute::Endian(endian)),
+                        None => {
+                            cx.span_err(attr.span, "endian must be \"little\", \"big\", or \"native\"");
+                            break
+                        },
+                    },
+                    // Read by `derive_type` through `item_bound`
+                    "bound" => (),
+                    "option" => match value.find(':') {
+                        Some(i) => attrs.push(ItemAttribute::Option(value[..i].trim().to_string(), value[i + 1..].trim().to_string())),
+                        None => {
+                            cx.span_err(attr.span, "option must be of the form \"name: Type\"");
+                            break
+                        },
+                    },
+                    "tag_type" => if TAG_TYPES.contains(&&**value) {
+                        attrs.push(ItemAttribute::TagType(value.to_string()))
+                    } else {
+                        cx.span_err(attr.span, "tag_type must be a primitive integer type");
+                        break
+                    },
+                    _ => {
+                        cx.span_err(attr.span, "invalid attribute key");
+                        break
+                    },
+                },
+                _ => {
+                    cx.span_err(attr.span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+    attrs
+}
+
+fn variant_attrs(cx: &mut ExtCtxt, attrs: &[ast::Attribute], meta_name: &'static str) -> Vec<VariantAttribute> {
+    let attr = attrs.iter().filter_map(|v| match &v.node.value.node {
+        &MetaItem_::MetaList(ref name, ref attrs) if *name == meta_name || *name == "nue" => {
+            attr::mark_used(v);
+
+            Some(attrs)
+        },
+        _ => None,
+    });
+
+    let mut attrs = Vec::new();
+    for attr in attr {
+        for attr in attr.iter() {
+            match &attr.node {
+                &MetaItem_::MetaNameValue(ref name, Spanned { node: Lit_::LitStr(ref value, _), .. } ) => match &**name {
+                    "discriminant" => attrs.push(VariantAttribute::Discriminant(cx.parse_expr(value.to_string()))),
+                    "align" => attrs.push(VariantAttribute::Align(cx.parse_expr(value.to_string()))),
+                    "cond" => attrs.push(VariantAttribute::Cond(cx.parse_expr(value.to_string()))),
+                    _ => {
+                        cx.span_err(attr.span, "invalid attribute key");
+                        break
+                    },
+                },
+                &MetaItem_::MetaWord(ref name) if *name == "other" => attrs.push(VariantAttribute::Other),
+                _ => {
+                    cx.span_err(attr.span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+    attrs
+}
+
+enum VariantAttribute {
+    /// The encoded discriminant, in place of the variant's own
+    Discriminant(P<ast::Expr>),
+    /// The alignment of the payload following the discriminant
+    Align(P<ast::Expr>),
+    /// Whether the payload is coded at all
+    Cond(P<ast::Expr>),
+    /// Holds any unknown discriminant in its first field when decoding
+    Other,
+}
+
+/// The index of the `other` variant, if any.
+fn other_variant(cx: &mut ExtCtxt, variants: &[P<ast::Variant>], attrs: &[Vec<VariantAttribute>]) -> Option<usize> {
+    let others = attrs.iter().enumerate().filter(|&(_, attrs)| attrs.iter().any(|attr| match *attr {
+        VariantAttribute::Other => true,
+        _ => false,
+    })).map(|(i, _)| i).collect::<Vec<_>>();
+
+    for &i in others.iter().skip(1) {
+        cx.span_err(variants[i].span, "only one variant can be `other`");
+    }
+
+    match others.first() {
+        Some(&i) if variant_is_unit(&variants[i].node) => {
+            cx.span_err(variants[i].span, "the `other` variant must hold the discriminant in its first field");
+            None
+        },
+        other => other.cloned(),
+    }
+}
+
+fn variant_is_unit(variant: &ast::Variant_) -> bool {
+    match variant.kind {
+        ast::TupleVariantKind(ref args) => args.is_empty(),
+        ast::StructVariantKind(ref def) => def.fields.is_empty(),
+    }
+}
+
+fn variant_discriminant(attrs: &[VariantAttribute]) -> Option<P<ast::Expr>> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Discriminant(ref expr) => Some(expr.clone()), _ => None }).last()
+}
+
+fn variant_align(attrs: &[VariantAttribute]) -> Option<P<ast::Expr>> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Align(ref expr) => Some(expr.clone()), _ => None }).last()
+}
+
+fn variant_cond(attrs: &[VariantAttribute]) -> Option<P<ast::Expr>> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Cond(ref expr) => Some(expr.clone()), _ => None }).last()
+}
+
+enum ItemAttribute {
+    TagType(String),
+    Magic(P<ast::Expr>),
+    Endian(&'static str),
+    /// An expression selecting the `Endianness` of primitive fields at runtime
+    ByteOrder(String),
+    /// The name and type of a field of the generated options struct
+    Option(String, String),
+    /// The size that the encoding is padded to
+    PadTo(P<ast::Expr>),
+    /// A function called with `&self` before encoding
+    BeforeEncode(P<ast::Expr>),
+    /// A function called with `&mut` the decoded value before it is validated
+    AfterDecode(P<ast::Expr>),
+}
+
+/// The `pad_to` size of an item, if any.
+fn item_pad_to(attrs: &[ItemAttribute]) -> Option<P<ast::Expr>> {
+    attrs.iter().filter_map(|attr| match *attr {
+        ItemAttribute::PadTo(ref pad) => Some(pad.clone()),
+        _ => None,
+    }).last()
+}
+
+/// Generates the options struct named after the item and `suffix` from its `option` attributes.
+///
+/// Returns `None` if there are none, so the item keeps `()` as its options.
+fn options_struct(cx: &mut ExtCtxt, builder: &AstBuilder, item: &ast::Item, attrs: &[ItemAttribute], suffix: &str) -> Option<(P<ast::Item>, P<ast::Ty>)> {
+    let fields = attrs.iter().filter_map(|attr| match *attr {
+        ItemAttribute::Option(ref name, ref ty) => Some(format!("pub {}: {},", name, ty)),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    if fields.is_empty() {
+        return None
+    }
+
+    let name = format!("{}{}", item.ident, suffix);
+    let vis = if item.vis == ast::Public { "pub " } else { "" };
+    let options_item = cx.parse_item(format!(
+        "#[derive(Clone, Default, Debug)] #[allow(missing_docs)] {}struct {} {{ {} }}",
+        vis, name, fields.concat()
+    ));
+
+    Some((options_item, builder.ty().id(&*name)))
+}
+
+/// Enums use `TagOptions`, so they can't have an options struct.
+///
+/// Their tag is coded before any field could select a `byte_order`, so they can't have one either.
+fn enum_options_unsupported(cx: &mut ExtCtxt, meta_item: &MetaItem, attrs: &[ItemAttribute]) {
+    if attrs.iter().any(|attr| match *attr { ItemAttribute::Option(..) => true, _ => false }) {
+        cx.span_err(meta_item.span, "option is only supported on structs");
+    }
+    if attrs.iter().any(|attr| match *attr { ItemAttribute::ByteOrder(..) => true, _ => false }) {
+        cx.span_err(meta_item.span, "byte_order is only supported on structs");
+    }
+}
+
+/// Makes the options of a derived impl available to attribute expressions as `options`.
+fn bind_options(cx: &mut ExtCtxt) -> P<ast::Stmt> {
+    quote_stmt!(cx, #[allow(unused_variables)] let options = __options;).unwrap()
+}
+
+fn encode_magic(cx: &mut ExtCtxt, magic: &P<ast::Expr>) -> P<ast::Stmt> {
+    quote_stmt!(cx, let _ = try!(::std::io::Write::write_all(__w, $magic));).unwrap()
+}
+
+fn decode_magic(cx: &mut ExtCtxt, magic: &P<ast::Expr>) -> P<ast::Stmt> {
+    quote_stmt!(cx, let _ = try!(::nue::ReadExactExt::expect_exact(__r, $magic));).unwrap()
+}
+
+enum FieldAttribute {
+    Cond(P<ast::Expr>),
+    Default(P<ast::Expr>),
+    Align(P<ast::Expr>),
+    Limit(P<ast::Expr>),
+    Skip(P<ast::Expr>),
+    Consume(P<ast::Expr>),
     Assert(P<ast::Expr>),
+    Tag(P<ast::Expr>),
+    Count(P<ast::Expr>),
+    /// The name of the counted field, and the expression for its length
+    CountOf(String, P<ast::Expr>),
+    Magic(P<ast::Expr>),
+    Endian(&'static str),
+    /// The `encode` or `decode` function of a `with` module
+    With(P<ast::Expr>),
+    /// The conversion between the wire and field representations, `map` when decoding and `unmap` when encoding
+    Map(P<ast::Expr>),
+    /// Seeks back to where the field started once it has been decoded
+    RestorePosition,
+    /// The field holds the offset of the stream from the start of the value
+    Position,
+    /// The element that ends a `Vec` field
+    Terminator(P<ast::Expr>),
+    /// Whether `last`, the latest element of a `Vec` field, is its final one
+    Until(P<ast::Expr>),
+    /// The value of a field that the stream ends before
+    IfEof(P<ast::Expr>),
+    /// The digest a checksum field is computed with
+    Checksum(P<ast::Expr>),
+    /// The name of the field a checksum starts at
+    Over(String),
+    /// The width of a field packed into bytes with its neighbours
+    Bits(u32),
+    /// The name of the last field that a `limit` spans
+    Through(String),
+    /// The value encoded in place of the field's own
+    Value(P<ast::Expr>),
+    /// Coded as a LEB128 `Varint`
+    Varint,
+}
+
+/// The index of the field that the checksum of a struct or variant starts at, and its digest.
+///
+/// The checksum covers every byte from the start of its `over` field, or the first field,
+/// up to the checksum field itself.
+fn checksum_range(cx: &mut ExtCtxt, fields: &[Field], attrs: &[Vec<FieldAttribute>]) -> Option<(usize, P<ast::Expr>)> {
+    let mut checksums = fields.iter().zip(attrs).enumerate().filter_map(|(i, (field, attrs))| {
+        find_attr(attrs, |attr| match *attr { FieldAttribute::Checksum(ref expr) => Some(expr), _ => None })
+            .map(|hasher| (i, field, hasher, attrs.iter().filter_map(|attr| match *attr {
+                FieldAttribute::Over(ref name) => Some(name.clone()),
+                _ => None,
+            }).last()))
+    }).collect::<Vec<_>>();
+
+    if checksums.len() > 1 {
+        cx.span_err(checksums[1].1.ty.span, "only one checksum is supported per struct or variant");
+    }
+
+    checksums.pop().and_then(|(index, field, hasher, over)| {
+        let start = match over {
+            Some(over) => match fields[..index].iter().position(|field| field.label() == over) {
+                Some(start) => start,
+                None => {
+                    cx.span_err(field.ty.span, &format!("over must name an earlier field, not `{}`", over));
+                    return None
+                },
+            },
+            None => 0,
+        };
+
+        Some((start, hasher))
+    })
+}
+
+/// The fields spanned by each `limit` with a `through` attribute, as the index of
+/// the first and last field of the group, and its limit.
+fn limit_groups(cx: &mut ExtCtxt, fields: &[Field], attrs: &[Vec<FieldAttribute>]) -> Vec<(usize, usize, P<ast::Expr>)> {
+    let mut groups: Vec<(usize, usize, P<ast::Expr>)> = Vec::new();
+    for (start, field) in fields.iter().enumerate() {
+        let through = match attrs[start].iter().filter_map(|attr| match *attr { FieldAttribute::Through(ref name) => Some(name), _ => None }).last() {
+            Some(through) => through,
+            None => continue,
+        };
+        let limit = match find_attr(&attrs[start], |attr| match *attr { FieldAttribute::Limit(ref expr) => Some(expr), _ => None }) {
+            Some(limit) => limit,
+            None => {
+                cx.span_err(field.ty.span, "through requires a limit");
+                continue
+            },
+        };
+        let end = match fields[start + 1..].iter().position(|field| field.label() == *through) {
+            Some(end) => start + 1 + end,
+            None => {
+                cx.span_err(field.ty.span, &format!("through must name a later field, not `{}`", through));
+                continue
+            },
+        };
+
+        if groups.last().map(|&(_, last, _)| start <= last).unwrap_or(false) {
+            cx.span_err(field.ty.span, "limit groups can't overlap");
+        } else if attrs[start..end + 1].iter().any(|attrs| attrs.iter().any(|attr| match *attr { FieldAttribute::Bits(_) => true, _ => false })) {
+            cx.span_err(field.ty.span, "bits fields can't be part of a limit group");
+        } else {
+            groups.push((start, end, limit));
+        }
+    }
+
+    groups
+}
+
+/// Whether a field is the last of its limit group, if it's part of one.
+fn field_group(groups: &[(usize, usize, P<ast::Expr>)], index: usize) -> Option<bool> {
+    groups.iter().find(|&&(start, end, _)| start <= index && index <= end).map(|&(_, end, _)| index == end)
+}
+
+/// The byte order of a field, which may override the item's default.
+fn field_endian(attrs: &[FieldAttribute], defaults: &Defaults) -> Option<&'static str> {
+    attrs.iter().filter_map(|attr| match *attr {
+        FieldAttribute::Endian(endian) => Some(endian),
+        _ => None,
+    }).last().or(defaults.endian)
+}
+
+/// The wrapper type a field of type `ty` is coded through, if any.
+fn field_wrapper(cx: &mut ExtCtxt, attrs: &[FieldAttribute], defaults: &Defaults, ty: &P<ast::Ty>) -> Option<P<ast::Ty>> {
+    if attrs.iter().any(|attr| match *attr { FieldAttribute::Varint => true, _ => false }) {
+        Some(quote_ty!(cx, ::nue::Varint<$ty>))
+    } else {
+        endian_wrapper(cx, field_endian(attrs, defaults), ty)
+    }
+}
+
+/// The runtime byte order of a primitive field that doesn't specify its own `endian` or `varint`.
+fn field_byte_order<'a>(cx: &mut ExtCtxt, attrs: &[FieldAttribute], order: Option<&'a P<ast::Expr>>, ty: &P<ast::Ty>) -> Option<&'a P<ast::Expr>> {
+    let own = attrs.iter().any(|attr| match *attr { FieldAttribute::Endian(_) | FieldAttribute::Varint => true, _ => false });
+    match order {
+        Some(order) if !own && endian_wrapper(cx, Some("Le"), ty).is_some() => Some(order),
+        _ => None,
+    }
+}
+
+/// The expression of the last attribute selected by `f`, if any.
+fn find_attr<F: Fn(&FieldAttribute) -> Option<&P<ast::Expr>>>(attrs: &[FieldAttribute], f: F) -> Option<P<ast::Expr>> {
+    attrs.iter().filter_map(f).last().cloned()
+}
+
+/// The options to decode a field with, as determined by its `tag` and `count` attributes.
+///
+/// A `String` is counted in bytes, and anything else is assumed to be a `Vec`.
+fn decode_options_expr(cx: &mut ExtCtxt, attrs: &[FieldAttribute], ty: &ast::Ty) -> Option<P<ast::Expr>> {
+    let tag = find_attr(attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None })
+        .map(|tag| quote_expr!(cx, ::nue::TagOptions { tag: Some(($tag) as _) }));
+
+    match find_attr(attrs, |attr| match *attr { FieldAttribute::Count(ref expr) => Some(expr), _ => None }) {
+        Some(count) if type_name_is(ty, "String") => Some(quote_expr!(cx, ::nue::StringDecodeOptions { len: Some(($count) as usize) })),
+        Some(count) => {
+            let options = tag.unwrap_or_else(|| quote_expr!(cx, ::std::default::Default::default()));
+            Some(quote_expr!(cx, ::nue::VecDecodeOptions { len: Some(($count) as usize), options: $options }))
+        },
+        None => tag,
+    }
 }
@@ -4,7 +4,7 @@
 
 //! nue derive syntax extension.
 //!
-//! Provides the `#[derive(PodPacked, Pod, NueEncode, NueDecode)]` extensions documented in `nue-macros`.
+//! Provides the `#[derive(PodPacked, Pod, NueEncode, NueDecode, NueDecodeRef, NueEncodedSize)]` extensions documented in `nue-macros`.
 //!
 //! ## Stable
 //!
@@ -46,6 +46,8 @@ pub fn register(reg: &mut syntex::Registry) {
     reg.add_decorator("derive_Pod", expand_derive_pod);
     reg.add_decorator("derive_NueEncode", expand_derive_encode);
     reg.add_decorator("derive_NueDecode", expand_derive_decode);
+    reg.add_decorator("derive_NueDecodeRef", expand_derive_decode_ref);
+    reg.add_decorator("derive_NueEncodedSize", expand_derive_encoded_size);
 
     reg.add_post_expansion_pass(strip_attributes);
 
@@ -57,6 +59,7 @@ pub fn register(reg: &mut syntex::Registry) {
             fn fold_attribute(&mut self, attr: ast::Attribute) -> Option<ast::Attribute> {
                 match attr.node.value.node {
                     ast::MetaWord(ref n) if *n == "__nue_packed" => { return None; },
+                    ast::MetaList(ref n, _) if *n == "__nue_packed" => { return None; },
                     ast::MetaList(ref n, _) if *n == "nue" || *n == "nue_enc" || *n == "nue_dec" => { return None; },
                     _ => {}
                 }
@@ -117,4 +120,18 @@ pub fn register(reg: &mut rustc::plugin::Registry) {
             Box::new(expand_derive_decode)
         )
     );
+
+    reg.register_syntax_extension(
+        syntax::parse::token::intern("derive_NueDecodeRef"),
+        syntax::ext::base::MultiDecorator(
+            Box::new(expand_derive_decode_ref)
+        )
+    );
+
+    reg.register_syntax_extension(
+        syntax::parse::token::intern("derive_NueEncodedSize"),
+        syntax::ext::base::MultiDecorator(
+            Box::new(expand_derive_encoded_size)
+        )
+    );
 }
@@ -0,0 +1,2653 @@
+#![deny(missing_docs)]
+
+//! Procedural `#[derive(..)]` macros for POD and binary encodable types.
+//!
+//! Provides `#[derive(Packed, Pod, NueEncode, NueDecode, NueEncodedSize, NueDecodeRef)]`,
+//! which accept the same `#[nue(..)]`, `#[nue_enc(..)]`, and `#[nue_dec(..)]` attributes
+//! as the syntax extensions documented in `nue-macros`, but work on stable Rust without
+//! a build script.
+//!
+//! A derive can't modify the type it is applied to, so there is no `#[packed]` or
+//! `#[derive(PodPacked)]`. Spell out `#[repr(C, packed)]` or `#[repr(C, packed(N))]`
+//! instead, which `Packed` and `Pod` read the alignment from.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate nue_derive;
+//! extern crate nue;
+//! extern crate pod;
+//!
+//! use nue::{Encode, Decode, Un, Aligned};
+//!
+//! #[derive(Pod, Packed, Copy, Clone)]
+//! #[repr(C, packed)]
+//! struct Entry {
+//!     kind: u8,
+//!     len: Un<u16>,
+//! }
+//!
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(magic = "b\"NUE\"", endian = "big")]
+//! struct Header {
+//!     version: u16,
+//!     #[nue(count_of = "entries")]
+//!     count: u8,
+//!     #[nue(skip = "1")]
+//!     entries: Vec<u8>,
+//! }
+//!
+//! # fn main() {
+//! let entry = Entry { kind: 1, len: 2u16.unaligned() };
+//! assert_eq!(entry.encode_vec().unwrap().len(), 3);
+//!
+//! let header = Header { version: 2, count: 1, entries: vec![3] };
+//! let buffer = header.encode_vec().unwrap();
+//! assert_eq!(buffer, b"NUE\x00\x02\x01\x00\x03");
+//! assert_eq!(Header::decode_slice(&buffer).unwrap(), header);
+//! # }
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use std::cmp::Reverse;
+use proc_macro2::{Span, TokenStream};
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Generics, Ident, Lit, Meta, Type, WherePredicate};
+use syn::parse::Parse;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::token::Comma;
+
+/// Implements `nue::Packed` and `nue::Unaligned` for a `#[repr(C, packed)]` struct,
+/// or `nue::PackedAlign` for a `#[repr(C, packed(N))]` one.
+///
+/// Fields of a `Packed` struct must be `Unaligned` and have an alignment of `1`,
+/// which rejects fieldless enums with a wider `#[repr(..)]`. Fields whose types depend on
+/// the struct's parameters are only required to be `Unaligned`.
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate nue_derive;
+/// extern crate nue;
+///
+/// #[repr(u32)]
+/// #[derive(Copy, Clone)]
+/// enum Kind { A, B }
+///
+/// unsafe impl nue::Unaligned for Kind { }
+///
+/// #[derive(Packed)]
+/// #[repr(C, packed)]
+/// struct Entry {
+///     id: u8,
+///     kind: Kind,
+/// }
+/// # fn main() { }
+/// ```
+#[proc_macro_derive(Packed, attributes(nue))]
+pub fn derive_packed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_packed)
+}
+
+/// Implements `pod::Pod` for a `#[repr(C, packed)]` struct or a `#[repr(C)]` union whose members
+/// all fill it, or `pod::CheckedPod` for a C-like enum with a primitive `#[repr(..)]`.
+#[proc_macro_derive(Pod, attributes(nue))]
+pub fn derive_pod(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_pod)
+}
+
+/// Implements `nue::Encode`.
+#[proc_macro_derive(NueEncode, attributes(nue, nue_enc, nue_dec))]
+pub fn derive_encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_encode)
+}
+
+/// Implements `nue::Decode`.
+#[proc_macro_derive(NueDecode, attributes(nue, nue_enc, nue_dec))]
+pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_decode)
+}
+
+/// Implements `nue::EncodedSize`.
+#[proc_macro_derive(NueEncodedSize, attributes(nue, nue_enc, nue_dec))]
+pub fn derive_encoded_size(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_encoded_size)
+}
+
+/// Implements `nue::DecodeRef`.
+#[proc_macro_derive(NueDecodeRef, attributes(nue, nue_enc, nue_dec))]
+pub fn derive_decode_ref(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input, expand_derive_decode_ref)
+}
+
+fn expand(input: proc_macro::TokenStream, f: fn(&mut Context, &DeriveInput) -> TokenStream) -> proc_macro::TokenStream {
+    let item = match syn::parse::<DeriveInput>(input) {
+        Ok(item) => item,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut cx = Context { errors: Vec::new() };
+    let tokens = f(&mut cx, &item);
+    cx.finish(tokens).into()
+}
+
+/// Collects errors so that expansion can carry on and report all of them at once.
+struct Context {
+    errors: Vec<syn::Error>,
+}
+
+impl Context {
+    fn span_err(&mut self, span: Span, msg: &str) {
+        self.errors.push(syn::Error::new(span, msg));
+    }
+
+    fn parse<T: Parse>(&mut self, span: Span, value: &str) -> Option<T> {
+        match syn::parse_str(value) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.span_err(span, &format!("failed to parse `{}`: {}", value, err));
+                None
+            },
+        }
+    }
+
+    fn parse_expr(&mut self, span: Span, value: &str) -> Expr {
+        self.parse(span, value).unwrap_or_else(|| parse_quote!(()))
+    }
+
+    fn finish(self, tokens: TokenStream) -> TokenStream {
+        if self.errors.is_empty() {
+            tokens
+        } else {
+            self.errors.iter().map(syn::Error::to_compile_error).collect()
+        }
+    }
+}
+
+/// Bounds the generics of the item being derived for the impl.
+///
+/// Type parameters used by fields must be `Pod`, unless a `bound` attribute under `meta_name`
+/// replaces those bounds with its own where clause. Parameters that only appear in
+/// `PhantomData` or `skip_bound` fields are left unbounded.
+fn derive_type(cx: &mut Context, item: &DeriveInput, meta_name: Option<&'static str>) -> Option<Generics> {
+    let predicates = match meta_name.and_then(|meta_name| item_bound(cx, &item.attrs, meta_name)) {
+        Some(predicates) => predicates,
+        None => {
+            let fields = item_fields(item);
+            item.generics.type_params().filter(|param| fields.iter().any(|field|
+                !type_name_is(field.ty, "PhantomData") && !skips_bound(field.attrs) && type_mentions(field.ty, &param.ident)
+            )).map(|param| {
+                let ident = &param.ident;
+                parse_quote!(#ident: ::pod::Pod)
+            }).collect()
+        },
+    };
+
+    let mut generics = item.generics.clone();
+    if !predicates.is_empty() {
+        generics.make_where_clause().predicates.extend(predicates);
+    }
+
+    Some(generics)
+}
+
+/// The alignment of a `#[repr(packed)]` or `#[repr(packed(N))]` item, if it is packed.
+fn packed_align(cx: &mut Context, attrs: &[Attribute]) -> Option<usize> {
+    let mut align = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        let items = match attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated) {
+            Ok(items) => items,
+            Err(_) => continue,
+        };
+
+        for item in items {
+            match item {
+                Meta::Path(ref path) if path.is_ident("packed") => align = Some(1),
+                Meta::List(ref list) if list.path.is_ident("packed") => match list.parse_args::<syn::LitInt>().and_then(|lit| lit.base10_parse::<usize>()) {
+                    Ok(value) if value.is_power_of_two() => align = Some(value),
+                    _ => cx.span_err(list.span(), "packed alignment must be a power of two"),
+                },
+                _ => (),
+            }
+        }
+    }
+    align
+}
+
+fn expand_derive_packed(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    let generics = if let Some(generics) = derive_type(cx, item, None) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let fields = match item.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            cx.span_err(item.ident.span(), "packed types must be structs");
+            return TokenStream::new()
+        },
+    };
+
+    let align = match packed_align(cx, &item.attrs) {
+        Some(align) => align,
+        None => {
+            cx.span_err(item.ident.span(), "packed types require #[repr(packed)]");
+            return TokenStream::new()
+        },
+    };
+
+    let ident = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let tys = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+    // Fields that depend on the item's parameters can't be named outside of its impls
+    let concrete = tys.iter().filter(|ty| !type_is_generic(ty, &item.generics)).collect::<Vec<_>>();
+
+    if align > 1 {
+        return quote! {
+            const _: () = {
+                #(
+                    assert!(::std::mem::align_of::<#concrete>() <= #align, concat!(stringify!(#concrete), " is not packed"));
+                )*
+            };
+
+            #[automatically_derived]
+            unsafe impl #impl_generics ::nue::PackedAlign for #ident #ty_generics #where_clause {
+                const ALIGN: usize = #align;
+            }
+        }
+    }
+
+    // Fieldless enums can be `Unaligned` without having an alignment of `1`, and generic
+    // fields are only checked to be `Unaligned`
+    quote! {
+        const _: () = {
+            #(
+                assert!(::std::mem::align_of::<#concrete>() == 1, concat!(stringify!(#concrete), " is not unaligned"));
+            )*
+        };
+
+        #[automatically_derived]
+        unsafe impl #impl_generics ::nue::Packed for #ident #ty_generics #where_clause {
+            fn __assert_unaligned() {
+                fn assert<T: ::nue::Unaligned>() { }
+
+                #( assert::<#tys>(); )*
+            }
+        }
+
+        #[automatically_derived]
+        unsafe impl #impl_generics ::nue::Unaligned for #ident #ty_generics #where_clause { }
+    }
+}
+
+fn expand_derive_pod(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    let generics = if let Some(generics) = derive_type(cx, item, None) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let ident = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    match item.data {
+        Data::Struct(ref data) => {
+            if packed_align(cx, &item.attrs) != Some(1) {
+                cx.span_err(item.ident.span(), "POD types require #[repr(packed)]");
+                return TokenStream::new()
+            }
+
+            let tys = data.fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+            quote! {
+                #[automatically_derived]
+                unsafe impl #impl_generics ::pod::Pod for #ident #ty_generics #where_clause {
+                    fn __assert_pod() {
+                        fn assert<T: ::pod::Pod>() { }
+
+                        #( assert::<#tys>(); )*
+                    }
+                }
+            }
+        },
+        Data::Enum(ref data) => {
+            let repr = match enum_repr(&item.attrs) {
+                Some(repr) => repr,
+                None => {
+                    cx.span_err(item.ident.span(), "POD enums require a primitive #[repr(..)]");
+                    return TokenStream::new()
+                },
+            };
+
+            let mut valid = quote!(false);
+            for variant in &data.variants {
+                if !variant.fields.is_empty() {
+                    cx.span_err(variant.ident.span(), "POD enums must not have fields");
+                    return TokenStream::new()
+                }
+
+                let name = &variant.ident;
+                valid = quote!(#valid || __disc == #ident::#name as #repr);
+            }
+
+            // Single byte enums may be members of packed types
+            let unaligned = if repr == "u8" || repr == "i8" {
+                quote! {
+                    #[automatically_derived]
+                    unsafe impl #impl_generics ::pod::packed::Unaligned for #ident #ty_generics #where_clause { }
+                }
+            } else {
+                TokenStream::new()
+            };
+
+            // Not every discriminant is valid, so enums are only `CheckedPod`
+            quote! {
+                #unaligned
+
+                #[automatically_derived]
+                unsafe impl #impl_generics ::pod::CheckedPod for #ident #ty_generics #where_clause {
+                    fn valid_bytes(__bytes: &[u8]) -> bool {
+                        let __disc = <#repr as ::pod::Pod>::copy_from(__bytes);
+                        #valid
+                    }
+                }
+            }
+        },
+        Data::Union(ref data) => {
+            if !repr_c(&item.attrs) {
+                cx.span_err(item.ident.span(), "POD unions require #[repr(C)]");
+                return TokenStream::new()
+            }
+
+            if item.generics.type_params().next().is_some() {
+                cx.span_err(item.ident.span(), "POD unions can't be generic");
+                return TokenStream::new()
+            }
+
+            let tys = data.fields.named.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+            // Any member may be read, so each must cover every byte of the union
+            quote! {
+                const _: () = {
+                    #(
+                        assert!(::std::mem::size_of::<#tys>() == ::std::mem::size_of::<#ident>(), concat!(stringify!(#tys), " does not fill ", stringify!(#ident)));
+                    )*
+                };
+
+                #[automatically_derived]
+                unsafe impl #impl_generics ::pod::Pod for #ident #ty_generics #where_clause {
+                    fn __assert_pod() {
+                        fn assert<T: ::pod::Pod>() { }
+
+                        #( assert::<#tys>(); )*
+                    }
+                }
+            }
+        },
+    }
+}
+
+fn expand_derive_encode(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    if let Data::Union(..) = item.data {
+        cx.span_err(item.ident.span(), "`NueEncode` may only be applied to structs or enums");
+        return TokenStream::new()
+    }
+
+    let generics = if let Some(generics) = derive_type(cx, item, Some("nue_enc")) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let mut needs_seek = false;
+    let attrs = item_attrs(cx, &item.attrs, "nue_enc");
+    let defaults = Defaults::new(&attrs);
+    let ident = &item.ident;
+    let mut items = TokenStream::new();
+
+    let (mut encoders, options) = match item.data {
+        Data::Struct(ref data) => {
+            let fields = struct_fields(&data.fields);
+            let mut encoders = encode_fields(cx, &fields, &defaults, false, &mut needs_seek);
+            let options = match options_struct(item, &attrs, "EncodeOptions") {
+                Some((options_item, options)) => {
+                    items.extend(options_item);
+                    encoders.insert(0, bind_options());
+                    options
+                },
+                None => quote!(()),
+            };
+
+            (encoders, options)
+        },
+        Data::Enum(ref data) => {
+            enum_options_unsupported(cx, item, &attrs);
+            let tag_type = enum_tag_type(&item.attrs, &attrs);
+            let variant_attrs = data.variants.iter().map(|variant| variant_attrs(cx, &variant.attrs, "nue_enc")).collect::<Vec<_>>();
+            let other = other_variant(cx, &data.variants, &variant_attrs);
+            let arms = data.variants.iter().zip(discriminants(&data.variants, &variant_attrs)).zip(&variant_attrs).enumerate().map(|(i, ((variant, disc), attrs))| {
+                let all_fields = struct_fields(&variant.fields);
+                let pat = variant_pat(ident, &variant.ident, &variant.fields, &all_fields);
+                // The `other` variant encodes its first field as the tag
+                let (tag, fields) = if other == Some(i) {
+                    let tag = &all_fields[0].let_name;
+                    (quote!(((*#tag) as #tag_type)), &all_fields[1..])
+                } else {
+                    (quote!(((#disc) as #tag_type)), &all_fields[..])
+                };
+                let mut encoders = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => Vec::new(),
+                    Some(cond) => {
+                        let encoders = encode_fields(cx, fields, &defaults, true, &mut needs_seek);
+                        vec![quote!(if #cond { #(#encoders)* })]
+                    },
+                    None => encode_fields(cx, fields, &defaults, true, &mut needs_seek),
+                };
+                if let Some(align) = variant_align(attrs) {
+                    needs_seek = true;
+                    encoders.insert(0, quote!(::nue::SeekAlignExt::align_to(__w, #align)?;));
+                }
+                let encode_tag = encode_value(quote!(&#tag), &tag_type, endian_wrapper(defaults.endian, &tag_type), None);
+
+                quote! {
+                    #pat => {
+                        match __options.tag {
+                            Some(__tag) => if __tag != #tag {
+                                return Err(::std::convert::From::from(::nue::Error::Validation {
+                                    field: None,
+                                    message: format!("variant does not match the external tag {:?}", __tag),
+                                }));
+                            },
+                            None => {
+                                #encode_tag?;
+                            },
+                        }
+                        #(#encoders)*
+                    }
+                }
+            }).collect::<Vec<_>>();
+
+            (vec![quote!(match *self { #(#arms)* })], quote!(::nue::TagOptions<#tag_type>))
+        },
+        Data::Union(..) => unreachable!(),
+    };
+
+    for attr in attrs.iter().rev() {
+        if let ItemAttribute::Magic(ref magic) = *attr {
+            encoders.insert(0, encode_magic(magic));
+        }
+    }
+
+    // Hooks run before anything is written
+    for attr in attrs.iter().rev() {
+        if let ItemAttribute::BeforeEncode(ref hook) = *attr {
+            encoders.insert(0, quote!((#hook)(self)?;));
+        }
+    }
+
+    if let Some(pad) = item_pad_to(&attrs) {
+        needs_seek = true;
+        encoders.push(quote! {
+            {
+                let __end = ::nue::Tell::tell(__w)?;
+                let __pad_to = (#pad) as u64;
+                if __end > __pad_to {
+                    return Err(::std::convert::From::from(::nue::Error::Limit { limit: __pad_to }));
+                }
+                ::nue::SeekForward::seek_forward(__w, __pad_to - __end)?;
+            }
+        });
+    }
+
+    let needs_seek = if needs_seek {
+        quote!(let __w = &mut ::nue::ReadWriteTell::new(::nue::SeekForwardWrite::new(::nue::SeekAll::new(__w)));)
+    } else {
+        quote!(let __w = &mut ::nue::SeekAll::new(__w);)
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    items.extend(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::nue::Encode for #ident #ty_generics #where_clause {
+            type Options = #options;
+
+            fn encode<__W: ::std::io::Write>(&self, __w: &mut __W) -> ::std::io::Result<()> {
+                ::nue::Encode::encode_options(self, __w, ::std::default::Default::default())
+            }
+
+            fn encode_options<__W: ::std::io::Write>(&self, __w: &mut __W, __options: Self::Options) -> ::std::io::Result<()> {
+                let _ = __options;
+                #needs_seek
+                #(#encoders)*
+
+                Ok(())
+            }
+        }
+    });
+
+    items
+}
+
+fn expand_derive_encoded_size(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    if let Data::Union(..) = item.data {
+        cx.span_err(item.ident.span(), "`NueEncodedSize` may only be applied to structs or enums");
+        return TokenStream::new()
+    }
+
+    let generics = if let Some(generics) = derive_type(cx, item, Some("nue_enc")) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let attrs = item_attrs(cx, &item.attrs, "nue_enc");
+    let ident = &item.ident;
+
+    let mut sizes = match item.data {
+        Data::Struct(ref data) => {
+            let fields = struct_fields(&data.fields);
+            let mut sizes = size_fields(cx, &fields, false);
+            if attrs.iter().any(|attr| matches!(*attr, ItemAttribute::Option(..))) {
+                sizes.insert(0, bind_options());
+            }
+            sizes
+        },
+        Data::Enum(ref data) => {
+            let tag_type = enum_tag_type(&item.attrs, &attrs);
+            let variant_attrs = data.variants.iter().map(|variant| variant_attrs(cx, &variant.attrs, "nue_enc")).collect::<Vec<_>>();
+            let other = other_variant(cx, &data.variants, &variant_attrs);
+            let arms = data.variants.iter().zip(&variant_attrs).enumerate().map(|(i, (variant, attrs))| {
+                let all_fields = struct_fields(&variant.fields);
+                let pat = variant_pat(ident, &variant.ident, &variant.fields, &all_fields);
+                // The first field of the `other` variant is counted as the tag
+                let fields = if other == Some(i) { &all_fields[1..] } else { &all_fields[..] };
+                let mut sizes = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => Vec::new(),
+                    Some(cond) => {
+                        let sizes = size_fields(cx, fields, true);
+                        vec![quote!(if #cond { #(#sizes)* })]
+                    },
+                    None => size_fields(cx, fields, true),
+                };
+                if let Some(align) = variant_align(attrs) {
+                    sizes.insert(0, quote!(__size += ((#align) as u64 - __size % (#align) as u64) % (#align) as u64;));
+                }
+
+                quote! {
+                    #pat => {
+                        if __options.tag.is_none() {
+                            __size += ::std::mem::size_of::<#tag_type>() as u64;
+                        }
+                        #(#sizes)*
+                    }
+                }
+            }).collect::<Vec<_>>();
+
+            vec![quote!(match *self { #(#arms)* })]
+        },
+        Data::Union(..) => unreachable!(),
+    };
+
+    for attr in attrs.iter().rev() {
+        if let ItemAttribute::Magic(ref magic) = *attr {
+            sizes.insert(0, quote!(__size += (#magic).len() as u64;));
+        }
+    }
+
+    if let Some(pad) = item_pad_to(&attrs) {
+        sizes.push(quote!(__size = ::std::cmp::max(__size, (#pad) as u64);));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::nue::EncodedSize for #ident #ty_generics #where_clause {
+            fn encoded_size(&self) -> u64 {
+                ::nue::EncodedSize::encoded_size_options(self, ::std::default::Default::default())
+            }
+
+            #[allow(unused_mut)]
+            fn encoded_size_options(&self, __options: <Self as ::nue::Encode>::Options) -> u64 {
+                let _ = __options;
+                let mut __size = 0u64;
+                #(#sizes)*
+
+                __size
+            }
+        }
+    }
+}
+
+fn expand_derive_decode(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    if let Data::Union(..) = item.data {
+        cx.span_err(item.ident.span(), "`NueDecode` may only be applied to structs or enums");
+        return TokenStream::new()
+    }
+
+    let generics = if let Some(generics) = derive_type(cx, item, Some("nue_dec")) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let mut seeking = Seeking::None;
+    let attrs = item_attrs(cx, &item.attrs, "nue_dec");
+    let defaults = Defaults::new(&attrs);
+    let ident = &item.ident;
+    let mut items = TokenStream::new();
+
+    // Field types can't be named in an array length if they may depend on type parameters.
+    let coalesce = generics.type_params().next().is_none();
+
+    let (mut decoders, result, options) = match item.data {
+        Data::Struct(ref data) => {
+            let fields = struct_fields(&data.fields);
+            let mut decoders = decode_fields(cx, &ident.to_string(), &fields, &defaults, coalesce, &mut seeking);
+            let options = match options_struct(item, &attrs, "DecodeOptions") {
+                Some((options_item, options)) => {
+                    items.extend(options_item);
+                    decoders.insert(0, bind_options());
+                    options
+                },
+                None => quote!(()),
+            };
+
+            (decoders, construct(&quote!(#ident), &data.fields, &fields), options)
+        },
+        Data::Enum(ref data) => {
+            enum_options_unsupported(cx, item, &attrs);
+            let tag_type = enum_tag_type(&item.attrs, &attrs);
+            let variant_attrs = data.variants.iter().map(|variant| variant_attrs(cx, &variant.attrs, "nue_dec")).collect::<Vec<_>>();
+            let other = other_variant(cx, &data.variants, &variant_attrs);
+            let variants = data.variants.iter().zip(discriminants(&data.variants, &variant_attrs)).zip(&variant_attrs).collect::<Vec<_>>();
+
+            let (mut arms, mut fallback) = (Vec::new(), None);
+            for (i, ((variant, disc), attrs)) in variants.into_iter().enumerate() {
+                let all_fields = struct_fields(&variant.fields);
+                let type_name = format!("{}::{}", ident, variant.ident);
+                let name = &variant.ident;
+                let ctor = construct(&quote!(#ident::#name), &variant.fields, &all_fields);
+
+                // The `other` variant takes any unknown tag as its first field
+                let (tag, fields) = if other == Some(i) {
+                    let (tag, ty) = (&all_fields[0].let_name, all_fields[0].ty);
+                    (quote!(let #tag: #ty = __tag as #ty;), &all_fields[1..])
+                } else {
+                    (TokenStream::new(), &all_fields[..])
+                };
+
+                // The payload of a variant whose condition isn't met is left to `Default::default()`
+                let (lets, tys) = (fields.iter().map(|field| &field.let_name), fields.iter().map(|field| field.ty));
+                let defaulted = quote! {
+                    #(let #lets: #tys = ::std::default::Default::default();)*
+                    #ctor
+                };
+                let mut payload = match variant_cond(attrs) {
+                    Some(ref cond) if expr_is_false(cond) => defaulted,
+                    Some(cond) => {
+                        let decoders = decode_fields(cx, &type_name, fields, &defaults, coalesce, &mut seeking);
+                        quote! {
+                            if #cond {
+                                #(#decoders)*
+                                #ctor
+                            } else {
+                                #defaulted
+                            }
+                        }
+                    },
+                    None => {
+                        let decoders = decode_fields(cx, &type_name, fields, &defaults, coalesce, &mut seeking);
+                        quote! {
+                            #(#decoders)*
+                            #ctor
+                        }
+                    },
+                };
+                if let Some(align) = variant_align(attrs) {
+                    seeking.require(Seeking::Forward);
+                    payload = quote! {
+                        ::nue::SeekAlignExt::align_to(__r, #align)?;
+                        #payload
+                    };
+                }
+                let payload = quote! {
+                    #tag
+                    #payload
+                };
+
+                if other == Some(i) {
+                    fallback = Some(payload);
+                } else {
+                    arms.push((disc, payload));
+                }
+            }
+
+            let mut result = fallback.unwrap_or_else(|| quote! {
+                return Err(::std::convert::From::from(::nue::Error::Validation {
+                    field: None,
+                    message: format!("unknown discriminant {:?}", __tag),
+                }))
+            });
+            for (disc, payload) in arms.into_iter().rev() {
+                result = quote! {
+                    if __tag == ((#disc) as #tag_type) {
+                        #payload
+                    } else {
+                        #result
+                    }
+                };
+            }
+
+            let decode_tag = decode_value(&tag_type, endian_wrapper(defaults.endian, &tag_type), None);
+            let decoders = vec![quote! {
+                let __tag: #tag_type = match __options.tag {
+                    Some(__tag) => __tag,
+                    None => #decode_tag?,
+                };
+            }];
+            (decoders, result, quote!(::nue::TagOptions<#tag_type>))
+        },
+        Data::Union(..) => unreachable!(),
+    };
+
+    for attr in attrs.iter().rev() {
+        if let ItemAttribute::Magic(ref magic) = *attr {
+            decoders.insert(0, decode_magic(magic));
+        }
+    }
+
+    let mut finish = match item_pad_to(&attrs) {
+        Some(pad) => {
+            seeking.require(Seeking::Forward);
+            vec![quote! {
+                {
+                    let __end = ::nue::Tell::tell(__r)?;
+                    let __pad_to = (#pad) as u64;
+                    if __end > __pad_to {
+                        return Err(::std::convert::From::from(::nue::Error::Limit { limit: __pad_to }));
+                    }
+                    ::nue::SeekForward::seek_forward(__r, __pad_to - __end)?;
+                }
+            }]
+        },
+        None => Vec::new(),
+    };
+
+    for attr in &attrs {
+        if let ItemAttribute::AfterDecode(ref hook) = *attr {
+            finish.push(quote!((#hook)(&mut __result)?;));
+        }
+    }
+
+    let needs_seek = match seeking {
+        Seeking::Rewind => quote!(let __r = &mut ::nue::RewindableReader::new(__r);),
+        Seeking::Forward => quote!(let __r = &mut ::nue::ReadWriteTell::new(::nue::SeekForwardRead::new(::nue::SeekAll::new(__r)));),
+        Seeking::None => quote!(let __r = &mut ::nue::ReadWriteTell::new(::nue::SeekAll::new(__r));),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    items.extend(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::nue::Decode for #ident #ty_generics #where_clause {
+            type Options = #options;
+
+            fn decode<__R: ::std::io::Read>(__r: &mut __R) -> ::std::io::Result<Self> {
+                ::nue::Decode::decode_options(__r, ::std::default::Default::default())
+            }
+
+            fn decode_options<__R: ::std::io::Read>(__r: &mut __R, __options: Self::Options) -> ::std::io::Result<Self> {
+                let _ = __options;
+                #needs_seek
+                #(#decoders)*
+                #[allow(unused_mut)]
+                let mut __result = #result;
+                #(#finish)*
+
+                ::nue::Decode::validate(&__result)?;
+
+                Ok(__result)
+            }
+        }
+    });
+
+    items
+}
+
+fn expand_derive_decode_ref(cx: &mut Context, item: &DeriveInput) -> TokenStream {
+    let data = match item.data {
+        Data::Struct(ref data) => data,
+        _ => {
+            cx.span_err(item.ident.span(), "`NueDecodeRef` may only be applied to structs");
+            return TokenStream::new()
+        },
+    };
+
+    let lifetime = match item.generics.lifetimes().next() {
+        Some(def) => def.lifetime.clone(),
+        None => {
+            cx.span_err(item.ident.span(), "`NueDecodeRef` requires a lifetime parameter to borrow from the slice with");
+            return TokenStream::new()
+        },
+    };
+
+    let generics = if let Some(generics) = derive_type(cx, item, Some("nue_dec")) {
+        generics
+    } else {
+        return TokenStream::new()
+    };
+
+    let attrs = item_attrs(cx, &item.attrs, "nue_dec");
+    let defaults = Defaults::new(&attrs);
+    let ident = &item.ident;
+    let fields = struct_fields(&data.fields);
+    let mut decoders = decode_ref_fields(cx, &ident.to_string(), &fields, &defaults);
+    let result = construct(&quote!(#ident), &data.fields, &fields);
+
+    let mut finish = Vec::new();
+    for attr in attrs.iter().rev() {
+        match *attr {
+            ItemAttribute::Magic(ref magic) => decoders.insert(0, decode_magic(magic)),
+            ItemAttribute::AfterDecode(ref hook) => finish.insert(0, quote!((#hook)(&mut __result)?;)),
+            ItemAttribute::PadTo(_) | ItemAttribute::Option(..) => cx.span_err(item.ident.span(), "attribute is not supported by `NueDecodeRef`"),
+            _ => (),
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::nue::DecodeRef<#lifetime> for #ident #ty_generics #where_clause {
+            type Options = ();
+
+            #[allow(unused_variables)]
+            fn decode_ref_options(__r: &mut &#lifetime [u8], __options: Self::Options) -> ::std::io::Result<Self> {
+                let __start = __r.len();
+                #(#decoders)*
+                #[allow(unused_mut)]
+                let mut __result = #result;
+                #(#finish)*
+
+                Ok(__result)
+            }
+        }
+    }
+}
+
+/// Generates statements that decode each field from the slice `__r`, borrowing
+/// from it for field types that have a lifetime.
+///
+/// Only the attributes that don't require seeking are supported.
+fn decode_ref_fields(cx: &mut Context, type_name: &str, fields: &[Field], defaults: &Defaults) -> Vec<TokenStream> {
+    let mut attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_dec", &SelfAccess::Locals)).collect::<Vec<_>>();
+    count_of_fields(cx, fields, &mut attrs);
+    let byte_order = defaults.byte_order.as_ref().map(|&(span, ref value)| attr_expr(cx, span, &SelfAccess::Locals, value));
+
+    fields.iter().zip(attrs).map(|(field, attrs)| {
+        let let_name = &field.let_name;
+        let field_str = field.label();
+        let field_type = field.ty;
+        let (mut cond, mut cond_default) = (None, None);
+
+        let count = find_attr(&attrs, |attr| match *attr { FieldAttribute::Count(ref expr) => Some(expr), _ => None });
+        let position = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Position));
+        let value = if position {
+            quote!(Ok::<_, ::std::io::Error>((__start - __r.len()) as #field_type))
+        } else if type_borrows(field_type) {
+            let options = match count {
+                Some(count) if type_is_str_ref(field_type) => quote!(::nue::StringDecodeOptions { len: Some((#count) as usize) }),
+                Some(count) => quote!(::nue::VecDecodeOptions { len: Some((#count) as usize), options: () }),
+                None => quote!(::std::default::Default::default()),
+            };
+            quote!(::nue::DecodeRef::decode_ref_options(__r, #options))
+        } else {
+            match decode_options_expr(&attrs, field_type) {
+                Some(options) => quote!(::nue::Decode::decode_options(__r, #options)),
+                None => decode_value(field_type, field_wrapper(&attrs, defaults, field_type), field_byte_order(&attrs, byte_order.as_ref(), field_type)),
+            }
+        };
+
+        let mut statement = vec![
+            quote!(let __offset = (__start - __r.len()) as u64;),
+            quote! {
+                let #let_name: #field_type = match #value {
+                    Ok(__value) => __value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field(#type_name, #field_str, Some(__offset)))),
+                };
+            },
+        ];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, decode_magic(&magic));
+        }
+
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Default(expr) => cond_default = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::CountOf(..) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::Position |
+                FieldAttribute::Value(_) | FieldAttribute::Varint => (),
+                FieldAttribute::Skip(expr) => statement.insert(0, quote! {
+                    {
+                        let __skip = (#expr) as usize;
+                        if __r.len() < __skip {
+                            return Err(::std::convert::From::from(::nue::Error::UnexpectedEof { needed: Some((__skip - __r.len()) as u64) }));
+                        }
+                        let __data = *__r;
+                        *__r = &__data[__skip..];
+                    }
+                }),
+                FieldAttribute::Assert(expr) => statement.push(quote! {
+                    if !(#expr) {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some(#field_str),
+                            message: concat!("assertion ", stringify!(#expr), " failed").to_owned(),
+                        }));
+                    }
+                }),
+                FieldAttribute::Align(_) | FieldAttribute::Limit(_) | FieldAttribute::Consume(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) |
+                FieldAttribute::RestorePosition | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) | FieldAttribute::IfEof(_) | FieldAttribute::Checksum(_) | FieldAttribute::Over(_) |
+                FieldAttribute::Bits(_) | FieldAttribute::Through(_) =>
+                    cx.span_err(field.ty.span(), "attribute is not supported by `NueDecodeRef`"),
+            }
+        }
+
+        match cond {
+            Some(cond) => {
+                let default = cond_default.map(|default| quote!(#default)).unwrap_or_else(|| quote!(::std::default::Default::default()));
+
+                if expr_is_false(&cond) {
+                    quote!(let #let_name = #default;)
+                } else {
+                    quote! {
+                        let #let_name = if #cond {
+                            #(#statement)*
+                            #let_name
+                        } else {
+                            #default
+                        };
+                    }
+                }
+            },
+            None => quote!(let #let_name = { #(#statement)* #let_name };),
+        }
+    }).collect()
+}
+
+/// Whether a type borrows with a lifetime anywhere within it.
+fn type_borrows(ty: &Type) -> bool {
+    match *ty {
+        Type::Reference(..) => true,
+        _ => quote!(#ty).to_string().contains('\''),
+    }
+}
+
+/// Whether a type is a `&str`.
+fn type_is_str_ref(ty: &Type) -> bool {
+    match *ty {
+        Type::Reference(ref reference) => type_name_is(&reference.elem, "str"),
+        _ => false,
+    }
+}
+
+/// A field of a struct or enum variant.
+struct Field<'a> {
+    /// The local that holds the field's value while decoding, or its reference while encoding a variant
+    let_name: Ident,
+    /// The field's name, or `None` for tuple fields
+    name: Option<&'a Ident>,
+    index: usize,
+    ty: &'a Type,
+    attrs: &'a [Attribute],
+}
+
+impl<'a> Field<'a> {
+    /// The name used to refer to the field in error messages
+    fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+}
+
+fn struct_fields<'a, I: IntoIterator<Item = &'a syn::Field>>(fields: I) -> Vec<Field<'a>> {
+    fields.into_iter().enumerate().map(|(i, field)| Field {
+        let_name: match field.ident {
+            Some(ref name) => format_ident!("__self_0{}", name),
+            None => format_ident!("__self_0{}", i),
+        },
+        name: field.ident.as_ref(),
+        index: i,
+        ty: &field.ty,
+        attrs: &field.attrs,
+    }).collect()
+}
+
+/// The fields of a struct or union, or of every variant of an enum.
+fn item_fields<'a>(item: &'a DeriveInput) -> Vec<Field<'a>> {
+    match item.data {
+        Data::Struct(ref data) => struct_fields(&data.fields),
+        Data::Enum(ref data) => data.variants.iter().flat_map(|variant| struct_fields(&variant.fields)).collect(),
+        Data::Union(ref data) => struct_fields(&data.fields.named),
+    }
+}
+
+/// Builds a pattern that matches the variant, binding each field by reference to its `let_name`.
+fn variant_pat(enum_name: &Ident, variant: &Ident, kind: &Fields, fields: &[Field]) -> TokenStream {
+    let lets = fields.iter().map(|field| &field.let_name);
+
+    match *kind {
+        Fields::Named(..) => {
+            let names = fields.iter().map(|field| field.name.unwrap());
+            quote!(#enum_name::#variant { #(#names: ref #lets),* })
+        },
+        Fields::Unnamed(..) => quote!(#enum_name::#variant(#(ref #lets),*)),
+        Fields::Unit => quote!(#enum_name::#variant),
+    }
+}
+
+/// Builds the expression that constructs `path` from the decoded fields.
+fn construct(path: &TokenStream, kind: &Fields, fields: &[Field]) -> TokenStream {
+    let lets = fields.iter().map(|field| &field.let_name);
+
+    match *kind {
+        Fields::Named(..) => {
+            let names = fields.iter().map(|field| field.name.unwrap());
+            quote!(#path { #(#names: #lets),* })
+        },
+        Fields::Unnamed(..) => quote!(#path(#(#lets),*)),
+        Fields::Unit => path.clone(),
+    }
+}
+
+/// The discriminant of each variant, following the implicit numbering rules of Rust enums.
+///
+/// A `discriminant` attribute overrides the variant's own, and later variants are numbered from it.
+fn discriminants<'a, I: IntoIterator<Item=&'a syn::Variant>>(variants: I, attrs: &[Vec<VariantAttribute>]) -> Vec<TokenStream> {
+    let mut next = quote!(0);
+    variants.into_iter().zip(attrs).map(|(variant, attrs)| {
+        let disc = match (variant_discriminant(attrs), &variant.discriminant) {
+            (Some(disc), _) => quote!(#disc),
+            (None, &Some((_, ref disc))) => quote!(#disc),
+            (None, &None) => next.clone(),
+        };
+        next = quote!((#disc) + 1);
+        disc
+    }).collect()
+}
+
+const TAG_TYPES: &[&str] = &["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"];
+
+/// Whether an item is `#[repr(C)]`.
+fn repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|attr| attr.path().is_ident("repr")).filter_map(|attr|
+        attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated).ok()
+    ).flat_map(|items| items.into_iter()).any(|item| match item {
+        Meta::Path(path) => path.is_ident("C"),
+        _ => false,
+    })
+}
+
+/// The integer type of an enum's `#[repr(..)]`, if any.
+fn enum_repr(attrs: &[Attribute]) -> Option<Ident> {
+    attrs.iter().filter(|attr| attr.path().is_ident("repr")).filter_map(|attr|
+        attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated).ok()
+    ).flat_map(|items| items.into_iter()).filter_map(|item| match item {
+        Meta::Path(path) => path.get_ident().filter(|ident| TAG_TYPES.iter().any(|ty| *ident == ty)).cloned(),
+        _ => None,
+    }).next()
+}
+
+/// The integer type of an enum's encoded discriminant.
+///
+/// Taken from `#[nue(tag_type = "..")]`, or the enum's `#[repr(..)]`, and otherwise `u8`.
+fn enum_tag_type(item_attrs: &[Attribute], attrs: &[ItemAttribute]) -> Type {
+    let mut tag_type = enum_repr(item_attrs);
+
+    for attr in attrs {
+        if let ItemAttribute::TagType(ref ty) = *attr {
+            tag_type = Some(ty.clone());
+        }
+    }
+
+    let tag_type = tag_type.unwrap_or_else(|| Ident::new("u8", Span::call_site()));
+    parse_quote!(#tag_type)
+}
+
+fn expr_is_false(expr: &Expr) -> bool {
+    match *expr {
+        Expr::Lit(ref lit) => match lit.lit {
+            Lit::Bool(ref value) => !value.value,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Generates statements that encode each field in turn.
+///
+/// Struct fields are read through `self`, while `bound` variant fields have
+/// been bound by reference to their `let_name`.
+fn encode_fields(cx: &mut Context, fields: &[Field], defaults: &Defaults, bound: bool, needs_seek: &mut bool) -> Vec<TokenStream> {
+    let access = if bound { SelfAccess::refs(fields) } else { SelfAccess::Self_ };
+    let attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_enc", &access)).collect::<Vec<_>>();
+    let checksum = checksum_range(cx, fields, &attrs);
+    let groups = limit_groups(cx, fields, &attrs);
+    let byte_order = defaults.byte_order.as_ref().map(|&(span, ref value)| attr_expr(cx, span, &access, value));
+
+    let mut encoders = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        if let Some(bits) = field_bits(cx, field, &attrs) {
+            let expr = encoded_value(field, &attrs, bound);
+            let write = if type_is_signed(field.ty) {
+                quote!(__bits.write_signed_bits(*(#expr) as i64, #bits)?;)
+            } else {
+                quote!(__bits.write_bits(*(#expr) as u64, #bits)?;)
+            };
+            return (TokenStream::new(), Some(write))
+        }
+
+        let field_str = field.label();
+        let field_type = field.ty;
+        let expr = encoded_value(field, &attrs, bound);
+        let (wrapper, order) = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None }) {
+            Some(_) => (None, None),
+            None => (field_wrapper(&attrs, defaults, field_type), field_byte_order(&attrs, byte_order.as_ref(), field_type)),
+        };
+
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        // Decoding reads at least one element, so there must be a last one
+        let until = find_attr(&attrs, |attr| match *attr { FieldAttribute::Until(ref expr) => Some(expr), _ => None }).map(|until| quote! {
+            if (#expr).is_empty() {
+                return Err(::std::convert::From::from(::nue::Error::Validation {
+                    field: Some(#field_str),
+                    message: concat!("the last element must satisfy ", stringify!(#until)).to_owned(),
+                }));
+            }
+            for (__i, last) in (#expr).iter().enumerate() {
+                if (#until) != (__i + 1 == (#expr).len()) {
+                    return Err(::std::convert::From::from(::nue::Error::Validation {
+                        field: Some(#field_str),
+                        message: concat!("only the last element may satisfy ", stringify!(#until)).to_owned(),
+                    }));
+                }
+            }
+        });
+        let is_checksum = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Checksum(..)));
+        let statement = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None }) {
+            _ if is_checksum => {
+                let encode = encode_value(quote!(&__digest), field_type, wrapper, order);
+                quote! {
+                    {
+                        let __digest: #field_type = __w.digest();
+                        #encode?;
+                    }
+                }
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                let (wrapper, order) = (field_wrapper(&attrs, defaults, &item), field_byte_order(&attrs, byte_order.as_ref(), &item));
+                let encode_item = encode_value(quote!(__item), &item, wrapper.clone(), order);
+                let encode_terminator = encode_value(quote!(&__terminator), &item, wrapper, order);
+                quote! {
+                    {
+                        let __terminator: #item = #terminator;
+                        for __item in (#expr).iter() {
+                            if *__item == __terminator {
+                                return Err(::std::convert::From::from(::nue::Error::Validation {
+                                    field: Some(#field_str),
+                                    message: "an element equals the terminator".to_owned(),
+                                }));
+                            }
+                            #encode_item?;
+                        }
+                        #encode_terminator?;
+                    }
+                }
+            },
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote!(#with(#expr, __w)?;)
+            },
+            Some(tag) => quote! {
+                ::nue::Encode::encode_options(#expr, __w, ::nue::TagOptions { tag: Some((#tag) as _) })?;
+            },
+            None => {
+                let encode = encode_value(expr, field_type, wrapper, order);
+                quote!(#encode?;)
+            },
+        };
+        let mut statement = until.into_iter().chain(Some(statement)).collect::<Vec<_>>();
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, encode_magic(&magic));
+        }
+
+        let mut cond = None;
+        let through = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Through(_)));
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) |
+                FieldAttribute::Checksum(_) | FieldAttribute::Over(_) | FieldAttribute::Bits(_) | FieldAttribute::Through(_) | FieldAttribute::Value(_) |
+                FieldAttribute::Varint => (),
+                FieldAttribute::Default(_) | FieldAttribute::RestorePosition | FieldAttribute::IfEof(_) | FieldAttribute::Position => (),
+                // The count is otherwise truncated by `encoded_value`
+                FieldAttribute::CountOf(target, len) => statement.insert(0, quote! {
+                    if <#field_type as ::std::convert::TryFrom<_>>::try_from(#len).is_err() {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some(#field_str),
+                            message: format!("the length of {} doesn't fit in {}", #target, stringify!(#field_type)),
+                        }));
+                    }
+                }),
+                FieldAttribute::Align(expr) => {
+                    *needs_seek = true;
+                    statement.insert(0, quote!(::nue::SeekAlignExt::align_to(__w, #expr)?;));
+                },
+                FieldAttribute::Skip(expr) => {
+                    *needs_seek = true;
+                    statement.insert(0, quote!(::nue::SeekForward::seek_forward(__w, #expr)?;));
+                },
+                // The limit of a group applies to all of its fields
+                FieldAttribute::Limit(_) if through => (),
+                FieldAttribute::Limit(expr) => statement.insert(0, quote!(let __w = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__w), #expr);)),
+                FieldAttribute::Consume(expr) => statement.push(quote! {
+                    if #expr {
+                        let _ = match ::std::io::copy(&mut ::std::io::repeat(0), __w) {
+                            ::std::result::Result::Err(ref err) if err.kind() == ::std::io::ErrorKind::WriteZero => Ok(0),
+                            res => res,
+                        }?;
+                    }
+                }),
+                FieldAttribute::Assert(expr) => statement.insert(0, quote! {
+                    if !(#expr) {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some(#field_str),
+                            message: concat!("assertion ", stringify!(#expr), " failed").to_owned(),
+                        }));
+                    }
+                }),
+            }
+        }
+
+        let statement = match cond {
+            Some(ref cond) if expr_is_false(cond) => TokenStream::new(),
+            Some(cond) => quote! {
+                if #cond {
+                    #(#statement)*
+                }
+            },
+            None => quote!({ #(#statement)* }),
+        };
+
+        // Fields of a group each take what remains of its limit
+        let statement = match field_group(&groups, i) {
+            Some(last) => {
+                let remaining = if last { TokenStream::new() } else { quote!(__group_limit = ::nue::Take::limit(__w);) };
+                quote!({
+                    let __w = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__w), __group_limit);
+                    #statement
+                    #remaining
+                })
+            },
+            None => statement,
+        };
+
+        (statement, None)
+    }).collect::<Vec<_>>();
+
+    let mut scopes = groups.into_iter().map(|(start, _, limit)| (start, quote!(let mut __group_limit = (#limit) as u64;))).collect::<Vec<_>>();
+    if let Some((start, hasher)) = checksum {
+        *needs_seek = true;
+        scopes.push((start, quote!(let __w = &mut ::nue::HashWrite::new(__w, #hasher);)));
+    }
+    scopes.sort_by_key(|scope| Reverse(scope.0));
+    for (start, scope) in scopes {
+        encoders.insert(start, (scope, None));
+    }
+
+    encode_bit_fields(encoders)
+}
+
+/// Replaces each run of adjacent `bits` fields with a single statement that writes
+/// them through a `BitWriter`, padding the final byte with zero bits.
+fn encode_bit_fields(fields: Vec<(TokenStream, Option<TokenStream>)>) -> Vec<TokenStream> {
+    fn flush(stmts: &mut Vec<TokenStream>, run: &mut Vec<TokenStream>) {
+        if run.is_empty() {
+            return
+        }
+
+        let writes = run.drain(..);
+        stmts.push(quote! {
+            {
+                let mut __bits = ::nue::BitWriter::new(&mut *__w);
+                #(#writes)*
+                __bits.align()?;
+            }
+        });
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, bits) in fields {
+        match bits {
+            Some(bits) => run.push(bits),
+            None => {
+                flush(&mut stmts, &mut run);
+                stmts.push(stmt);
+            },
+        }
+    }
+    flush(&mut stmts, &mut run);
+
+    stmts
+}
+
+/// The width of a `bits` field, which can't be combined with other attributes.
+fn field_bits(cx: &mut Context, field: &Field, attrs: &[FieldAttribute]) -> Option<u32> {
+    let bits = attrs.iter().filter_map(|attr| match *attr {
+        FieldAttribute::Bits(bits) => Some(bits),
+        _ => None,
+    }).next_back();
+
+    if bits.is_some() && attrs.len() > 1 {
+        cx.span_err(field.ty.span(), "bits can't be combined with other attributes");
+    }
+
+    bits
+}
+
+/// A reference to the value encoded for a field, after `value`, `count_of` and `unmap`.
+fn encoded_value(field: &Field, attrs: &[FieldAttribute], bound: bool) -> TokenStream {
+    let field_type = field.ty;
+    let expr = match find_attr(attrs, |attr| match *attr { FieldAttribute::Value(ref expr) => Some(expr), _ => None }) {
+        Some(value) => quote!(&{ let __value: #field_type = #value; __value }),
+        None if bound => {
+            let let_name = &field.let_name;
+            quote!(#let_name)
+        },
+        None => match field.name {
+            Some(name) => quote!(&self.#name),
+            None => {
+                let index = syn::Index::from(field.index);
+                quote!(&self.#index)
+            },
+        },
+    };
+
+    let expr = match find_attr(attrs, |attr| match *attr { FieldAttribute::CountOf(_, ref len) => Some(len), _ => None }) {
+        Some(len) => quote!(&((#len) as #field_type)),
+        None => expr,
+    };
+
+    match find_attr(attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None }) {
+        Some(unmap) => quote!(&((#unmap)(#expr))),
+        None => expr,
+    }
+}
+
+/// Generates statements that add the encoded size of each field to `__size`.
+fn size_fields(cx: &mut Context, fields: &[Field], bound: bool) -> Vec<TokenStream> {
+    let access = if bound { SelfAccess::refs(fields) } else { SelfAccess::Self_ };
+    let attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_enc", &access)).collect::<Vec<_>>();
+    let groups = limit_groups(cx, fields, &attrs);
+
+    // Each run of `bits` fields is counted once it ends, rounded up to whole bytes
+    let mut bits = 0u64;
+    let mut sizes = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        let run = match field_bits(cx, field, &attrs) {
+            Some(width) => {
+                bits += width as u64;
+                return TokenStream::new()
+            },
+            None => ::std::mem::replace(&mut bits, 0),
+        };
+        let expr = encoded_value(field, &attrs, bound);
+        let field_type = field.ty;
+
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        let varint = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Varint));
+        let size = match find_attr(&attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None }) {
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote!({
+                    let mut __null = ::nue::Null::new();
+                    let _ = #with(#expr, &mut __null);
+                    __null.len()
+                })
+            },
+            _ if terminator.is_some() && varint => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                quote!({
+                    let __terminator: #item = #terminator;
+                    (#expr).iter().map(|__item| ::nue::EncodedSize::encoded_size(&::nue::Varint(*__item))).sum::<u64>() +
+                        ::nue::EncodedSize::encoded_size(&::nue::Varint(__terminator))
+                })
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                quote!({
+                    let __terminator: #item = #terminator;
+                    ::nue::EncodedSize::encoded_size(#expr) + ::nue::EncodedSize::encoded_size(&__terminator)
+                })
+            },
+            Some(tag) => quote!(::nue::EncodedSize::encoded_size_options(#expr, ::nue::TagOptions { tag: Some((#tag) as _) })),
+            None if varint => quote!(::nue::EncodedSize::encoded_size(&::nue::Varint(*#expr))),
+            None => quote!(::nue::EncodedSize::encoded_size(#expr)),
+        };
+        let mut statement = vec![quote!(__size += #size;)];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, quote!(__size += (#magic).len() as u64;));
+        }
+
+        let mut cond = None;
+        let group = field_group(&groups, i);
+        let through = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Through(_)));
+        let limit = !through && attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Limit(_)));
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Align(expr) => statement.insert(0, quote!(__size += ((#expr) as u64 - __size % (#expr) as u64) % (#expr) as u64;)),
+                FieldAttribute::Skip(expr) => statement.insert(0, quote!(__size += (#expr) as u64;)),
+                FieldAttribute::Limit(expr) if limit => statement.insert(0, quote!(let __limit = __size + (#expr) as u64;)),
+                // Padding is written up to the limit, and without one only stops at the end of the stream
+                FieldAttribute::Consume(expr) => if limit {
+                    statement.push(quote!(if #expr { __size = __limit; }))
+                } else if group.is_some() {
+                    statement.push(quote!(if #expr { __size = __group_end; }))
+                },
+                _ => (),
+            }
+        }
+
+        if limit {
+            statement.push(quote!(__size = ::std::cmp::min(__size, __limit);));
+        }
+
+        let statement = match cond {
+            Some(ref cond) if expr_is_false(cond) => TokenStream::new(),
+            Some(cond) => quote! {
+                if #cond {
+                    #(#statement)*
+                }
+            },
+            None => quote!({ #(#statement)* }),
+        };
+
+        let statement = match group {
+            Some(true) => quote!({
+                #statement
+                __size = ::std::cmp::min(__size, __group_end);
+            }),
+            _ => statement,
+        };
+
+        if run > 0 {
+            let run = run.div_ceil(8);
+            quote!({ __size += #run; #statement })
+        } else {
+            statement
+        }
+    }).collect::<Vec<_>>();
+
+    if bits > 0 {
+        let run = bits.div_ceil(8);
+        sizes.push(quote!(__size += #run;));
+    }
+
+    for (start, _, limit) in groups.into_iter().rev() {
+        sizes.insert(start, quote!(let __group_end = __size + (#limit) as u64;));
+    }
+
+    sizes
+}
+
+/// Gives fields counted by a `count_of` field a `count` of its decoded value.
+fn count_of_fields(cx: &mut Context, fields: &[Field], attrs: &mut [Vec<FieldAttribute>]) {
+    let counts = fields.iter().zip(attrs.iter()).flat_map(|(field, attrs)| attrs.iter().filter_map(move |attr| match *attr {
+        FieldAttribute::CountOf(ref target, _) => Some((target.clone(), field.let_name.clone())),
+        _ => None,
+    })).collect::<Vec<_>>();
+    for (target, count) in counts {
+        match fields.iter().position(|field| field.label() == target) {
+            Some(i) => if !attrs[i].iter().any(|attr| matches!(*attr, FieldAttribute::Count(..))) {
+                attrs[i].push(FieldAttribute::Count(parse_quote!(#count)))
+            },
+            None => cx.span_err(fields[0].ty.span(), &format!("count_of refers to unknown field `{}`", target)),
+        }
+    }
+}
+
+/// The wire layout of a plain `Pod` field: its local, label, type, and endian wrapper.
+type PodField = (Ident, String, Type, Option<TokenStream>);
+
+/// Generates statements that decode each field into its `let_name`.
+///
+/// Errors are attributed to the failing field of `type_name`, along with its offset from the start of the value.
+fn decode_fields(cx: &mut Context, type_name: &str, fields: &[Field], defaults: &Defaults, coalesce: bool, seeking: &mut Seeking) -> Vec<TokenStream> {
+    let mut attrs = fields.iter().map(|field| field_attrs(cx, field.attrs, "nue_dec", &SelfAccess::Locals)).collect::<Vec<_>>();
+    count_of_fields(cx, fields, &mut attrs);
+
+    let checksum = checksum_range(cx, fields, &attrs);
+    let groups = limit_groups(cx, fields, &attrs);
+    let byte_order = defaults.byte_order.as_ref().map(|&(span, ref value)| attr_expr(cx, span, &SelfAccess::Locals, value));
+
+    let mut decoders = fields.iter().zip(attrs).enumerate().map(|(i, (field, attrs))| {
+        let let_name = &field.let_name;
+        let field_str = field.label();
+        if let Some(bits) = field_bits(cx, field, &attrs) {
+            return (TokenStream::new(), None, Some((let_name.clone(), field_str, field.ty.clone(), bits)))
+        }
+
+        let (mut cond, mut cond_default, mut restore, mut if_eof) = (None, None, false, None);
+        let field_type = field.ty;
+
+        let wrapper = field_wrapper(&attrs, defaults, field_type);
+        let order = field_byte_order(&attrs, byte_order.as_ref(), field_type);
+        let with = find_attr(&attrs, |attr| match *attr { FieldAttribute::With(ref path) => Some(path), _ => None });
+        let map = find_attr(&attrs, |attr| match *attr { FieldAttribute::Map(ref expr) => Some(expr), _ => None });
+        let terminator = find_attr(&attrs, |attr| match *attr { FieldAttribute::Terminator(ref expr) => Some(expr), _ => None });
+        let until = find_attr(&attrs, |attr| match *attr { FieldAttribute::Until(ref expr) => Some(expr), _ => None });
+        let is_checksum = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Checksum(..)));
+        let position = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Position));
+        let value = match decode_options_expr(&attrs, field_type) {
+            _ if position => quote!(::nue::Tell::tell(__r).map(|__pos| __pos as #field_type)),
+            _ if until.is_some() => {
+                let (until, item) = (until.unwrap(), vec_item_type(cx, field_type));
+                let decode_item = decode_value(&item, field_wrapper(&attrs, defaults, &item), field_byte_order(&attrs, byte_order.as_ref(), &item));
+                quote!({
+                    let mut __vec = ::std::vec::Vec::new();
+                    loop {
+                        let __item: #item = #decode_item?;
+                        __vec.push(__item);
+                        let last = &__vec[__vec.len() - 1];
+                        if #until {
+                            break
+                        }
+                    }
+                    Ok::<_, ::std::io::Error>(__vec)
+                })
+            },
+            _ if terminator.is_some() => {
+                let (terminator, item) = (terminator.unwrap(), vec_item_type(cx, field_type));
+                let decode_item = decode_value(&item, field_wrapper(&attrs, defaults, &item), field_byte_order(&attrs, byte_order.as_ref(), &item));
+                quote!({
+                    let __terminator: #item = #terminator;
+                    let mut __vec = ::std::vec::Vec::new();
+                    loop {
+                        let __item: #item = #decode_item?;
+                        if __item == __terminator {
+                            break
+                        }
+                        __vec.push(__item);
+                    }
+                    Ok::<_, ::std::io::Error>(__vec)
+                })
+            },
+            _ if with.is_some() => {
+                let with = with.unwrap();
+                quote!(#with(__r))
+            },
+            Some(options) => quote!(::nue::Decode::decode_options(__r, #options)),
+            // The wire type of a mapped field is inferred from its conversion
+            None if map.is_some() => quote!(::nue::Decode::decode(__r)),
+            None => decode_value(field_type, wrapper.clone(), order),
+        };
+        let statement = match map {
+            Some(map) => quote!(let #let_name: #field_type = (#map)((#value)?);),
+            None => quote!(let #let_name: #field_type = (#value)?;),
+        };
+        let mut statement = vec![statement];
+        if let Some(magic) = find_attr(&attrs, |attr| match *attr { FieldAttribute::Magic(ref expr) => Some(expr), _ => None }) {
+            statement.insert(0, decode_magic(&magic));
+        }
+        if is_checksum {
+            let index = statement.len() - 1;
+            statement.insert(index, quote!(let __digest = __r.digest();));
+            statement.push(quote! {
+                if #let_name != __digest {
+                    return Err(::std::convert::From::from(::nue::Error::Validation {
+                        field: Some(#field_str),
+                        message: "checksum mismatch".to_owned(),
+                    }));
+                }
+            });
+        }
+
+        let plain = attrs.iter().all(|attr| matches!(*attr, FieldAttribute::Endian(_)));
+        let group = field_group(&groups, i);
+        let pod = if coalesce && plain && group.is_none() && order.is_none() && is_pod_type(field_type) {
+            Some((let_name.clone(), field_str.clone(), field_type.clone(), wrapper))
+        } else {
+            None
+        };
+
+        let through = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Through(_)));
+
+        for attr in attrs {
+            match attr {
+                FieldAttribute::Cond(expr) => cond = Some(expr),
+                FieldAttribute::Default(expr) => cond_default = Some(expr),
+                FieldAttribute::Tag(_) | FieldAttribute::Count(_) | FieldAttribute::CountOf(..) | FieldAttribute::Magic(_) | FieldAttribute::Endian(_) | FieldAttribute::With(_) | FieldAttribute::Map(_) | FieldAttribute::Terminator(_) | FieldAttribute::Until(_) |
+                FieldAttribute::Checksum(_) | FieldAttribute::Over(_) | FieldAttribute::Position | FieldAttribute::Bits(_) | FieldAttribute::Through(_) |
+                FieldAttribute::Value(_) | FieldAttribute::Varint => (),
+                FieldAttribute::RestorePosition => {
+                    seeking.require(Seeking::Rewind);
+                    restore = true;
+                },
+                FieldAttribute::IfEof(expr) => {
+                    seeking.require(Seeking::Rewind);
+                    if_eof = Some(expr);
+                },
+                FieldAttribute::Align(expr) => {
+                    seeking.require(Seeking::Forward);
+                    statement.insert(0, quote!(::nue::SeekAlignExt::align_to(__r, #expr)?;));
+                },
+                FieldAttribute::Skip(expr) => {
+                    seeking.require(Seeking::Forward);
+                    statement.insert(0, quote!(::nue::SeekForward::seek_forward(__r, #expr)?;));
+                },
+                // The limit of a group applies to all of its fields
+                FieldAttribute::Limit(_) if through => (),
+                FieldAttribute::Limit(expr) => statement.insert(0, quote!(let __r = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__r), #expr);)),
+                FieldAttribute::Consume(expr) => statement.push(quote! {
+                    if #expr {
+                        ::std::io::copy(__r, &mut ::std::io::sink())?;
+                    }
+                }),
+                FieldAttribute::Assert(expr) => statement.push(quote! {
+                    if !(#expr) {
+                        return Err(::std::convert::From::from(::nue::Error::Validation {
+                            field: Some(#field_str),
+                            message: concat!("assertion ", stringify!(#expr), " failed").to_owned(),
+                        }));
+                    }
+                }),
+            }
+        }
+
+        let statement = match cond {
+            Some(cond) => {
+                let default = cond_default.map(|default| quote!(#default)).unwrap_or_else(|| quote!(::std::default::Default::default()));
+
+                if expr_is_false(&cond) {
+                    quote!(let #let_name = #default;)
+                } else {
+                    quote! {
+                        let #let_name = if #cond {
+                            #(#statement)*
+                            #let_name
+                        } else {
+                            #default
+                        };
+                    }
+                }
+            },
+            None => quote!(let #let_name = { #(#statement)* #let_name };),
+        };
+
+        let statement = if restore {
+            quote! {
+                let #let_name = {
+                    let __restore = ::nue::Tell::tell(__r)?;
+                    #statement
+                    ::nue::SeekAbsolute::seek_absolute(__r, __restore)?;
+                    #let_name
+                };
+            }
+        } else {
+            statement
+        };
+
+        let statement = match if_eof {
+            Some(default) => quote! {
+                let #let_name = {
+                    let __pos = ::nue::Tell::tell(__r)?;
+                    if ::nue::ReadExactExt::read_exact_eof(__r, &mut [0u8; 1])? == 0 {
+                        #default
+                    } else {
+                        ::nue::SeekAbsolute::seek_absolute(__r, __pos)?;
+                        #statement
+                        #let_name
+                    }
+                };
+            },
+            None => statement,
+        };
+
+        let statement = quote! {
+            let #let_name = {
+                let __offset = ::nue::Tell::tell(__r).ok();
+                let __result = (|| -> ::std::io::Result<#field_type> { #statement Ok(#let_name) })();
+                match __result {
+                    Ok(__value) => __value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field(#type_name, #field_str, __offset))),
+                }
+            };
+        };
+
+        // Fields of a group each take what remains of its limit
+        let statement = match group {
+            Some(last) => {
+                let remaining = if last { TokenStream::new() } else { quote!(__group_limit = ::nue::Take::limit(__r);) };
+                quote! {
+                    let #let_name = {
+                        let __r = &mut ::nue::Take::new(::std::borrow::BorrowMut::borrow_mut(__r), __group_limit);
+                        #statement
+                        #remaining
+                        #let_name
+                    };
+                }
+            },
+            None => statement,
+        };
+
+        (statement, pod, None)
+    }).collect::<Vec<_>>();
+
+    let mut scopes = groups.into_iter().map(|(start, _, limit)| (start, quote!(let mut __group_limit = (#limit) as u64;))).collect::<Vec<_>>();
+    if let Some((start, hasher)) = checksum {
+        seeking.require(Seeking::Forward);
+        scopes.push((start, quote!(let __r = &mut ::nue::HashRead::new(__r, #hasher);)));
+    }
+    scopes.sort_by_key(|scope| Reverse(scope.0));
+    for (start, scope) in scopes {
+        decoders.insert(start, (scope, None, None));
+    }
+
+    coalesce_pod_fields(type_name, decode_bit_fields(type_name, decoders))
+}
+
+/// The local, label, type, and width of a `bits` field.
+type BitField = (Ident, String, Type, u32);
+
+/// Replaces each run of adjacent `bits` fields with a single statement that reads
+/// them through a `BitReader`, skipping any bits left over in the final byte.
+fn decode_bit_fields(type_name: &str, fields: Vec<(TokenStream, Option<PodField>, Option<BitField>)>) -> Vec<(TokenStream, Option<PodField>)> {
+    fn flush(type_name: &str, stmts: &mut Vec<(TokenStream, Option<PodField>)>, run: &mut Vec<BitField>) {
+        if run.is_empty() {
+            return
+        }
+
+        let mut offset = 0;
+        let reads = run.iter().map(|&(ref let_name, ref field_str, ref ty, bits)| {
+            let value = if type_name_is(ty, "bool") {
+                quote!(__value != 0)
+            } else {
+                quote!(__value as #ty)
+            };
+            let read = if type_is_signed(ty) { quote!(read_signed_bits) } else { quote!(read_bits) };
+            let byte = (offset / 8) as u64;
+            offset += bits;
+
+            quote! {
+                let #let_name: #ty = match __bits.#read(#bits) {
+                    Ok(__value) => #value,
+                    Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field(#type_name, #field_str, __offset.map(|__offset| __offset + #byte)))),
+                };
+            }
+        }).collect::<Vec<_>>();
+        let lets = run.drain(..).map(|(let_name, _, _, _)| let_name).collect::<Vec<_>>();
+
+        stmts.push((quote! {
+            let (#(#lets,)*) = {
+                let __offset = ::nue::Tell::tell(__r).ok();
+                let mut __bits = ::nue::BitReader::new(&mut *__r);
+                #(#reads)*
+                (#(#lets,)*)
+            };
+        }, None));
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, pod, bits) in fields {
+        match bits {
+            Some(bits) => run.push(bits),
+            None => {
+                flush(type_name, &mut stmts, &mut run);
+                stmts.push((stmt, pod));
+            },
+        }
+    }
+    flush(type_name, &mut stmts, &mut run);
+
+    stmts
+}
+
+/// Whether the last segment of a path type is `name`.
+fn type_name_is(ty: &Type, name: &str) -> bool {
+    match *ty {
+        Type::Path(ref path) if path.qself.is_none() => path.path.segments.last().map(|segment| segment.ident == name).unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Whether a type is a primitive signed integer, whose bit fields are sign extended.
+fn type_is_signed(ty: &Type) -> bool {
+    ["i8", "i16", "i32", "i64", "isize"].iter().any(|name| type_name_is(ty, name))
+}
+
+/// The element type of a `Vec<T>` field.
+fn vec_item_type(cx: &mut Context, ty: &Type) -> Type {
+    let item = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) if segment.ident == "Vec" => match segment.arguments {
+                syn::PathArguments::AngleBracketed(ref args) => args.args.iter().filter_map(|arg| match *arg {
+                    syn::GenericArgument::Type(ref ty) => Some(ty.clone()),
+                    _ => None,
+                }).next(),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    };
+
+    item.unwrap_or_else(|| {
+        cx.span_err(ty.span(), "expected a `Vec<T>` field");
+        parse_quote!(())
+    })
+}
+
+/// Whether a field type is syntactically known to be `Pod`, and so decodes as its raw bytes.
+fn is_pod_type(ty: &Type) -> bool {
+    const PODS: &[&str] = &[
+        "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128",
+        "usize", "isize", "f32", "f64", "Le", "Be", "Native",
+    ];
+
+    match *ty {
+        Type::Path(ref path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => PODS.iter().any(|name| segment.ident == name),
+            None => false,
+        },
+        Type::Array(ref array) => is_pod_type(&array.elem),
+        Type::Paren(ref paren) => is_pod_type(&paren.elem),
+        _ => false,
+    }
+}
+
+/// The seeking that decoding requires of the stream, from least to most capable.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+enum Seeking {
+    None,
+    Forward,
+    /// Seeking backward, through a buffer of what has been read
+    Rewind,
+}
+
+impl Seeking {
+    fn require(&mut self, seeking: Seeking) {
+        if seeking > *self {
+            *self = seeking
+        }
+    }
+}
+
+/// Item-level settings that apply to every field.
+struct Defaults {
+    /// The endian wrapper used to code primitive fields
+    endian: Option<&'static str>,
+    /// The `byte_order` expression, rewritten for the fields' access to `self` by each derive
+    byte_order: Option<(Span, String)>,
+}
+
+impl Defaults {
+    fn new(attrs: &[ItemAttribute]) -> Self {
+        let mut defaults = Defaults {
+            endian: None,
+            byte_order: None,
+        };
+
+        for attr in attrs {
+            match *attr {
+                ItemAttribute::Endian(endian) => defaults.endian = Some(endian),
+                ItemAttribute::ByteOrder(span, ref value) => defaults.byte_order = Some((span, value.clone())),
+                _ => (),
+            }
+        }
+
+        defaults
+    }
+}
+
+/// Parses a byte order into the name of its endian wrapper type.
+fn endian_name(value: &str) -> Option<&'static str> {
+    match value {
+        "little" | "le" => Some("Le"),
+        "big" | "be" => Some("Be"),
+        "native" => Some("Native"),
+        _ => None,
+    }
+}
+
+/// The endian wrapper for a primitive type coded in the given byte order, if it needs one.
+fn endian_wrapper(endian: Option<&'static str>, ty: &Type) -> Option<TokenStream> {
+    const PRIMITIVES: &[&str] = &["u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "f32", "f64"];
+
+    match endian {
+        Some(endian) if PRIMITIVES.iter().any(|name| type_name_is(ty, name)) => {
+            let endian = Ident::new(endian, Span::call_site());
+            Some(quote!(::nue::#endian<#ty>))
+        },
+        _ => None,
+    }
+}
+
+/// An expression that encodes the referenced value to `__w`, through an endian wrapper if provided,
+/// or in the byte order selected by `order` at runtime.
+fn encode_value(value: TokenStream, ty: &Type, wrapper: Option<TokenStream>, order: Option<&Expr>) -> TokenStream {
+    if let Some(order) = order {
+        let little = encode_value(value.clone(), ty, endian_wrapper(Some("Le"), ty), None);
+        let big = encode_value(value, ty, endian_wrapper(Some("Be"), ty), None);
+        return byte_order_match(order, little, big)
+    }
+
+    match wrapper {
+        Some(wrapper) => quote!(::nue::Encode::encode(&<#wrapper as ::std::convert::From<#ty>>::from(*#value), __w)),
+        None => quote!(::nue::Encode::encode(#value, __w)),
+    }
+}
+
+/// An expression that decodes a value from `__r`, through an endian wrapper if provided,
+/// or in the byte order selected by `order` at runtime.
+fn decode_value(ty: &Type, wrapper: Option<TokenStream>, order: Option<&Expr>) -> TokenStream {
+    if let Some(order) = order {
+        let little = decode_value(ty, endian_wrapper(Some("Le"), ty), None);
+        let big = decode_value(ty, endian_wrapper(Some("Be"), ty), None);
+        return byte_order_match(order, little, big)
+    }
+
+    match wrapper {
+        Some(wrapper) => quote!(<#wrapper as ::nue::Decode>::decode(__r).map(|__value| __value.get())),
+        None => quote!(::nue::Decode::decode(__r)),
+    }
+}
+
+/// Selects between the little and big endian forms of an expression by a runtime `Endianness`.
+fn byte_order_match(order: &Expr, little: TokenStream, big: TokenStream) -> TokenStream {
+    quote! {
+        match #order {
+            ::nue::Endianness::Little => #little,
+            ::nue::Endianness::Big => #big,
+        }
+    }
+}
+
+/// Replaces each run of adjacent plain `Pod` fields with a single `read_exact`
+/// into a stack buffer that is then split per field.
+///
+/// Fields coded in a specific byte order are copied out as their endian wrapper type.
+/// A short read is attributed to the field that the stream ended in.
+fn coalesce_pod_fields(type_name: &str, fields: Vec<(TokenStream, Option<PodField>)>) -> Vec<TokenStream> {
+    fn flush(type_name: &str, stmts: &mut Vec<TokenStream>, run: &mut Vec<(TokenStream, PodField)>) {
+        if run.len() < 2 {
+            stmts.extend(run.drain(..).map(|(stmt, _)| stmt));
+            return
+        }
+
+        let first_str = (run[0].1).1.clone();
+        let mut offset = quote!(0);
+        let mut fields = Vec::new();
+        let mut ranges = Vec::new();
+        for (_, (let_name, field_str, ty, wrapper)) in run.drain(..) {
+            let end = quote!(#offset + ::std::mem::size_of::<#ty>());
+            ranges.push((field_str, offset.clone(), end.clone()));
+            fields.push(match wrapper {
+                Some(wrapper) => quote!(let #let_name: #ty = <#wrapper as ::nue::Pod>::copy_from(&__pod_buf[#offset..#end]).get();),
+                None => quote!(let #let_name: #ty = ::nue::Pod::copy_from(&__pod_buf[#offset..#end]);),
+            });
+            offset = end;
+        }
+
+        let (last_str, last_start, _) = ranges.pop().unwrap();
+        let short_field = ranges.into_iter().rev().fold(quote!((#last_str, #last_start)), |rest, (field_str, start, end)| quote! {
+            if __read < #end { (#field_str, #start) } else { #rest }
+        });
+
+        stmts.push(quote! {
+            let mut __pod_buf = [0u8; #offset];
+            let __offset = ::nue::Tell::tell(__r).ok();
+            let __read = match ::nue::ReadExactExt::read_exact_eof(__r, &mut __pod_buf) {
+                Ok(__read) => __read,
+                Err(__err) => return Err(::std::convert::From::from(::nue::Error::from(__err).in_field(#type_name, #first_str, __offset))),
+            };
+            if __read < __pod_buf.len() {
+                let (__field, __start) = #short_field;
+                let __err = ::nue::Error::UnexpectedEof { needed: Some((__pod_buf.len() - __read) as u64) };
+                return Err(::std::convert::From::from(__err.in_field(#type_name, __field, __offset.map(|__offset| __offset + __start as u64))));
+            }
+        });
+        stmts.extend(fields);
+    }
+
+    let mut stmts = Vec::new();
+    let mut run = Vec::new();
+    for (stmt, pod) in fields {
+        match pod {
+            Some(pod) => run.push((stmt, pod)),
+            None => {
+                flush(type_name, &mut stmts, &mut run);
+                stmts.push(stmt);
+            },
+        }
+    }
+    flush(type_name, &mut stmts, &mut run);
+
+    stmts
+}
+
+/// How `self.field` in attribute expressions is rewritten.
+enum SelfAccess {
+    /// Left as is, for struct fields read through `self`
+    Self_,
+    /// Replaced with the local holding the decoded field
+    Locals,
+    /// Replaced with a dereference of the local bound to each of the named variant fields
+    Refs(Vec<String>),
+}
+
+impl SelfAccess {
+    fn refs(fields: &[Field]) -> Self {
+        let mut labels = fields.iter().map(|field| field.label()).collect::<Vec<_>>();
+        // Longer names first, so `self.ab` isn't rewritten as `self.a` followed by `b`
+        labels.sort_by_key(|label| Reverse(label.len()));
+        SelfAccess::Refs(labels)
+    }
+}
+
+/// The items of each attribute named `nue` or any of `meta_names`.
+fn meta_lists(attrs: &[Attribute], meta_names: &[&str]) -> Vec<syn::Result<Punctuated<Meta, Comma>>> {
+    attrs.iter().filter(|attr| attr.path().is_ident("nue") || meta_names.iter().any(|name| attr.path().is_ident(name)))
+        .map(|attr| attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated))
+        .collect()
+}
+
+/// The key and string value of a `key = "value"` attribute item.
+fn meta_str(meta: &Meta) -> Option<(String, String)> {
+    match *meta {
+        Meta::NameValue(ref meta) => match meta.value {
+            Expr::Lit(syn::ExprLit { lit: Lit::Str(ref value), .. }) =>
+                Some((meta.path.get_ident().map(|ident| ident.to_string()).unwrap_or_default(), value.value())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses an attribute expression, rewriting its accesses to `self` as described by `access`.
+fn attr_expr(cx: &mut Context, span: Span, access: &SelfAccess, value: &str) -> Expr {
+    let value = match *access {
+        SelfAccess::Self_ => value.into(),
+        SelfAccess::Locals => value.replace("self.", "__self_0"),
+        SelfAccess::Refs(ref labels) => labels.iter().fold(value.to_owned(), |value, label|
+            value.replace(&format!("self.{}", label), &format!("(*__self_0{})", label))
+        ),
+    };
+    cx.parse_expr(span, &value)
+}
+
+fn field_attrs(cx: &mut Context, attrs: &[Attribute], meta_name: &'static str, access: &SelfAccess) -> Vec<FieldAttribute> {
+    let mut attrs_out = Vec::new();
+    let mut since = None;
+    for items in meta_lists(attrs, &[meta_name]) {
+        let items = match items {
+            Ok(items) => items,
+            Err(err) => {
+                cx.errors.push(err);
+                continue
+            },
+        };
+
+        for attr in items.iter() {
+            let span = attr.span();
+            let attrs = &mut attrs_out;
+            match (meta_str(attr), attr) {
+                (Some((name, value)), _) => match &*name {
+                    "assert" => attrs.push(FieldAttribute::Assert(attr_expr(cx, span, access, &value))),
+                    "align" => attrs.push(FieldAttribute::Align(attr_expr(cx, span, access, &value))),
+                    "skip" => attrs.push(FieldAttribute::Skip(attr_expr(cx, span, access, &value))),
+                    "limit" => attrs.push(FieldAttribute::Limit(attr_expr(cx, span, access, &value))),
+                    "cond" => attrs.push(FieldAttribute::Cond(attr_expr(cx, span, access, &value))),
+                    "since" => since = Some(attr_expr(cx, span, access, &value)),
+                    "default" => attrs.push(FieldAttribute::Default(attr_expr(cx, span, access, &value))),
+                    "value" => attrs.push(FieldAttribute::Value(attr_expr(cx, span, access, &value))),
+                    "ignore" => {
+                        attrs.push(FieldAttribute::Cond(parse_quote!(false)));
+                        attrs.push(FieldAttribute::Default(attr_expr(cx, span, access, &value)));
+                    },
+                    "consume" => attrs.push(FieldAttribute::Consume(attr_expr(cx, span, access, &value))),
+                    "tag" => attrs.push(FieldAttribute::Tag(attr_expr(cx, span, access, &value))),
+                    "magic" => attrs.push(FieldAttribute::Magic(cx.parse_expr(span, &value))),
+                    "with" => {
+                        let function = if meta_name == "nue_enc" { "encode" } else { "decode" };
+                        attrs.push(FieldAttribute::With(cx.parse_expr(span, &format!("{}::{}", value, function))))
+                    },
+                    "map" => if meta_name != "nue_enc" {
+                        attrs.push(FieldAttribute::Map(attr_expr(cx, span, access, &value)))
+                    },
+                    "unmap" => if meta_name == "nue_enc" {
+                        attrs.push(FieldAttribute::Map(attr_expr(cx, span, access, &value)))
+                    },
+                    "endian" => match endian_name(&value) {
+                        Some(endian) => attrs.push(FieldAttribute::Endian(endian)),
+                        None => {
+                            cx.span_err(span, "endian must be \"little\", \"big\", or \"native\"");
+                            break
+                        },
+                    },
+                    "count" => attrs.push(FieldAttribute::Count(attr_expr(cx, span, access, &value))),
+                    "terminator" => attrs.push(FieldAttribute::Terminator(attr_expr(cx, span, access, &value))),
+                    "until" => attrs.push(FieldAttribute::Until(attr_expr(cx, span, access, &value))),
+                    "if_eof" => attrs.push(FieldAttribute::IfEof(attr_expr(cx, span, access, &value))),
+                    "checksum" => attrs.push(FieldAttribute::Checksum(match &*value {
+                        "crc32" => parse_quote!(::nue::Crc32::new()),
+                        ty => cx.parse_expr(span, &format!("<{} as ::std::default::Default>::default()", ty)),
+                    })),
+                    "over" => attrs.push(FieldAttribute::Over(value)),
+                    "through" => attrs.push(FieldAttribute::Through(value)),
+                    "bits" => match bit_width(value.parse().ok()) {
+                        Some(bits) => attrs.push(FieldAttribute::Bits(bits)),
+                        None => {
+                            cx.span_err(span, "bits must be a width from 1 to 64");
+                            break
+                        },
+                    },
+                    "count_of" => {
+                        let len = attr_expr(cx, span, access, &format!("self.{}.len()", value));
+                        attrs.push(FieldAttribute::CountOf(value, len))
+                    },
+                    _ => {
+                        cx.span_err(span, "invalid attribute key");
+                        break
+                    },
+                },
+                (None, Meta::NameValue(meta)) if meta.path.is_ident("bits") => {
+                    let bits = match meta.value {
+                        Expr::Lit(syn::ExprLit { lit: Lit::Int(ref lit), .. }) => bit_width(lit.base10_parse().ok()),
+                        _ => None,
+                    };
+                    match bits {
+                        Some(bits) => attrs.push(FieldAttribute::Bits(bits)),
+                        None => {
+                            cx.span_err(span, "bits must be a width from 1 to 64");
+                            break
+                        },
+                    }
+                },
+                (None, Meta::Path(path)) => match &*path.get_ident().map(|ident| ident.to_string()).unwrap_or_default() {
+                    "restore_position" => attrs.push(FieldAttribute::RestorePosition),
+                    "varint" => attrs.push(FieldAttribute::Varint),
+                    // A peeked field's bytes belong to the fields that follow it
+                    "peek" => attrs.push(if meta_name == "nue_enc" {
+                        FieldAttribute::Cond(parse_quote!(false))
+                    } else {
+                        FieldAttribute::RestorePosition
+                    }),
+                    "ignore" => attrs.push(FieldAttribute::Cond(parse_quote!(false))),
+                    // The position is only read when decoding
+                    "position" => attrs.push(if meta_name == "nue_enc" {
+                        FieldAttribute::Cond(parse_quote!(false))
+                    } else {
+                        FieldAttribute::Position
+                    }),
+                    // Read by `derive_type` through `skips_bound`
+                    "skip_bound" => (),
+                    _ => {
+                        cx.span_err(span, "invalid attribute key");
+                        break
+                    },
+                },
+                _ => {
+                    cx.span_err(span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+
+    // A version predicate must hold alongside any `cond`
+    if let Some(since) = since {
+        match attrs_out.iter_mut().rev().filter_map(|attr| match *attr { FieldAttribute::Cond(ref mut cond) => Some(cond), _ => None }).next() {
+            Some(cond) => *cond = parse_quote!((#cond) && (#since)),
+            None => attrs_out.push(FieldAttribute::Cond(since)),
+        }
+    }
+
+    attrs_out
+}
+
+/// A valid `bits` width.
+fn bit_width(bits: Option<u32>) -> Option<u32> {
+    bits.and_then(|bits| if (1..=64).contains(&bits) { Some(bits) } else { None })
+}
+
+/// The where predicates of the last `bound` attribute, if any.
+fn item_bound(cx: &mut Context, attrs: &[Attribute], meta_name: &'static str) -> Option<Vec<WherePredicate>> {
+    let bound = meta_lists(attrs, &[meta_name]).into_iter().filter_map(Result::ok).flat_map(|items| items.into_iter())
+        .filter_map(|attr| meta_str(&attr).map(|(name, value)| (name, value, attr.span())))
+        .filter(|(name, _, _)| name == "bound")
+        .last();
+
+    bound.map(|(_, bound, span)| parse_where(cx, span, &bound))
+}
+
+/// Parses the predicates of a where clause.
+fn parse_where(cx: &mut Context, span: Span, predicates: &str) -> Vec<WherePredicate> {
+    if predicates.trim().is_empty() {
+        return Vec::new()
+    }
+
+    cx.parse::<syn::WhereClause>(span, &format!("where {}", predicates))
+        .map(|clause| clause.predicates.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Whether a field is marked with `skip_bound` or `ignore`.
+fn skips_bound(attrs: &[Attribute]) -> bool {
+    meta_lists(attrs, &["nue_enc", "nue_dec"]).into_iter().filter_map(Result::ok).flat_map(|items| items.into_iter()).any(|attr| match attr {
+        Meta::Path(ref path) => path.is_ident("skip_bound") || path.is_ident("ignore"),
+        Meta::NameValue(ref meta) => meta.path.is_ident("ignore"),
+        _ => false,
+    })
+}
+
+/// Whether a type refers to the named type parameter anywhere within it.
+fn type_mentions(ty: &Type, ident: &Ident) -> bool {
+    let name = ident.to_string();
+    quote!(#ty).to_string().split(|c: char| !(c.is_alphanumeric() || c == '_')).any(|word| word == name)
+}
+
+/// Whether a type depends on any of the parameters of `generics`.
+fn type_is_generic(ty: &Type, generics: &Generics) -> bool {
+    generics.params.iter().any(|param| match *param {
+        syn::GenericParam::Type(ref param) => type_mentions(ty, &param.ident),
+        syn::GenericParam::Lifetime(ref param) => type_mentions(ty, &param.lifetime.ident),
+        syn::GenericParam::Const(ref param) => type_mentions(ty, &param.ident),
+    })
+}
+
+fn item_attrs(cx: &mut Context, attrs: &[Attribute], meta_name: &'static str) -> Vec<ItemAttribute> {
+    let mut attrs_out = Vec::new();
+    for items in meta_lists(attrs, &[meta_name]) {
+        let items = match items {
+            Ok(items) => items,
+            Err(err) => {
+                cx.errors.push(err);
+                continue
+            },
+        };
+
+        for attr in items.iter() {
+            let span = attr.span();
+            let attrs = &mut attrs_out;
+            match meta_str(attr) {
+                Some((name, value)) => match &*name {
+                    "magic" => attrs.push(ItemAttribute::Magic(cx.parse_expr(span, &value))),
+                    "pad_to" => attrs.push(ItemAttribute::PadTo(cx.parse_expr(span, &value))),
+                    "before_encode" => attrs.push(ItemAttribute::BeforeEncode(cx.parse_expr(span, &value))),
+                    "after_decode" => attrs.push(ItemAttribute::AfterDecode(cx.parse_expr(span, &value))),
+                    // Parsed by each derive once it knows how fields are accessed
+                    "byte_order" => attrs.push(ItemAttribute::ByteOrder(span, value)),
+                    "endian" => match endian_name(&value) {
+                        Some(endian) => attrs.push(ItemAttribute::Endian(endian)),
+                        None => {
+                            cx.span_err(span, "endian must be \"little\", \"big\", or \"native\"");
+                            break
+                        },
+                    },
+                    // Read by `derive_type` through `item_bound`
+                    "bound" => (),
+                    "option" => match value.find(':') {
+                        Some(i) => match (cx.parse(span, value[..i].trim()), cx.parse(span, value[i + 1..].trim())) {
+                            (Some(name), Some(ty)) => attrs.push(ItemAttribute::Option(name, ty)),
+                            _ => break,
+                        },
+                        None => {
+                            cx.span_err(span, "option must be of the form \"name: Type\"");
+                            break
+                        },
+                    },
+                    "tag_type" => if TAG_TYPES.contains(&&*value) {
+                        attrs.push(ItemAttribute::TagType(Ident::new(&value, span)))
+                    } else {
+                        cx.span_err(span, "tag_type must be a primitive integer type");
+                        break
+                    },
+                    _ => {
+                        cx.span_err(span, "invalid attribute key");
+                        break
+                    },
+                },
+                None => {
+                    cx.span_err(span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+    attrs_out
+}
+
+fn variant_attrs(cx: &mut Context, attrs: &[Attribute], meta_name: &'static str) -> Vec<VariantAttribute> {
+    let mut attrs_out = Vec::new();
+    for items in meta_lists(attrs, &[meta_name]) {
+        let items = match items {
+            Ok(items) => items,
+            Err(err) => {
+                cx.errors.push(err);
+                continue
+            },
+        };
+
+        for attr in items.iter() {
+            let span = attr.span();
+            let attrs = &mut attrs_out;
+            match meta_str(attr) {
+                Some((name, value)) => match &*name {
+                    "discriminant" => attrs.push(VariantAttribute::Discriminant(cx.parse_expr(span, &value))),
+                    "align" => attrs.push(VariantAttribute::Align(cx.parse_expr(span, &value))),
+                    "cond" => attrs.push(VariantAttribute::Cond(cx.parse_expr(span, &value))),
+                    _ => {
+                        cx.span_err(span, "invalid attribute key");
+                        break
+                    },
+                },
+                None if attr.path().is_ident("other") => attrs.push(VariantAttribute::Other),
+                None => {
+                    cx.span_err(span, "invalid attribute");
+                    break
+                },
+            }
+        }
+    }
+    attrs_out
+}
+
+enum VariantAttribute {
+    /// The encoded discriminant, in place of the variant's own
+    Discriminant(Expr),
+    /// The alignment of the payload following the discriminant
+    Align(Expr),
+    /// Whether the payload is coded at all
+    Cond(Expr),
+    /// Holds any unknown discriminant in its first field when decoding
+    Other,
+}
+
+/// The index of the `other` variant, if any.
+fn other_variant(cx: &mut Context, variants: &Punctuated<syn::Variant, Comma>, attrs: &[Vec<VariantAttribute>]) -> Option<usize> {
+    let others = attrs.iter().enumerate().filter(|&(_, attrs)| attrs.iter().any(|attr| matches!(*attr, VariantAttribute::Other))).map(|(i, _)| i).collect::<Vec<_>>();
+
+    for &i in others.iter().skip(1) {
+        cx.span_err(variants[i].ident.span(), "only one variant can be `other`");
+    }
+
+    match others.first() {
+        Some(&i) if variants[i].fields.is_empty() => {
+            cx.span_err(variants[i].ident.span(), "the `other` variant must hold the discriminant in its first field");
+            None
+        },
+        other => other.cloned(),
+    }
+}
+
+fn variant_discriminant(attrs: &[VariantAttribute]) -> Option<Expr> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Discriminant(ref expr) => Some(expr.clone()), _ => None }).next_back()
+}
+
+fn variant_align(attrs: &[VariantAttribute]) -> Option<Expr> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Align(ref expr) => Some(expr.clone()), _ => None }).next_back()
+}
+
+fn variant_cond(attrs: &[VariantAttribute]) -> Option<Expr> {
+    attrs.iter().filter_map(|attr| match *attr { VariantAttribute::Cond(ref expr) => Some(expr.clone()), _ => None }).next_back()
+}
+
+enum ItemAttribute {
+    TagType(Ident),
+    Magic(Expr),
+    Endian(&'static str),
+    /// An expression selecting the `Endianness` of primitive fields at runtime
+    ByteOrder(Span, String),
+    /// The name and type of a field of the generated options struct
+    Option(Ident, Type),
+    /// The size that the encoding is padded to
+    PadTo(Expr),
+    /// A function called with `&self` before encoding
+    BeforeEncode(Expr),
+    /// A function called with `&mut` the decoded value before it is validated
+    AfterDecode(Expr),
+}
+
+/// The `pad_to` size of an item, if any.
+fn item_pad_to(attrs: &[ItemAttribute]) -> Option<Expr> {
+    attrs.iter().filter_map(|attr| match *attr {
+        ItemAttribute::PadTo(ref pad) => Some(pad.clone()),
+        _ => None,
+    }).next_back()
+}
+
+/// Generates the options struct named after the item and `suffix` from its `option` attributes.
+///
+/// Returns `None` if there are none, so the item keeps `()` as its options.
+fn options_struct(item: &DeriveInput, attrs: &[ItemAttribute], suffix: &str) -> Option<(TokenStream, TokenStream)> {
+    let (names, tys): (Vec<_>, Vec<_>) = attrs.iter().filter_map(|attr| match *attr {
+        ItemAttribute::Option(ref name, ref ty) => Some((name, ty)),
+        _ => None,
+    }).unzip();
+
+    if names.is_empty() {
+        return None
+    }
+
+    let name = format_ident!("{}{}", item.ident, suffix);
+    let vis = &item.vis;
+    let options_item = quote! {
+        #[derive(Clone, Default, Debug)]
+        #[allow(missing_docs)]
+        #vis struct #name {
+            #(pub #names: #tys,)*
+        }
+    };
+
+    Some((options_item, quote!(#name)))
+}
+
+/// Enums use `TagOptions`, so they can't have an options struct.
+fn enum_options_unsupported(cx: &mut Context, item: &DeriveInput, attrs: &[ItemAttribute]) {
+    if attrs.iter().any(|attr| matches!(*attr, ItemAttribute::Option(..))) {
+        cx.span_err(item.ident.span(), "option is only supported on structs");
+    }
+    if attrs.iter().any(|attr| matches!(*attr, ItemAttribute::ByteOrder(..))) {
+        cx.span_err(item.ident.span(), "byte_order is only supported on structs");
+    }
+}
+
+/// Makes the options of a derived impl available to attribute expressions as `options`.
+fn bind_options() -> TokenStream {
+    quote!(#[allow(unused_variables)] let options = __options;)
+}
+
+fn encode_magic(magic: &Expr) -> TokenStream {
+    quote!(::std::io::Write::write_all(__w, #magic)?;)
+}
+
+fn decode_magic(magic: &Expr) -> TokenStream {
+    quote!(::nue::ReadExactExt::expect_exact(__r, #magic)?;)
+}
+
+enum FieldAttribute {
+    Cond(Expr),
+    Default(Expr),
+    Align(Expr),
+    Limit(Expr),
+    Skip(Expr),
+    Consume(Expr),
+    Assert(Expr),
+    Tag(Expr),
+    Count(Expr),
+    /// The name of the counted field, and the expression for its length
+    CountOf(String, Expr),
+    Magic(Expr),
+    Endian(&'static str),
+    /// The `encode` or `decode` function of a `with` module
+    With(Expr),
+    /// The conversion between the wire and field representations, `map` when decoding and `unmap` when encoding
+    Map(Expr),
+    /// Seeks back to where the field started once it has been decoded
+    RestorePosition,
+    /// The field holds the offset of the stream from the start of the value
+    Position,
+    /// The element that ends a `Vec` field
+    Terminator(Expr),
+    /// Whether `last`, the latest element of a `Vec` field, is its final one
+    Until(Expr),
+    /// The value of a field that the stream ends before
+    IfEof(Expr),
+    /// The digest a checksum field is computed with
+    Checksum(Expr),
+    /// The name of the field a checksum starts at
+    Over(String),
+    /// The width of a field packed into bytes with its neighbours
+    Bits(u32),
+    /// The name of the last field that a `limit` spans
+    Through(String),
+    /// The value encoded in place of the field's own
+    Value(Expr),
+    /// Coded as a LEB128 `Varint`
+    Varint,
+}
+
+/// The index of the field that the checksum of a struct or variant starts at, and its digest.
+///
+/// The checksum covers every byte from the start of its `over` field, or the first field,
+/// up to the checksum field itself.
+fn checksum_range(cx: &mut Context, fields: &[Field], attrs: &[Vec<FieldAttribute>]) -> Option<(usize, Expr)> {
+    let mut checksums = fields.iter().zip(attrs).enumerate().filter_map(|(i, (field, attrs))| {
+        find_attr(attrs, |attr| match *attr { FieldAttribute::Checksum(ref expr) => Some(expr), _ => None })
+            .map(|hasher| (i, field, hasher, attrs.iter().filter_map(|attr| match *attr {
+                FieldAttribute::Over(ref name) => Some(name.clone()),
+                _ => None,
+            }).next_back()))
+    }).collect::<Vec<_>>();
+
+    if checksums.len() > 1 {
+        cx.span_err(checksums[1].1.ty.span(), "only one checksum is supported per struct or variant");
+    }
+
+    checksums.pop().and_then(|(index, field, hasher, over)| {
+        let start = match over {
+            Some(over) => match fields[..index].iter().position(|field| field.label() == over) {
+                Some(start) => start,
+                None => {
+                    cx.span_err(field.ty.span(), &format!("over must name an earlier field, not `{}`", over));
+                    return None
+                },
+            },
+            None => 0,
+        };
+
+        Some((start, hasher))
+    })
+}
+
+/// The fields spanned by each `limit` with a `through` attribute, as the index of
+/// the first and last field of the group, and its limit.
+fn limit_groups(cx: &mut Context, fields: &[Field], attrs: &[Vec<FieldAttribute>]) -> Vec<(usize, usize, Expr)> {
+    let mut groups: Vec<(usize, usize, Expr)> = Vec::new();
+    for (start, field) in fields.iter().enumerate() {
+        let through = match attrs[start].iter().filter_map(|attr| match *attr { FieldAttribute::Through(ref name) => Some(name), _ => None }).next_back() {
+            Some(through) => through,
+            None => continue,
+        };
+        let limit = match find_attr(&attrs[start], |attr| match *attr { FieldAttribute::Limit(ref expr) => Some(expr), _ => None }) {
+            Some(limit) => limit,
+            None => {
+                cx.span_err(field.ty.span(), "through requires a limit");
+                continue
+            },
+        };
+        let end = match fields[start + 1..].iter().position(|field| field.label() == *through) {
+            Some(end) => start + 1 + end,
+            None => {
+                cx.span_err(field.ty.span(), &format!("through must name a later field, not `{}`", through));
+                continue
+            },
+        };
+
+        if groups.last().map(|&(_, last, _)| start <= last).unwrap_or(false) {
+            cx.span_err(field.ty.span(), "limit groups can't overlap");
+        } else if attrs[start..end + 1].iter().any(|attrs| attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Bits(_)))) {
+            cx.span_err(field.ty.span(), "bits fields can't be part of a limit group");
+        } else {
+            groups.push((start, end, limit));
+        }
+    }
+
+    groups
+}
+
+/// Whether a field is the last of its limit group, if it's part of one.
+fn field_group(groups: &[(usize, usize, Expr)], index: usize) -> Option<bool> {
+    groups.iter().find(|&&(start, end, _)| start <= index && index <= end).map(|&(_, end, _)| index == end)
+}
+
+/// The byte order of a field, which may override the item's default.
+fn field_endian(attrs: &[FieldAttribute], defaults: &Defaults) -> Option<&'static str> {
+    attrs.iter().filter_map(|attr| match *attr {
+        FieldAttribute::Endian(endian) => Some(endian),
+        _ => None,
+    }).next_back().or(defaults.endian)
+}
+
+/// The wrapper type a field of type `ty` is coded through, if any.
+fn field_wrapper(attrs: &[FieldAttribute], defaults: &Defaults, ty: &Type) -> Option<TokenStream> {
+    if attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Varint)) {
+        Some(quote!(::nue::Varint<#ty>))
+    } else {
+        endian_wrapper(field_endian(attrs, defaults), ty)
+    }
+}
+
+/// The runtime byte order of a primitive field that doesn't specify its own `endian` or `varint`.
+fn field_byte_order<'a>(attrs: &[FieldAttribute], order: Option<&'a Expr>, ty: &Type) -> Option<&'a Expr> {
+    let own = attrs.iter().any(|attr| matches!(*attr, FieldAttribute::Endian(_) | FieldAttribute::Varint));
+    match order {
+        Some(order) if !own && endian_wrapper(Some("Le"), ty).is_some() => Some(order),
+        _ => None,
+    }
+}
+
+/// The expression of the last attribute selected by `f`, if any.
+fn find_attr<F: Fn(&FieldAttribute) -> Option<&Expr>>(attrs: &[FieldAttribute], f: F) -> Option<Expr> {
+    attrs.iter().filter_map(f).next_back().cloned()
+}
+
+/// The options to decode a field with, as determined by its `tag` and `count` attributes.
+///
+/// A `String` is counted in bytes, and anything else is assumed to be a `Vec`.
+fn decode_options_expr(attrs: &[FieldAttribute], ty: &Type) -> Option<TokenStream> {
+    let tag = find_attr(attrs, |attr| match *attr { FieldAttribute::Tag(ref expr) => Some(expr), _ => None })
+        .map(|tag| quote!(::nue::TagOptions { tag: Some((#tag) as _) }));
+
+    match find_attr(attrs, |attr| match *attr { FieldAttribute::Count(ref expr) => Some(expr), _ => None }) {
+        Some(count) if type_name_is(ty, "String") => Some(quote!(::nue::StringDecodeOptions { len: Some((#count) as usize) })),
+        Some(count) => {
+            let options = tag.unwrap_or_else(|| quote!(::std::default::Default::default()));
+            Some(quote!(::nue::VecDecodeOptions { len: Some((#count) as usize), options: #options }))
+        },
+        None => tag,
+    }
+}
@@ -1,5 +1,6 @@
-use std::io;
-use seek_forward::{Tell, SeekForward};
+use std::io::{self, Write};
+use std::cmp::min;
+use seek_forward::{Tell, SeekForward, SeekBackward};
 
 /// An extension trait that will seek to meet a specified alignment.
 pub trait SeekAlignExt {
@@ -7,6 +8,40 @@ pub trait SeekAlignExt {
     ///
     /// Returns the resulting offset in the stream upon success.
     fn align_to(&mut self, alignment: u64) -> io::Result<u64>;
+
+    /// Writes `fill` bytes until the position is a multiple of `alignment`.
+    ///
+    /// Returns the resulting offset in the stream upon success.
+    fn align_to_with(&mut self, alignment: u64, fill: u8) -> io::Result<u64> where Self: Tell + Write {
+        let pos = try!(self.tell());
+        let pos_alignment = pos % alignment;
+        if pos_alignment > 0 {
+            let buf = [fill; 0x100];
+            let mut remaining = alignment - pos_alignment;
+            while remaining > 0 {
+                let len = min(remaining, buf.len() as u64) as usize;
+                try!(self.write_all(&buf[..len]));
+                remaining -= len as u64;
+            }
+
+            Ok(pos + alignment - pos_alignment)
+        } else {
+            Ok(pos)
+        }
+    }
+
+    /// Seeks backward to a multiple of `alignment`.
+    ///
+    /// Returns the resulting offset in the stream upon success.
+    fn align_backward(&mut self, alignment: u64) -> io::Result<u64> where Self: Tell + SeekBackward {
+        let pos = try!(self.tell());
+        let pos_alignment = pos % alignment;
+        if pos_alignment > 0 {
+            self.seek_backward(pos_alignment).map(|v| pos - v)
+        } else {
+            Ok(pos)
+        }
+    }
 }
 
 impl<T: Tell + SeekForward> SeekAlignExt for T {
@@ -40,3 +75,17 @@ fn align() {
     cursor.align_to(0x20).unwrap();
     assert_eq!(cursor.tell().unwrap(), 0x40);
 }
+
+#[test]
+fn align_fill() {
+    use cursor::Cursor;
+
+    let mut cursor = Cursor::new(vec![1, 2, 3]);
+    cursor.seek_forward(3).unwrap();
+    assert_eq!(cursor.align_to_with(8, 0xff).unwrap(), 8);
+    assert_eq!(cursor.get_ref(), &[1, 2, 3, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+    cursor.seek_backward(3).unwrap();
+    assert_eq!(cursor.align_backward(4).unwrap(), 4);
+    assert_eq!(cursor.tell().unwrap(), 4);
+}
@@ -0,0 +1,711 @@
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::cmp::min;
+use futures_io::{AsyncRead, AsyncWrite, AsyncSeek, AsyncBufRead};
+
+macro_rules! try_ready {
+    ($e:expr) => {
+        match $e {
+            Poll::Ready(Ok(v)) => v,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+    };
+}
+
+/// The async counterpart of `SeekRewind`.
+pub trait AsyncSeekRewind {
+    /// Attempts to seek back to the beginning of the stream.
+    fn poll_seek_rewind(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>>;
+}
+
+/// The async counterpart of `SeekForward`.
+pub trait AsyncSeekForward {
+    /// Attempts to seek forward in the stream.
+    ///
+    /// Returns the number of bytes skipped.
+    fn poll_seek_forward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>>;
+}
+
+/// The async counterpart of `SeekBackward`.
+pub trait AsyncSeekBackward {
+    /// Attempts to seek backward in the stream.
+    ///
+    /// Returns the number of bytes reversed by.
+    fn poll_seek_backward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>>;
+}
+
+/// The async counterpart of `SeekEnd`.
+pub trait AsyncSeekEnd {
+    /// Attempts to seek to the end of the stream + `offset`.
+    ///
+    /// Returns the new position in the stream.
+    fn poll_seek_end(self: Pin<&mut Self>, cx: &mut Context, offset: i64) -> Poll<io::Result<u64>>;
+}
+
+/// The async counterpart of `SeekAbsolute`.
+pub trait AsyncSeekAbsolute {
+    /// Attempts to seek to the specified position in the stream.
+    ///
+    /// Returns the new position in the stream.
+    fn poll_seek_absolute(self: Pin<&mut Self>, cx: &mut Context, pos: u64) -> Poll<io::Result<u64>>;
+}
+
+/// The async counterpart of `Tell`.
+pub trait AsyncTell {
+    /// Attempts to return the current absolute position in the stream.
+    fn poll_tell(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>>;
+}
+
+macro_rules! impl_deref {
+    ($($t:ty),*) => {
+        $(
+            impl<'a, T: AsyncSeekRewind + Unpin + ?Sized> AsyncSeekRewind for $t {
+                #[inline]
+                fn poll_seek_rewind(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                    Pin::new(&mut **self).poll_seek_rewind(cx)
+                }
+            }
+
+            impl<'a, T: AsyncSeekForward + Unpin + ?Sized> AsyncSeekForward for $t {
+                #[inline]
+                fn poll_seek_forward(mut self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+                    Pin::new(&mut **self).poll_seek_forward(cx, offset)
+                }
+            }
+
+            impl<'a, T: AsyncSeekBackward + Unpin + ?Sized> AsyncSeekBackward for $t {
+                #[inline]
+                fn poll_seek_backward(mut self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+                    Pin::new(&mut **self).poll_seek_backward(cx, offset)
+                }
+            }
+
+            impl<'a, T: AsyncSeekEnd + Unpin + ?Sized> AsyncSeekEnd for $t {
+                #[inline]
+                fn poll_seek_end(mut self: Pin<&mut Self>, cx: &mut Context, offset: i64) -> Poll<io::Result<u64>> {
+                    Pin::new(&mut **self).poll_seek_end(cx, offset)
+                }
+            }
+
+            impl<'a, T: AsyncSeekAbsolute + Unpin + ?Sized> AsyncSeekAbsolute for $t {
+                #[inline]
+                fn poll_seek_absolute(mut self: Pin<&mut Self>, cx: &mut Context, pos: u64) -> Poll<io::Result<u64>> {
+                    Pin::new(&mut **self).poll_seek_absolute(cx, pos)
+                }
+            }
+
+            impl<'a, T: AsyncTell + Unpin + ?Sized> AsyncTell for $t {
+                #[inline]
+                fn poll_tell(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+                    Pin::new(&mut **self).poll_tell(cx)
+                }
+            }
+        )*
+    };
+}
+
+impl_deref!(&'a mut T, Box<T>);
+
+macro_rules! impl_io {
+    ($t:ident => AsyncRead) => {
+        impl<T: AsyncRead + Unpin> AsyncRead for $t<T> {
+            #[inline]
+            fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+                Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+            }
+        }
+    };
+    ($t:ident => AsyncWrite) => {
+        impl<T: AsyncWrite + Unpin> AsyncWrite for $t<T> {
+            #[inline]
+            fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+                Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+            }
+
+            #[inline]
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+            }
+
+            #[inline]
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.get_mut().inner).poll_close(cx)
+            }
+        }
+    };
+    ($t:ident => AsyncBufRead) => {
+        impl<T: AsyncBufRead + Unpin> AsyncBufRead for $t<T> {
+            #[inline]
+            fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+                Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+            }
+
+            #[inline]
+            fn consume(self: Pin<&mut Self>, amt: usize) {
+                Pin::new(&mut self.get_mut().inner).consume(amt)
+            }
+        }
+    };
+}
+
+/// A wrapper that decomposes `AsyncSeek` into the individual async seek traits.
+pub struct AsyncSeekAll<T> {
+    inner: T,
+}
+
+impl<T> AsyncSeekAll<T> {
+    /// Creates a new `AsyncSeekAll`.
+    pub fn new(inner: T) -> Self {
+        AsyncSeekAll {
+            inner: inner,
+        }
+    }
+
+    /// Unwraps the `AsyncSeekAll` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekAll<T> {
+    fn poll_seek(&mut self, cx: &mut Context, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_seek(cx, pos)
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncTell for AsyncSeekAll<T> {
+    fn poll_tell(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        self.get_mut().poll_seek(cx, SeekFrom::Current(0))
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekForward for AsyncSeekAll<T> {
+    fn poll_seek_forward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        self.get_mut().poll_seek(cx, SeekFrom::Current(offset as i64)).map(|r| r.map(|_| offset))
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekBackward for AsyncSeekAll<T> {
+    fn poll_seek_backward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        self.get_mut().poll_seek(cx, SeekFrom::Current(-(offset as i64))).map(|r| r.map(|_| offset))
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekAbsolute for AsyncSeekAll<T> {
+    fn poll_seek_absolute(self: Pin<&mut Self>, cx: &mut Context, pos: u64) -> Poll<io::Result<u64>> {
+        self.get_mut().poll_seek(cx, SeekFrom::Start(pos))
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekRewind for AsyncSeekAll<T> {
+    fn poll_seek_rewind(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.get_mut().poll_seek(cx, SeekFrom::Start(0)).map(|r| r.map(|_| ()))
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeekEnd for AsyncSeekAll<T> {
+    fn poll_seek_end(self: Pin<&mut Self>, cx: &mut Context, offset: i64) -> Poll<io::Result<u64>> {
+        self.get_mut().poll_seek(cx, SeekFrom::End(offset))
+    }
+}
+
+impl_io!(AsyncSeekAll => AsyncRead);
+impl_io!(AsyncSeekAll => AsyncWrite);
+impl_io!(AsyncSeekAll => AsyncBufRead);
+
+/// The async counterpart of `Take`.
+pub struct AsyncTake<T> {
+    inner: T,
+    limit: u64,
+}
+
+impl<T> AsyncTake<T> {
+    /// Creates a new `AsyncTake` with `limit` bytes
+    pub fn new(inner: T, limit: u64) -> Self {
+        AsyncTake {
+            inner: inner,
+            limit: limit,
+        }
+    }
+
+    /// Returns the number of bytes remaining before the limit is reached.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Replaces the remaining limit with `limit` bytes.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Unwraps the `AsyncTake` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for AsyncTake<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let limit = min(this.limit, buf.len() as u64) as usize;
+        if limit == 0 {
+            return Poll::Ready(Ok(0))
+        }
+
+        let read = try_ready!(Pin::new(&mut this.inner).poll_read(cx, &mut buf[..limit]));
+        this.limit -= read as u64;
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for AsyncTake<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let limit = min(this.limit, buf.len() as u64) as usize;
+        if limit == 0 {
+            return Poll::Ready(Ok(0))
+        }
+
+        let written = try_ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..limit]));
+        this.limit -= written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for AsyncTake<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.limit == 0 {
+            return Poll::Ready(Ok(&[]))
+        }
+
+        let limit = this.limit;
+        let buf = try_ready!(Pin::new(&mut this.inner).poll_fill_buf(cx));
+        let len = min(limit, buf.len() as u64) as usize;
+        Poll::Ready(Ok(&buf[..len]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        let amt = min(this.limit, amt as u64);
+        this.limit -= amt;
+        Pin::new(&mut this.inner).consume(amt as usize);
+    }
+}
+
+impl<T: AsyncSeekForward + Unpin> AsyncSeekForward for AsyncTake<T> {
+    fn poll_seek_forward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let offset = min(offset, this.limit);
+        let skipped = try_ready!(Pin::new(&mut this.inner).poll_seek_forward(cx, offset));
+        this.limit -= skipped;
+        Poll::Ready(Ok(skipped))
+    }
+}
+
+impl<T: AsyncTell + Unpin> AsyncTell for AsyncTake<T> {
+    fn poll_tell(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.get_mut().inner).poll_tell(cx)
+    }
+}
+
+/// The async counterpart of `Region`.
+///
+/// The position of the inner stream is checked once per operation, and
+/// remembered if the operation is pending so that it isn't repeated.
+pub struct AsyncRegion<T> {
+    inner: T,
+    start: u64,
+    end: u64,
+    pos: Option<u64>,
+    seeking: bool,
+}
+
+impl<T> AsyncRegion<T> {
+    /// Creates a new `AsyncRegion` at the specified offsets of `inner`.
+    pub fn new(inner: T, start: u64, end: u64) -> Self {
+        AsyncRegion {
+            inner: inner,
+            start: start,
+            end: end,
+            pos: None,
+            seeking: false,
+        }
+    }
+
+    /// Returns the region bounds.
+    pub fn region(&self) -> (u64, u64) {
+        (self.start, self.end)
+    }
+
+    /// Unwraps the `AsyncRegion` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn complete<V>(&mut self, res: Poll<io::Result<V>>) -> Poll<io::Result<V>> {
+        if res.is_ready() {
+            self.pos = None;
+        }
+
+        res
+    }
+}
+
+impl<T: AsyncTell + AsyncSeekAbsolute + Unpin> AsyncRegion<T> {
+    fn poll_position(&mut self, cx: &mut Context) -> Poll<io::Result<u64>> {
+        if let Some(pos) = self.pos {
+            return Poll::Ready(Ok(pos))
+        }
+
+        if !self.seeking {
+            let pos = try_ready!(Pin::new(&mut self.inner).poll_tell(cx));
+            if pos >= self.start {
+                self.pos = Some(pos);
+                return Poll::Ready(Ok(pos))
+            }
+
+            self.seeking = true;
+        }
+
+        let start = self.start;
+        let res = try_ready!(Pin::new(&mut self.inner).poll_seek_absolute(cx, start).map(|res| {
+            self.seeking = false;
+            res
+        }));
+        self.pos = Some(res);
+        Poll::Ready(Ok(res))
+    }
+
+    fn poll_limit(&mut self, cx: &mut Context, len: u64) -> Poll<io::Result<u64>> {
+        let pos = try_ready!(self.poll_position(cx));
+
+        Poll::Ready(Ok(min(self.end.saturating_sub(pos), len)))
+    }
+}
+
+impl<T: AsyncRead + AsyncTell + AsyncSeekAbsolute + Unpin> AsyncRead for AsyncRegion<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let len = try_ready!(this.poll_limit(cx, buf.len() as u64)) as usize;
+        let res = if len == 0 {
+            Poll::Ready(Ok(0))
+        } else {
+            Pin::new(&mut this.inner).poll_read(cx, &mut buf[..len])
+        };
+        this.complete(res)
+    }
+}
+
+impl<T: AsyncWrite + AsyncTell + AsyncSeekAbsolute + Unpin> AsyncWrite for AsyncRegion<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let len = try_ready!(this.poll_limit(cx, buf.len() as u64)) as usize;
+        let res = if len == 0 {
+            Poll::Ready(Ok(0))
+        } else {
+            Pin::new(&mut this.inner).poll_write(cx, &buf[..len])
+        };
+        this.complete(res)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+impl<T: AsyncBufRead + AsyncTell + AsyncSeekAbsolute + Unpin> AsyncBufRead for AsyncRegion<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        let limit = try_ready!(this.poll_limit(cx, u64::max_value()));
+        let buf = try_ready!(Pin::new(&mut this.inner).poll_fill_buf(cx));
+        let len = min(limit, buf.len() as u64) as usize;
+        Poll::Ready(Ok(&buf[..len]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.pos = None;
+        Pin::new(&mut this.inner).consume(amt)
+    }
+}
+
+impl<T: AsyncSeekAbsolute + Unpin> AsyncSeekAbsolute for AsyncRegion<T> {
+    fn poll_seek_absolute(self: Pin<&mut Self>, cx: &mut Context, pos: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let (start, end) = (this.start, this.end);
+        let res = Pin::new(&mut this.inner).poll_seek_absolute(cx, min(start.saturating_add(pos), end));
+        this.complete(res).map(|r| r.map(|v| v - start))
+    }
+}
+
+impl<T: AsyncSeekForward + AsyncTell + AsyncSeekAbsolute + Unpin> AsyncSeekForward for AsyncRegion<T> {
+    fn poll_seek_forward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let offset = try_ready!(this.poll_limit(cx, offset));
+        let res = Pin::new(&mut this.inner).poll_seek_forward(cx, offset);
+        this.complete(res)
+    }
+}
+
+impl<T: AsyncSeekAbsolute + Unpin> AsyncSeekRewind for AsyncRegion<T> {
+    fn poll_seek_rewind(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let start = this.start;
+        let res = Pin::new(&mut this.inner).poll_seek_absolute(cx, start);
+        this.complete(res).map(|r| r.map(|_| ()))
+    }
+}
+
+impl<T: AsyncSeekAbsolute + Unpin> AsyncSeekEnd for AsyncRegion<T> {
+    fn poll_seek_end(self: Pin<&mut Self>, cx: &mut Context, offset: i64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let (start, end) = (this.start, this.end);
+        let pos = if offset < 0 {
+            end.checked_sub(offset.wrapping_neg() as u64)
+        } else {
+            end.checked_add(offset as u64)
+        };
+        let pos = match pos {
+            _ if end == u64::MAX => return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot seek from the end of an open-ended region"))),
+            Some(pos) if pos >= start => pos,
+            _ => return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidInput, "seek to an invalid position"))),
+        };
+        let res = Pin::new(&mut this.inner).poll_seek_absolute(cx, pos);
+        this.complete(res).map(|r| r.map(|v| v - start))
+    }
+}
+
+impl<T: AsyncTell + Unpin> AsyncTell for AsyncRegion<T> {
+    fn poll_tell(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let (start, end) = (this.start, this.end);
+        Pin::new(&mut this.inner).poll_tell(cx).map(|r| r.map(|v| min(end, v.saturating_sub(start))))
+    }
+}
+
+const DEFAULT_BUF_SIZE: usize = 0x400 * 0x40;
+
+/// The async counterpart of `BufSeeker`.
+pub struct AsyncBufSeeker<T> {
+    inner: T,
+    buf: Box<[u8]>,
+    filled: usize,
+    pos: usize,
+}
+
+impl<T> AsyncBufSeeker<T> {
+    /// Creates a new `AsyncBufSeeker` around the specified `AsyncRead`.
+    pub fn new(inner: T) -> Self {
+        AsyncBufSeeker::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates an `AsyncBufSeeker` with a specific buffer size.
+    pub fn with_capacity(cap: usize, inner: T) -> Self {
+        AsyncBufSeeker {
+            inner: inner,
+            buf: vec![0; cap].into_boxed_slice(),
+            filled: 0,
+            pos: 0,
+        }
+    }
+
+    /// Unwraps the `AsyncBufSeeker`, returning the underlying reader.
+    ///
+    /// Note that any leftover data in the buffer will be lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn buffered(&self) -> u64 {
+        (self.filled - self.pos) as u64
+    }
+
+    fn discard(&mut self) {
+        self.pos = self.filled;
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for AsyncBufSeeker<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.pos >= this.filled && buf.len() >= this.buf.len() {
+            return Pin::new(&mut this.inner).poll_read(cx, buf)
+        }
+
+        let read = {
+            let data = try_ready!(Pin::new(&mut *this).poll_fill_buf(cx));
+            let len = min(data.len(), buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        };
+        this.pos += read;
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncBufRead for AsyncBufSeeker<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos >= this.filled {
+            this.filled = try_ready!(Pin::new(&mut this.inner).poll_read(cx, &mut this.buf));
+            this.pos = 0;
+        }
+
+        Poll::Ready(Ok(&this.buf[this.pos..this.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.pos = min(this.pos + amt, this.filled);
+    }
+}
+
+impl<T: AsyncSeekForward + Unpin> AsyncSeekForward for AsyncBufSeeker<T> {
+    fn poll_seek_forward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let buffered = this.buffered();
+        if offset <= buffered {
+            this.pos += offset as usize;
+            Poll::Ready(Ok(offset))
+        } else {
+            let res = try_ready!(Pin::new(&mut this.inner).poll_seek_forward(cx, offset - buffered));
+            this.discard();
+            Poll::Ready(Ok(res + buffered))
+        }
+    }
+}
+
+impl<T: AsyncSeekBackward + Unpin> AsyncSeekBackward for AsyncBufSeeker<T> {
+    fn poll_seek_backward(self: Pin<&mut Self>, cx: &mut Context, offset: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        if offset <= this.pos as u64 {
+            this.pos -= offset as usize;
+            Poll::Ready(Ok(offset))
+        } else {
+            // The inner stream is positioned at the end of the buffered data
+            let buffered = this.buffered();
+            let res = try_ready!(Pin::new(&mut this.inner).poll_seek_backward(cx, offset + buffered));
+            this.discard();
+            Poll::Ready(Ok(res.saturating_sub(buffered)))
+        }
+    }
+}
+
+impl<T: AsyncSeekRewind + Unpin> AsyncSeekRewind for AsyncBufSeeker<T> {
+    fn poll_seek_rewind(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        try_ready!(Pin::new(&mut this.inner).poll_seek_rewind(cx));
+        this.discard();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncSeekAbsolute + Unpin> AsyncSeekAbsolute for AsyncBufSeeker<T> {
+    fn poll_seek_absolute(self: Pin<&mut Self>, cx: &mut Context, pos: u64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let pos = try_ready!(Pin::new(&mut this.inner).poll_seek_absolute(cx, pos));
+        this.discard();
+        Poll::Ready(Ok(pos))
+    }
+}
+
+impl<T: AsyncSeekEnd + Unpin> AsyncSeekEnd for AsyncBufSeeker<T> {
+    fn poll_seek_end(self: Pin<&mut Self>, cx: &mut Context, offset: i64) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let pos = try_ready!(Pin::new(&mut this.inner).poll_seek_end(cx, offset));
+        this.discard();
+        Poll::Ready(Ok(pos))
+    }
+}
+
+impl<T: AsyncTell + Unpin> AsyncTell for AsyncBufSeeker<T> {
+    fn poll_tell(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let buffered = this.buffered();
+        Pin::new(&mut this.inner).poll_tell(cx).map(|r| r.map(|v| v - buffered))
+    }
+}
+
+#[test]
+fn async_region() {
+    use std::io::{Cursor, Read, Seek};
+    use std::task::Waker;
+
+    // Alternates between pending and ready to exercise restarts
+    struct Stutter(Cursor<Vec<u8>>, bool);
+
+    impl Stutter {
+        fn stutter(&mut self, cx: &mut Context) -> bool {
+            self.1 = !self.1;
+            if self.1 {
+                cx.waker().wake_by_ref();
+            }
+            self.1
+        }
+    }
+
+    impl AsyncRead for Stutter {
+        fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            if this.stutter(cx) { Poll::Pending } else { Poll::Ready(this.0.read(buf)) }
+        }
+    }
+
+    impl AsyncSeek for Stutter {
+        fn poll_seek(self: Pin<&mut Self>, cx: &mut Context, pos: SeekFrom) -> Poll<io::Result<u64>> {
+            let this = self.get_mut();
+            if this.stutter(cx) { Poll::Pending } else { Poll::Ready(this.0.seek(pos)) }
+        }
+    }
+
+    let data: Vec<u8> = (0..0x40).collect();
+    let stream = AsyncSeekAll::new(Stutter(Cursor::new(data.clone()), false));
+    let mut region = AsyncBufSeeker::with_capacity(8, AsyncRegion::new(stream, 0x10, 0x20));
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let mut odata = Vec::new();
+    let mut buf = [0u8; 4];
+    loop {
+        match Pin::new(&mut region).poll_read(&mut cx, &mut buf) {
+            Poll::Pending => (),
+            Poll::Ready(Ok(0)) => break,
+            Poll::Ready(Ok(read)) => odata.extend_from_slice(&buf[..read]),
+            Poll::Ready(Err(e)) => panic!("{}", e),
+        }
+    }
+    assert_eq!(&odata[..], &data[0x10..0x20]);
+
+    let tell = loop {
+        if let Poll::Ready(res) = Pin::new(&mut region).poll_tell(&mut cx) {
+            break res.unwrap()
+        }
+    };
+    assert_eq!(tell, 0x10);
+
+    let mut seek_end = |offset| loop {
+        if let Poll::Ready(res) = Pin::new(&mut region).poll_seek_end(&mut cx, offset) {
+            break res
+        }
+    };
+    assert_eq!(seek_end(-0x11).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(seek_end(-4).unwrap(), 0xc);
+
+    let mut region = AsyncRegion::new(AsyncSeekAll::new(Stutter(Cursor::new(data), false)), 0, u64::MAX - 1);
+    let res = Pin::new(&mut region).poll_seek_end(&mut cx, ::std::i64::MAX);
+    assert_eq!(res.map(|res| res.unwrap_err().kind()), Poll::Ready(io::ErrorKind::InvalidInput));
+}
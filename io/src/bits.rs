@@ -0,0 +1,207 @@
+use std::io::{self, Read, Write};
+use std::cmp::min;
+use read_exact::ReadExactExt;
+use error::Error;
+
+/// Reads integers of arbitrary bit widths from a byte stream, most significant bit first.
+///
+/// Bits are taken from the inner stream a byte at a time, so it is left positioned
+/// after the last byte that has been partially or fully read.
+pub struct BitReader<R> {
+    inner: R,
+    byte: u8,
+    remaining: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    /// Creates a new `BitReader` starting at the next byte of `inner`.
+    pub fn new(inner: R) -> Self {
+        BitReader {
+            inner: inner,
+            byte: 0,
+            remaining: 0,
+        }
+    }
+
+    /// Reads the next `bits` bits as an unsigned integer.
+    ///
+    /// Panics if `bits` is larger than 64.
+    pub fn read_bits(&mut self, bits: u32) -> io::Result<u64> {
+        assert!(bits <= 64, "can't read more than 64 bits at once");
+
+        let mut value = 0u64;
+        let mut bits = bits;
+        while bits > 0 {
+            if self.remaining == 0 {
+                let mut byte = [0u8; 1];
+                if try!(self.inner.read_exact_eof(&mut byte)) == 0 {
+                    return Err(Error::UnexpectedEof { needed: Some(((bits + 7) / 8) as u64) }.into())
+                }
+                self.byte = byte[0];
+                self.remaining = 8;
+            }
+
+            let take = min(bits, self.remaining);
+            let chunk = (self.byte >> (self.remaining - take)) as u64 & ((1 << take) - 1);
+            value = value << take | chunk;
+            self.remaining -= take;
+            bits -= take;
+        }
+
+        Ok(value)
+    }
+
+    /// Reads the next `bits` bits as a two's complement integer, sign extending it.
+    ///
+    /// Panics if `bits` is larger than 64.
+    pub fn read_signed_bits(&mut self, bits: u32) -> io::Result<i64> {
+        let value = try!(self.read_bits(bits));
+        Ok(match 64 - bits {
+            64 => 0,
+            shift => (value << shift) as i64 >> shift,
+        })
+    }
+
+    /// Discards the unread bits of the current byte.
+    pub fn align(&mut self) {
+        self.remaining = 0;
+    }
+
+    /// Unwraps the inner stream, discarding any unread bits of the current byte.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Writes integers of arbitrary bit widths to a byte stream, most significant bit first.
+///
+/// Each byte is written to the inner stream once it is full. Call `align` to write out
+/// a partial final byte.
+pub struct BitWriter<W> {
+    inner: W,
+    byte: u8,
+    used: u32,
+}
+
+impl<W: Write> BitWriter<W> {
+    /// Creates a new `BitWriter` starting at the next byte of `inner`.
+    pub fn new(inner: W) -> Self {
+        BitWriter {
+            inner: inner,
+            byte: 0,
+            used: 0,
+        }
+    }
+
+    /// Writes the low `bits` bits of `value`.
+    ///
+    /// Fails with `Error::Validation` if `value` doesn't fit in `bits` bits.
+    /// Panics if `bits` is larger than 64.
+    pub fn write_bits(&mut self, value: u64, bits: u32) -> io::Result<()> {
+        assert!(bits <= 64, "can't write more than 64 bits at once");
+
+        if bits < 64 && value >> bits != 0 {
+            return Err(Error::Validation {
+                field: None,
+                message: format!("{:#x} does not fit in {} bits", value, bits),
+            }.into())
+        }
+
+        let mut bits = bits;
+        while bits > 0 {
+            let take = min(bits, 8 - self.used);
+            let chunk = (value >> (bits - take)) as u8 & ((1u16 << take) - 1) as u8;
+            self.byte |= chunk << (8 - self.used - take);
+            self.used += take;
+            bits -= take;
+
+            if self.used == 8 {
+                try!(self.inner.write_all(&[self.byte]));
+                self.byte = 0;
+                self.used = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the low `bits` bits of `value` as a two's complement integer.
+    ///
+    /// Fails with `Error::Validation` if `value` doesn't fit in `bits` signed bits.
+    /// Panics if `bits` is larger than 64.
+    pub fn write_signed_bits(&mut self, value: i64, bits: u32) -> io::Result<()> {
+        assert!(bits <= 64, "can't write more than 64 bits at once");
+
+        let fits = match 64 - bits {
+            64 => value == 0,
+            shift => value << shift >> shift == value,
+        };
+        if !fits {
+            return Err(Error::Validation {
+                field: None,
+                message: format!("{} does not fit in {} signed bits", value, bits),
+            }.into())
+        }
+
+        let value = match bits {
+            64 => value as u64,
+            bits => value as u64 & ((1 << bits) - 1),
+        };
+        self.write_bits(value, bits)
+    }
+
+    /// Writes out a partial current byte, padded with zero bits.
+    pub fn align(&mut self) -> io::Result<()> {
+        if self.used > 0 {
+            try!(self.inner.write_all(&[self.byte]));
+            self.byte = 0;
+            self.used = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Unwraps the inner stream, discarding a partial current byte.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[test]
+fn bits() {
+    let mut w = BitWriter::new(Vec::new());
+    w.write_bits(0b101, 3).unwrap();
+    w.write_bits(0x1ff, 9).unwrap();
+    w.write_bits(1, 1).unwrap();
+    assert!(w.write_bits(4, 2).is_err());
+    w.align().unwrap();
+    let data = w.into_inner();
+    assert_eq!(data, [0b1011_1111, 0b1111_1000]);
+
+    let mut r = BitReader::new(&data[..]);
+    assert_eq!(r.read_bits(3).unwrap(), 0b101);
+    assert_eq!(r.read_bits(9).unwrap(), 0x1ff);
+    assert_eq!(r.read_bits(1).unwrap(), 1);
+    r.align();
+    assert_eq!(r.read_bits(1).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn signed_bits() {
+    let mut w = BitWriter::new(Vec::new());
+    w.write_signed_bits(-3, 4).unwrap();
+    w.write_signed_bits(7, 4).unwrap();
+    assert!(w.write_signed_bits(8, 4).is_err());
+    assert!(w.write_signed_bits(-9, 4).is_err());
+    w.write_signed_bits(-8, 4).unwrap();
+    w.write_signed_bits(-1, 64).unwrap();
+    w.align().unwrap();
+    let data = w.into_inner();
+    assert_eq!(&data[..2], [0b1101_0111, 0b1000_1111]);
+
+    let mut r = BitReader::new(&data[..]);
+    assert_eq!(r.read_signed_bits(4).unwrap(), -3);
+    assert_eq!(r.read_signed_bits(4).unwrap(), 7);
+    assert_eq!(r.read_signed_bits(4).unwrap(), -8);
+    assert_eq!(r.read_signed_bits(64).unwrap(), -1);
+}
@@ -0,0 +1,175 @@
+use std::io::{self, Write};
+use resize_slice::SliceExt;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+const DEFAULT_BUF_SIZE: usize = 0x400 * 0x40;
+
+/// A buffered writer that allows for seeking within the buffer.
+///
+/// Unlike `std::io::BufWriter`, seeking within the buffered window doesn't
+/// flush it. Writes behind the end of the buffer overwrite what was there.
+///
+/// Flushing while positioned behind the end of the buffer seeks the
+/// underlying writer back, so it must implement `SeekBackward`.
+pub struct BufSeekWriter<T> {
+    inner: T,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<T> BufSeekWriter<T> {
+    /// Creates a new `BufSeekWriter` around the specified `Write`.
+    pub fn new(inner: T) -> Self {
+        BufSeekWriter::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a `BufSeekWriter` with a specific buffer size.
+    pub fn with_capacity(cap: usize, inner: T) -> Self {
+        BufSeekWriter {
+            inner: inner,
+            buf: Vec::with_capacity(cap),
+            pos: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Write + SeekBackward> BufSeekWriter<T> {
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            try!(self.inner.write_all(&self.buf));
+            let back = (self.buf.len() - self.pos) as u64;
+            self.buf.clear();
+            self.pos = 0;
+
+            if back > 0 {
+                try!(self.inner.seek_backward(back));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the buffer and unwraps the `BufSeekWriter`, returning the underlying writer.
+    ///
+    /// Any data still in the buffer will be lost if the `BufSeekWriter` is dropped without this.
+    pub fn into_inner(mut self) -> io::Result<T> {
+        try!(self.flush_buf());
+        Ok(self.inner)
+    }
+}
+
+impl<T: Write + SeekBackward> Write for BufSeekWriter<T> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.pos + buf.len() > self.buf.capacity() {
+            try!(self.flush_buf());
+        }
+
+        if buf.len() >= self.buf.capacity() {
+            self.inner.write(buf)
+        } else {
+            let end = self.pos + buf.len();
+            if end > self.buf.len() {
+                self.buf.resize(end, 0);
+            }
+            self.buf[self.pos..end].copy_from(buf);
+            self.pos = end;
+            Ok(buf.len())
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        try!(self.flush_buf());
+        self.inner.flush()
+    }
+}
+
+impl<T: Write + SeekBackward + SeekForward> SeekForward for BufSeekWriter<T> {
+    #[inline]
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        if offset <= (self.buf.len() - self.pos) as u64 {
+            self.pos += offset as usize;
+            Ok(offset)
+        } else {
+            try!(self.flush_buf());
+            self.inner.seek_forward(offset)
+        }
+    }
+}
+
+impl<T: Write + SeekBackward> SeekBackward for BufSeekWriter<T> {
+    #[inline]
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        if offset <= self.pos as u64 {
+            self.pos -= offset as usize;
+            Ok(offset)
+        } else {
+            try!(self.flush_buf());
+            self.inner.seek_backward(offset)
+        }
+    }
+}
+
+impl<T: Tell> Tell for BufSeekWriter<T> {
+    #[inline]
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell().map(|v| v + self.pos as u64)
+    }
+}
+
+impl<T: Write + SeekBackward + SeekRewind> SeekRewind for BufSeekWriter<T> {
+    #[inline]
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        try!(self.flush_buf());
+        self.inner.seek_rewind()
+    }
+}
+
+impl<T: Write + SeekBackward + SeekAbsolute> SeekAbsolute for BufSeekWriter<T> {
+    #[inline]
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        try!(self.flush_buf());
+        self.inner.seek_absolute(pos)
+    }
+}
+
+impl<T: Write + SeekBackward + SeekEnd> SeekEnd for BufSeekWriter<T> {
+    #[inline]
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        try!(self.flush_buf());
+        self.inner.seek_end(offset)
+    }
+}
+
+#[test]
+fn buf_seek_writer() {
+    use std::io::Cursor;
+    use seek_forward::SeekAll;
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = BufSeekWriter::with_capacity(8, SeekAll::new(&mut cursor));
+
+        writer.write_all(&[1, 2, 3, 4]).unwrap();
+        writer.seek_backward(3).unwrap();
+        writer.write_all(&[5]).unwrap();
+        assert_eq!(writer.tell().unwrap(), 2);
+
+        writer.flush().unwrap();
+        assert_eq!(writer.tell().unwrap(), 2);
+        writer.seek_forward(1).unwrap();
+        writer.write_all(&[6, 7, 8, 9, 10, 11, 12, 13]).unwrap();
+        writer.seek_absolute(0).unwrap();
+        writer.write_all(&[0]).unwrap();
+
+        writer.into_inner().unwrap();
+    }
+
+    assert_eq!(&cursor.get_ref()[..], &[0, 5, 3, 6, 7, 8, 9, 10, 11, 12, 13]);
+}
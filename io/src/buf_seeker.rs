@@ -8,10 +8,15 @@ const DEFAULT_BUF_SIZE: usize = 0x400 * 0x40;
 /// A buffered reader that allows for seeking within the buffer.
 ///
 /// Unlike `std::io::BufReader`, seeking doesn't invalidate the buffer.
+///
+/// A lookback window can be retained when refilling the buffer, so that
+/// short backward seeks are possible over streams that can't seek at all.
+/// Wrap such streams in `SeekBackwardUnsupported` to use it.
 pub struct BufSeeker<T> {
     inner: T,
     buf: Vec<u8>,
     pos: usize,
+    lookback: usize,
 }
 
 impl<T> BufSeeker<T> {
@@ -26,15 +31,71 @@ impl<T> BufSeeker<T> {
             inner: inner,
             buf: Vec::with_capacity(cap),
             pos: 0,
+            lookback: 0,
         }
     }
 
+    /// Creates a `BufSeeker` that keeps at least `lookback` bytes of
+    /// already read data available for seeking backward.
+    pub fn with_lookback(lookback: usize, inner: T) -> Self {
+        let mut seeker = BufSeeker::with_capacity(DEFAULT_BUF_SIZE + lookback, inner);
+        seeker.lookback = lookback;
+        seeker
+    }
+
+    /// Creates a `BufSeeker` that reuses an existing allocation as its buffer.
+    ///
+    /// Any contents of `buf` are discarded, and its capacity determines the
+    /// buffer size. An unallocated `buf` is given the default size.
+    pub fn with_buffer(mut buf: Vec<u8>, inner: T) -> Self {
+        buf.clear();
+        if buf.capacity() == 0 {
+            buf.reserve(DEFAULT_BUF_SIZE);
+        }
+
+        BufSeeker {
+            inner: inner,
+            buf: buf,
+            pos: 0,
+            lookback: 0,
+        }
+    }
+
+    /// Returns the buffered data that has not yet been read.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Returns the size of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
     /// Unwraps the `BufSeeker`, returning the underlying reader.
     ///
     /// Note that any leftover data in the buffer will be lost.
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    /// Unwraps the `BufSeeker`, returning the underlying reader and the buffer.
+    ///
+    /// The buffer contains only the data that has not yet been read,
+    /// and may be passed to `with_buffer` to be reused.
+    pub fn into_parts(mut self) -> (T, Vec<u8>) {
+        self.buf.drain(..self.pos);
+        (self.inner, self.buf)
+    }
+
+    fn discard(&mut self) {
+        self.buf.clear();
+        self.pos = 0;
+    }
 }
 
 impl<T: SeekForward> SeekForward for BufSeeker<T> {
@@ -47,7 +108,7 @@ impl<T: SeekForward> SeekForward for BufSeeker<T> {
         } else {
             let offset = offset - pos;
             let res = try!(self.inner.seek_forward(offset));
-            self.pos = self.buf.len();
+            self.discard();
             Ok(res + pos)
         }
     }
@@ -56,15 +117,14 @@ impl<T: SeekForward> SeekForward for BufSeeker<T> {
 impl<T: SeekBackward> SeekBackward for BufSeeker<T> {
     #[inline]
     fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
-        let pos = self.pos as u64;
-        if offset <= pos {
+        if offset <= self.pos as u64 {
             self.pos -= offset as usize;
             Ok(offset)
         } else {
-            let offset = offset + pos;
-            let res = try!(self.inner.seek_backward(offset));
-            self.pos = self.buf.len();
-            Ok(res - pos)
+            let ahead = (self.buf.len() - self.pos) as u64;
+            let res = try!(self.inner.seek_backward(offset + ahead));
+            self.discard();
+            Ok(res.saturating_sub(ahead))
         }
     }
 }
@@ -73,7 +133,7 @@ impl<T: SeekRewind> SeekRewind for BufSeeker<T> {
     #[inline]
     fn seek_rewind(&mut self) -> io::Result<()> {
         try!(self.inner.seek_rewind());
-        self.pos = self.buf.len();
+        self.discard();
         Ok(())
     }
 }
@@ -89,7 +149,7 @@ impl<T: SeekAbsolute> SeekAbsolute for BufSeeker<T> {
     #[inline]
     fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
         let pos = try!(self.inner.seek_absolute(pos));
-        self.pos = self.buf.len();
+        self.discard();
         Ok(pos)
     }
 }
@@ -98,7 +158,7 @@ impl<T: SeekEnd> SeekEnd for BufSeeker<T> {
     #[inline]
     fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
         let pos = try!(self.inner.seek_end(offset));
-        self.pos = self.buf.len();
+        self.discard();
         Ok(pos)
     }
 }
@@ -106,7 +166,7 @@ impl<T: SeekEnd> SeekEnd for BufSeeker<T> {
 impl<T: Read> Read for BufSeeker<T> {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if self.pos >= self.buf.len() && buf.len() >= self.buf.capacity() {
+        if self.lookback == 0 && self.pos >= self.buf.len() && buf.len() >= self.buf.capacity() {
             self.inner.read(buf)
         } else {
             let read = buf.copy_from(try!(self.fill_buf()));
@@ -122,10 +182,16 @@ impl<T: Read> BufRead for BufSeeker<T> {
         use std::slice::from_raw_parts_mut;
 
         if self.pos >= self.buf.len() {
+            let keep = min(self.lookback, self.buf.len());
+            let start = self.buf.len() - keep;
+            self.buf.drain(..start);
+            // Left consistent in case the read fails
+            self.pos = keep;
+
             unsafe {
-                let buf = from_raw_parts_mut(self.buf.as_mut_ptr(), self.buf.capacity());
-                self.buf.set_len(try!(self.inner.read(buf)));
-                self.pos = 0;
+                let buf = from_raw_parts_mut(self.buf.as_mut_ptr().offset(keep as isize), self.buf.capacity() - keep);
+                let read = try!(self.inner.read(buf));
+                self.buf.set_len(keep + read);
             }
         }
         Ok(&self.buf[self.pos..])
@@ -136,3 +202,87 @@ impl<T: Read> BufRead for BufSeeker<T> {
         self.pos = min(self.pos + amt, self.buf.len());
     }
 }
+
+#[test]
+fn buf_seeker_buffer() {
+    use cursor::Cursor;
+
+    let mut r = BufSeeker::with_buffer(vec![0xff; 2], Cursor::new(vec![1, 2, 3, 4, 5]));
+    assert!(r.capacity() >= 2);
+    assert!(r.buffer().is_empty());
+
+    let mut odata = [0u8; 1];
+    r.read_exact(&mut odata).unwrap();
+    assert_eq!(r.buffer()[0], 2);
+
+    let (_, buf) = r.into_parts();
+    assert_eq!(buf[0], 2);
+    let r = BufSeeker::with_buffer(buf, Cursor::new(Vec::<u8>::new()));
+    assert!(r.buffer().is_empty());
+}
+
+#[test]
+fn buf_seeker_lookback() {
+    use seek_forward::SeekBackwardUnsupported;
+
+    let data: Vec<u8> = (0..0x40).collect();
+    let mut r = BufSeeker::with_lookback(4, SeekBackwardUnsupported::new(&data[..]));
+    let mut odata = [0u8; 0x20];
+    r.read_exact(&mut odata).unwrap();
+    assert_eq!(r.seek_backward(4).unwrap(), 4);
+    r.read_exact(&mut odata[..4]).unwrap();
+    assert_eq!(&odata[..4], &data[0x1c..0x20]);
+
+    let mut r = BufSeeker::with_lookback(4, SeekBackwardUnsupported::new(&data[..]));
+    r.buf = Vec::with_capacity(8);
+    r.read_exact(&mut odata[..0x10]).unwrap();
+    assert_eq!(r.seek_backward(4).unwrap(), 4);
+    r.read_exact(&mut odata[..2]).unwrap();
+    assert_eq!(&odata[..2], &data[0x0c..0x0e]);
+    assert!(r.seek_backward(8).is_err());
+}
+
+#[test]
+fn buf_seeker_read_error() {
+    struct Flaky {
+        pos: u64,
+        reads: usize,
+    }
+
+    impl Read for Flaky {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            if self.reads == 2 {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "flaky"))
+            }
+            for b in buf.iter_mut() {
+                *b = self.pos as u8;
+                self.pos += 1;
+            }
+            Ok(buf.len())
+        }
+    }
+
+    impl Tell for Flaky {
+        fn tell(&mut self) -> io::Result<u64> {
+            Ok(self.pos)
+        }
+    }
+
+    impl SeekForward for Flaky {
+        fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+            self.pos += offset;
+            Ok(offset)
+        }
+    }
+
+    let mut r = BufSeeker::with_capacity(8, Flaky { pos: 0, reads: 0 });
+    let mut odata = [0u8; 8];
+    r.read_exact(&mut odata).unwrap();
+    assert_eq!(r.fill_buf().unwrap_err().kind(), io::ErrorKind::Interrupted);
+    assert_eq!(r.tell().unwrap(), 8);
+    assert_eq!(r.seek_forward(2).unwrap(), 2);
+    assert_eq!(r.tell().unwrap(), 10);
+    r.read_exact(&mut odata[..1]).unwrap();
+    assert_eq!(odata[0], 10);
+}
@@ -0,0 +1,168 @@
+use std::io::{self, Read, Write, BufRead};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// Tracks the stream position locally so that `Tell` doesn't need to query
+/// the inner stream every time.
+///
+/// The inner stream is only asked for its position the first time, and again
+/// after an error or a call to `invalidate`. Wrapping a `SeekAll<File>` this
+/// way avoids a seek syscall for every `tell`.
+pub struct CachedPosition<T> {
+    inner: T,
+    pos: Option<u64>,
+}
+
+impl<T> CachedPosition<T> {
+    /// Creates a new `CachedPosition` that will query `inner` for its position when first needed.
+    pub fn new(inner: T) -> Self {
+        CachedPosition {
+            inner: inner,
+            pos: None,
+        }
+    }
+
+    /// Creates a new `CachedPosition` over a stream known to be positioned at `pos`.
+    pub fn with_position(inner: T, pos: u64) -> Self {
+        CachedPosition {
+            inner: inner,
+            pos: Some(pos),
+        }
+    }
+
+    /// Forgets the cached position, so that it will be queried again from the inner stream.
+    pub fn invalidate(&mut self) {
+        self.pos = None;
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    ///
+    /// The cached position is invalidated, since it may be changed through the reference.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.pos = None;
+        &mut self.inner
+    }
+
+    /// Unwraps the `CachedPosition` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn advance<F: FnOnce(u64, u64) -> u64>(&mut self, res: io::Result<u64>, f: F) -> io::Result<u64> {
+        match res {
+            Ok(v) => {
+                self.pos = self.pos.map(|pos| f(pos, v));
+                Ok(v)
+            },
+            Err(e) => {
+                self.pos = None;
+                Err(e)
+            },
+        }
+    }
+
+    fn set(&mut self, res: io::Result<u64>) -> io::Result<u64> {
+        self.pos = res.as_ref().ok().cloned();
+        res
+    }
+}
+
+impl<T: Read> Read for CachedPosition<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let res = self.inner.read(buf).map(|v| v as u64);
+        self.advance(res, |pos, read| pos + read).map(|v| v as usize)
+    }
+}
+
+impl<T: BufRead> BufRead for CachedPosition<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.pos = self.pos.map(|pos| pos + amt as u64);
+    }
+}
+
+impl<T: Write> Write for CachedPosition<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.inner.write(buf).map(|v| v as u64);
+        self.advance(res, |pos, written| pos + written).map(|v| v as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: SeekForward> SeekForward for CachedPosition<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let res = self.inner.seek_forward(offset);
+        self.advance(res, |pos, skipped| pos + skipped)
+    }
+}
+
+impl<T: SeekBackward> SeekBackward for CachedPosition<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let res = self.inner.seek_backward(offset);
+        self.advance(res, |pos, reversed| pos - reversed)
+    }
+}
+
+impl<T: SeekRewind> SeekRewind for CachedPosition<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        let res = self.inner.seek_rewind().map(|_| 0);
+        self.set(res).map(|_| ())
+    }
+}
+
+impl<T: SeekAbsolute> SeekAbsolute for CachedPosition<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        let res = self.inner.seek_absolute(pos);
+        self.set(res)
+    }
+}
+
+impl<T: SeekEnd> SeekEnd for CachedPosition<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let res = self.inner.seek_end(offset);
+        self.set(res)
+    }
+}
+
+impl<T: Tell> Tell for CachedPosition<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        match self.pos {
+            Some(pos) => Ok(pos),
+            None => {
+                let res = self.inner.tell();
+                self.set(res)
+            },
+        }
+    }
+}
+
+#[test]
+fn cached_position() {
+    use std::io::Cursor as StdCursor;
+    use seek_forward::SeekAll;
+    use stats::Stats;
+
+    let mut s = CachedPosition::new(Stats::new(SeekAll::new(StdCursor::new(vec![0u8; 0x20]))));
+    s.write_all(&[1, 2, 3, 4]).unwrap();
+    assert_eq!(s.tell().unwrap(), 4);
+    s.seek_forward(4).unwrap();
+    assert_eq!(s.tell().unwrap(), 8);
+    s.seek_backward(2).unwrap();
+    s.read_exact(&mut [0u8; 2]).unwrap();
+    assert_eq!(s.tell().unwrap(), 8);
+    assert_eq!(s.seek_end(-1).unwrap(), 0x1f);
+    assert_eq!(s.tell().unwrap(), 0x1f);
+
+    assert_eq!(s.get_ref().stats().seeks, 4);
+}
@@ -0,0 +1,196 @@
+use std::io::{self, Read, Write};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekAbsolute, SeekRewind, SeekEnd, Tell};
+
+/// Concatenates multiple streams into one logical stream.
+///
+/// Each segment has a fixed length, and global offsets are mapped onto
+/// the segment that contains them. Segments are positioned with `SeekAbsolute`
+/// when they are first accessed after a seek, so seeking the `Chain` itself is free.
+///
+/// Reaching the end of the last segment results in EOF when reading or writing.
+pub struct Chain<T> {
+    segments: Vec<T>,
+    ends: Vec<u64>,
+    pos: u64,
+    synced: Option<usize>,
+}
+
+impl<T> Chain<T> {
+    /// Creates a new `Chain` from streams paired with their lengths.
+    pub fn new<I: IntoIterator<Item=(T, u64)>>(segments: I) -> Self {
+        let mut end = 0;
+        let (segments, ends) = segments.into_iter().map(|(segment, len)| {
+            end += len;
+            (segment, end)
+        }).unzip();
+
+        Chain {
+            segments: segments,
+            ends: ends,
+            pos: 0,
+            synced: None,
+        }
+    }
+
+    /// Returns the combined length of all segments.
+    pub fn len(&self) -> u64 {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    /// Returns whether the `Chain` contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the segments.
+    pub fn get_ref(&self) -> &[T] {
+        &self.segments
+    }
+
+    /// Unwraps the `Chain` to return the segments.
+    pub fn into_inner(self) -> Vec<T> {
+        self.segments
+    }
+}
+
+impl<T: SeekEnd> Chain<T> {
+    /// Creates a new `Chain`, measuring the length of each stream with `SeekEnd`.
+    pub fn from_streams<I: IntoIterator<Item=T>>(streams: I) -> io::Result<Self> {
+        let mut segments = Vec::new();
+        for mut stream in streams {
+            let len = try!(stream.seek_end(0));
+            segments.push((stream, len));
+        }
+
+        Ok(Chain::new(segments))
+    }
+}
+
+impl<T: SeekAbsolute> Chain<T> {
+    /// Positions the segment containing the current offset, returning its
+    /// index along with how many of `len` bytes fit before its end.
+    fn segment(&mut self, len: u64) -> io::Result<Option<(usize, usize)>> {
+        let pos = self.pos;
+        let index = match self.ends.iter().position(|&end| end > pos) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        if self.synced != Some(index) {
+            let start = if index == 0 { 0 } else { self.ends[index - 1] };
+            try!(self.segments[index].seek_absolute(pos - start));
+            self.synced = Some(index);
+        }
+
+        Ok(Some((index, min(self.ends[index] - pos, len) as usize)))
+    }
+}
+
+impl<T: Read + SeekAbsolute> Read for Chain<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (index, len) = match try!(self.segment(buf.len() as u64)) {
+            Some(segment) => segment,
+            None => return Ok(0),
+        };
+
+        let read = try!(self.segments[index].read(&mut buf[..len]));
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T: Write + SeekAbsolute> Write for Chain<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (index, len) = match try!(self.segment(buf.len() as u64)) {
+            Some(segment) => segment,
+            None => return Ok(0),
+        };
+
+        let written = try!(self.segments[index].write(&buf[..len]));
+        self.pos += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for segment in &mut self.segments {
+            try!(segment.flush());
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> SeekAbsolute for Chain<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = min(pos, self.len());
+        self.synced = None;
+        Ok(self.pos)
+    }
+}
+
+impl<T> SeekForward for Chain<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let pos = self.pos;
+        self.seek_absolute(pos.saturating_add(offset)).map(|v| v - pos)
+    }
+}
+
+impl<T> SeekBackward for Chain<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let pos = self.pos;
+        self.seek_absolute(pos.saturating_sub(offset)).map(|v| pos - v)
+    }
+}
+
+impl<T> SeekRewind for Chain<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.seek_absolute(0).map(|_| ())
+    }
+}
+
+impl<T> SeekEnd for Chain<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let len = self.len();
+        let pos = if offset < 0 {
+            len.saturating_sub(offset.wrapping_neg() as u64)
+        } else {
+            len.saturating_add(offset as u64)
+        };
+
+        self.seek_absolute(pos)
+    }
+}
+
+impl<T> Tell for Chain<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn chain() {
+    use std::io::Cursor;
+    use seek_forward::SeekAll;
+
+    let data: Vec<u8> = (0..0x30).collect();
+    let segments = data.chunks(0x10).map(|chunk| SeekAll::new(Cursor::new(chunk.to_vec())));
+    let mut chain = Chain::from_streams(segments).unwrap();
+    assert_eq!(chain.len(), 0x30);
+
+    chain.seek_rewind().unwrap();
+    let mut odata = Vec::new();
+    chain.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, data);
+
+    chain.seek_absolute(0x0e).unwrap();
+    chain.write_all(&[0xff; 4]).unwrap();
+    assert_eq!(chain.tell().unwrap(), 0x12);
+
+    let mut segments = chain.into_inner();
+    let mut odata = Vec::new();
+    segments[1].seek_rewind().unwrap();
+    segments[1].read_to_end(&mut odata).unwrap();
+    assert_eq!(&odata[..0x02], &[0xff; 2]);
+    assert_eq!(&odata[0x02..], &data[0x12..0x20]);
+}
@@ -0,0 +1,181 @@
+use std::io::{self, Read, Write, copy, repeat, sink};
+use flate2::Compression;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use seek_forward::{SeekForward, SeekRewind, Tell};
+
+macro_rules! decoder {
+    ($(#[$attr:meta])* $t:ident => $decoder:ident) => {
+        $(#[$attr])*
+        ///
+        /// Seeking forward decompresses and discards data, while rewinding
+        /// rewinds the compressed stream and restarts decompression.
+        /// `Tell` reports the position in the decompressed data.
+        pub struct $t<R> {
+            inner: Option<$decoder<R>>,
+            pos: u64,
+        }
+
+        impl<R: Read> $t<R> {
+            /// Creates a new decompressor over a stream positioned at the start of the compressed data.
+            pub fn new(inner: R) -> Self {
+                $t {
+                    inner: Some($decoder::new(inner)),
+                    pos: 0,
+                }
+            }
+        }
+
+        impl<R> $t<R> {
+            fn decoder(&mut self) -> &mut $decoder<R> {
+                self.inner.as_mut().expect("decompressor lost its stream")
+            }
+
+            /// Returns a reference to the compressed stream.
+            pub fn get_ref(&self) -> &R {
+                self.inner.as_ref().expect("decompressor lost its stream").get_ref()
+            }
+
+            /// Unwraps the decompressor to return the compressed stream.
+            ///
+            /// The stream may have been read past the current position, as input is buffered.
+            pub fn into_inner(self) -> R {
+                self.inner.expect("decompressor lost its stream").into_inner()
+            }
+        }
+
+        impl<R: Read> Read for $t<R> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let read = try!(self.decoder().read(buf));
+                self.pos += read as u64;
+                Ok(read)
+            }
+        }
+
+        impl<R: Read> SeekForward for $t<R> {
+            fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+                let skipped = try!(copy(&mut self.decoder().take(offset), &mut sink()));
+                self.pos += skipped;
+                Ok(skipped)
+            }
+        }
+
+        impl<R: Read + SeekRewind> SeekRewind for $t<R> {
+            fn seek_rewind(&mut self) -> io::Result<()> {
+                let mut inner = self.inner.take().expect("decompressor lost its stream").into_inner();
+                let res = inner.seek_rewind();
+                self.inner = Some($decoder::new(inner));
+                self.pos = 0;
+                res
+            }
+        }
+
+        impl<R> Tell for $t<R> {
+            fn tell(&mut self) -> io::Result<u64> {
+                Ok(self.pos)
+            }
+        }
+    };
+}
+
+macro_rules! encoder {
+    ($(#[$attr:meta])* $t:ident => $encoder:ident) => {
+        $(#[$attr])*
+        ///
+        /// Seeking forward compresses zeroes to fill the gap.
+        /// `Tell` reports the position in the uncompressed data.
+        /// `finish` must be called to write out the end of the compressed stream.
+        pub struct $t<W: Write> {
+            inner: $encoder<W>,
+            pos: u64,
+        }
+
+        impl<W: Write> $t<W> {
+            /// Creates a new compressor writing to `inner`.
+            pub fn new(inner: W, level: Compression) -> Self {
+                $t {
+                    inner: $encoder::new(inner, level),
+                    pos: 0,
+                }
+            }
+
+            /// Returns a reference to the compressed stream.
+            pub fn get_ref(&self) -> &W {
+                self.inner.get_ref()
+            }
+
+            /// Finishes the compressed stream and returns it.
+            pub fn finish(self) -> io::Result<W> {
+                self.inner.finish()
+            }
+        }
+
+        impl<W: Write> Write for $t<W> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let written = try!(self.inner.write(buf));
+                self.pos += written as u64;
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl<W: Write> SeekForward for $t<W> {
+            fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+                copy(&mut repeat(0).take(offset), self)
+            }
+        }
+
+        impl<W: Write> Tell for $t<W> {
+            fn tell(&mut self) -> io::Result<u64> {
+                Ok(self.pos)
+            }
+        }
+    };
+}
+
+decoder! {
+    /// Decompresses a raw deflate stream.
+    DeflateReader => DeflateDecoder
+}
+
+decoder! {
+    /// Decompresses a gzip stream.
+    GzReader => GzDecoder
+}
+
+encoder! {
+    /// Compresses to a raw deflate stream.
+    DeflateWriter => DeflateEncoder
+}
+
+encoder! {
+    /// Compresses to a gzip stream.
+    GzWriter => GzEncoder
+}
+
+#[test]
+fn compress_roundtrip() {
+    use cursor::Cursor;
+
+    let mut w = GzWriter::new(Vec::new(), Compression::default());
+    w.write_all(b"hello").unwrap();
+    w.seek_forward(3).unwrap();
+    w.write_all(b"world").unwrap();
+    assert_eq!(w.tell().unwrap(), 13);
+    let data = w.finish().unwrap();
+
+    let mut r = GzReader::new(Cursor::new(data));
+    assert_eq!(r.seek_forward(8).unwrap(), 8);
+    let mut odata = Vec::new();
+    r.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, b"world");
+    assert_eq!(r.tell().unwrap(), 13);
+
+    r.seek_rewind().unwrap();
+    odata.clear();
+    r.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, b"hello\0\0\0world");
+}
@@ -0,0 +1,41 @@
+use std::io::{self, Read, Write};
+use std::cmp::min;
+use error::Error;
+
+/// Copies exactly `len` bytes from `r` to `w`.
+///
+/// Unlike `io::copy(&mut r.take(len), w)`, running out of input is an
+/// `UnexpectedEof` error rather than a short copy.
+/// Retries upon `Interrupted` errors.
+pub fn copy_exact<R: Read + ?Sized, W: Write + ?Sized>(r: &mut R, w: &mut W, len: u64) -> io::Result<()> {
+    let mut buf = [0u8; 0x2000];
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let chunk = min(remaining, buf.len() as u64) as usize;
+        let read = match r.read(&mut buf[..chunk]) {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+            Ok(0) => return Err(Error::UnexpectedEof { needed: Some(remaining) }.into()),
+            Ok(read) => read,
+        };
+
+        try!(w.write_all(&buf[..read]));
+        remaining -= read as u64;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn copy_exact_len() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..0x10).collect();
+    let mut r = Cursor::new(&data[..]);
+    let mut w = Vec::new();
+
+    copy_exact(&mut r, &mut w, 8).unwrap();
+    assert_eq!(&w[..], &data[..8]);
+    assert_eq!(copy_exact(&mut r, &mut w, 0x10).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
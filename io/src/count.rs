@@ -0,0 +1,159 @@
+use std::io::{self, Read, Write, BufRead};
+use seek_forward::{SeekForward, Tell};
+
+/// Counts the number of bytes read from an underlying stream.
+///
+/// `Tell` reports the count, so streams that can't otherwise report their
+/// position may be used where one is required.
+pub struct CountRead<T> {
+    inner: T,
+    count: u64,
+}
+
+impl<T> CountRead<T> {
+    /// Creates a new `CountRead` starting at zero.
+    pub fn new(inner: T) -> Self {
+        CountRead {
+            inner: inner,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of bytes read or skipped so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    ///
+    /// Bytes read through this reference will not be counted.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the `CountRead` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read> Read for CountRead<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read(buf));
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T: BufRead> BufRead for CountRead<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.count += amt as u64;
+    }
+}
+
+impl<T: SeekForward> SeekForward for CountRead<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let skipped = try!(self.inner.seek_forward(offset));
+        self.count += skipped;
+        Ok(skipped)
+    }
+}
+
+impl<T> Tell for CountRead<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.count)
+    }
+}
+
+/// Counts the number of bytes written to an underlying stream.
+///
+/// `Tell` reports the count, so streams that can't otherwise report their
+/// position may be used where one is required.
+pub struct CountWrite<T> {
+    inner: T,
+    count: u64,
+}
+
+impl<T> CountWrite<T> {
+    /// Creates a new `CountWrite` starting at zero.
+    pub fn new(inner: T) -> Self {
+        CountWrite {
+            inner: inner,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of bytes written or skipped so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    ///
+    /// Bytes written through this reference will not be counted.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the `CountWrite` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Write> Write for CountWrite<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = try!(self.inner.write(buf));
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: SeekForward> SeekForward for CountWrite<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let skipped = try!(self.inner.seek_forward(offset));
+        self.count += skipped;
+        Ok(skipped)
+    }
+}
+
+impl<T> Tell for CountWrite<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.count)
+    }
+}
+
+#[test]
+fn count() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..0x20).collect();
+    let mut read = CountRead::new(Cursor::new(&data[..]));
+    let mut odata = [0u8; 0x08];
+    read.read(&mut odata).unwrap();
+    read.consume(0x04);
+    assert_eq!(read.count(), 0x0c);
+
+    let mut write = CountWrite::new(Vec::new());
+    write.write_all(&data).unwrap();
+    assert_eq!(write.tell().unwrap(), 0x20);
+}
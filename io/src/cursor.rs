@@ -0,0 +1,208 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// An in-memory stream that implements the seek traits directly.
+///
+/// Equivalent to `std::io::Cursor`, without needing to be wrapped in `SeekAll`.
+/// Reading is supported for any `AsRef<[u8]>` buffer, and writing for
+/// `Vec<u8>`, `&mut Vec<u8>` and `&mut [u8]`.
+///
+/// As with `std::io::Cursor`, the position may be placed past the end of the buffer.
+/// Reads there will result in EOF, and writes to a `Vec` will pad the gap with zeroes.
+#[derive(Clone, Debug, Default)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> Cursor<T> {
+    /// Creates a new `Cursor` positioned at the start of `inner`.
+    pub fn new(inner: T) -> Self {
+        Cursor {
+            inner: inner,
+            pos: 0,
+        }
+    }
+
+    /// Returns the current position.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Sets the current position.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// Returns a reference to the underlying buffer.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying buffer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the `Cursor` to return the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsRef<[u8]>> Cursor<T> {
+    fn remaining(&self) -> &[u8] {
+        let buf = self.inner.as_ref();
+        let pos = min(self.pos, buf.len() as u64) as usize;
+        &buf[pos..]
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = {
+            let data = self.remaining();
+            let len = min(data.len(), buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        };
+
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<T: AsRef<[u8]>> BufRead for Cursor<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt as u64;
+    }
+}
+
+fn vec_write(pos: &mut u64, vec: &mut Vec<u8>, buf: &[u8]) -> io::Result<usize> {
+    if *pos > usize::max_value() as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "cursor position exceeds addressable memory"))
+    }
+
+    let start = *pos as usize;
+    if vec.len() < start {
+        vec.resize(start, 0);
+    }
+
+    let overlap = min(vec.len() - start, buf.len());
+    vec[start..start + overlap].copy_from_slice(&buf[..overlap]);
+    vec.extend_from_slice(&buf[overlap..]);
+
+    *pos += buf.len() as u64;
+    Ok(buf.len())
+}
+
+impl Write for Cursor<Vec<u8>> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        vec_write(&mut self.pos, &mut self.inner, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Write for Cursor<&'a mut Vec<u8>> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        vec_write(&mut self.pos, self.inner, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Write for Cursor<&'a mut [u8]> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let pos = min(self.pos, self.inner.len() as u64) as usize;
+        let len = min(self.inner.len() - pos, buf.len());
+        self.inner[pos..pos + len].copy_from_slice(&buf[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> SeekAbsolute for Cursor<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+impl<T> SeekForward for Cursor<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.pos = self.pos.saturating_add(offset);
+        Ok(offset)
+    }
+}
+
+impl<T> SeekBackward for Cursor<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, self.pos);
+        self.pos -= offset;
+        Ok(offset)
+    }
+}
+
+impl<T> SeekRewind for Cursor<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> SeekEnd for Cursor<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let len = self.inner.as_ref().len() as u64;
+        self.pos = if offset < 0 {
+            match len.checked_sub(offset.wrapping_neg() as u64) {
+                Some(pos) => pos,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")),
+            }
+        } else {
+            len.saturating_add(offset as u64)
+        };
+
+        Ok(self.pos)
+    }
+}
+
+impl<T> Tell for Cursor<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn cursor() {
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.seek_forward(2).unwrap();
+    cursor.write_all(&[1, 2, 3]).unwrap();
+    assert_eq!(cursor.seek_backward(4).unwrap(), 4);
+    cursor.write_all(&[4]).unwrap();
+    assert_eq!(cursor.get_ref(), &[0, 4, 1, 2, 3]);
+
+    assert_eq!(cursor.seek_end(-2).unwrap(), 3);
+    let mut odata = Vec::new();
+    cursor.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, [2, 3]);
+
+    let mut data = [0u8; 4];
+    let mut cursor = Cursor::new(&mut data[..]);
+    cursor.seek_absolute(2).unwrap();
+    assert_eq!(cursor.write(&[5, 6, 7]).unwrap(), 2);
+    assert_eq!(data, [0, 0, 5, 6]);
+}
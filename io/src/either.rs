@@ -0,0 +1,121 @@
+use std::io::{self, Read, Write, BufRead};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// One of two stream types.
+///
+/// Implements each I/O and seek trait that both arms implement, so a choice
+/// between streams doesn't need to be boxed into a single trait object.
+#[derive(Clone, Debug)]
+pub enum Either<L, R> {
+    /// The first stream type.
+    Left(L),
+    /// The second stream type.
+    Right(R),
+}
+
+macro_rules! either {
+    ($e:expr, $inner:ident => $body:expr) => {
+        match $e {
+            Either::Left(ref mut $inner) => $body,
+            Either::Right(ref mut $inner) => $body,
+        }
+    };
+}
+
+impl<L: Read, R: Read> Read for Either<L, R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        either!(*self, inner => inner.read(buf))
+    }
+}
+
+impl<L: BufRead, R: BufRead> BufRead for Either<L, R> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        either!(*self, inner => inner.fill_buf())
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        either!(*self, inner => inner.consume(amt))
+    }
+}
+
+impl<L: Write, R: Write> Write for Either<L, R> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        either!(*self, inner => inner.write(buf))
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        either!(*self, inner => inner.flush())
+    }
+}
+
+impl<L: SeekForward, R: SeekForward> SeekForward for Either<L, R> {
+    #[inline]
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        either!(*self, inner => inner.seek_forward(offset))
+    }
+}
+
+impl<L: SeekBackward, R: SeekBackward> SeekBackward for Either<L, R> {
+    #[inline]
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        either!(*self, inner => inner.seek_backward(offset))
+    }
+}
+
+impl<L: SeekRewind, R: SeekRewind> SeekRewind for Either<L, R> {
+    #[inline]
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        either!(*self, inner => inner.seek_rewind())
+    }
+}
+
+impl<L: SeekAbsolute, R: SeekAbsolute> SeekAbsolute for Either<L, R> {
+    #[inline]
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        either!(*self, inner => inner.seek_absolute(pos))
+    }
+}
+
+impl<L: SeekEnd, R: SeekEnd> SeekEnd for Either<L, R> {
+    #[inline]
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        either!(*self, inner => inner.seek_end(offset))
+    }
+}
+
+impl<L: Tell, R: Tell> Tell for Either<L, R> {
+    #[inline]
+    fn tell(&mut self) -> io::Result<u64> {
+        either!(*self, inner => inner.tell())
+    }
+}
+
+#[test]
+fn either() {
+    use cursor::Cursor;
+    use take::Take;
+
+    fn open(limit: Option<u64>) -> Either<Cursor<&'static [u8]>, Take<Cursor<&'static [u8]>>> {
+        let data = Cursor::new(&b"hello world"[..]);
+        match limit {
+            Some(limit) => Either::Right(Take::new(data, limit)),
+            None => Either::Left(data),
+        }
+    }
+
+    let mut odata = String::new();
+    open(Some(5)).read_to_string(&mut odata).unwrap();
+    assert_eq!(odata, "hello");
+
+    let mut r = open(None);
+    r.seek_forward(6).unwrap();
+    odata.clear();
+    r.read_to_string(&mut odata).unwrap();
+    assert_eq!(odata, "world");
+    assert_eq!(r.tell().unwrap(), 11);
+}
@@ -0,0 +1,153 @@
+use std::{io, fmt, error};
+
+/// The error type shared by `nue_io` and `pod::code`.
+///
+/// Fallible APIs still return `io::Result`, with these errors wrapped inside
+/// an `io::Error` of the matching `kind()`. Converting the `io::Error` back
+/// with `Error::from` recovers the original variant, so callers can match on
+/// it instead of inspecting error strings.
+#[derive(Debug)]
+pub enum Error {
+    /// An error from the underlying stream
+    Io(io::Error),
+    /// The stream ended before a value was complete
+    UnexpectedEof {
+        /// The number of bytes still needed, if known
+        needed: Option<u64>,
+    },
+    /// A decoded or encoded value failed validation
+    Validation {
+        /// The name of the offending field, if known
+        field: Option<&'static str>,
+        /// A description of the failed check
+        message: String,
+    },
+    /// A transfer exceeded a length limit
+    Limit {
+        /// The number of bytes that were allowed
+        limit: u64,
+    },
+    /// A magic number or signature did not match
+    Magic {
+        /// The expected bytes
+        expected: Vec<u8>,
+        /// The bytes that were read instead
+        found: Vec<u8>,
+    },
+    /// An error coding a field of a derived type
+    Field {
+        /// The name of the type
+        type_name: &'static str,
+        /// The name of the field
+        field: &'static str,
+        /// The offset of the field from the start of the value, if known
+        offset: Option<u64>,
+        /// The error that the field produced
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the `io::ErrorKind` this error is reported as
+    pub fn kind(&self) -> io::ErrorKind {
+        match *self {
+            Error::Io(ref err) => err.kind(),
+            Error::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof,
+            Error::Validation { .. } | Error::Limit { .. } => io::ErrorKind::InvalidInput,
+            Error::Magic { .. } => io::ErrorKind::InvalidData,
+            Error::Field { ref error, .. } => error.kind(),
+        }
+    }
+
+    /// Attributes this error to a field of a type, at an offset from the start of its value
+    pub fn in_field(self, type_name: &'static str, field: &'static str, offset: Option<u64>) -> Self {
+        Error::Field {
+            type_name: type_name,
+            field: field,
+            offset: offset,
+            error: Box::new(self),
+        }
+    }
+
+    /// Returns the innermost error, skipping any field context
+    pub fn root(&self) -> &Error {
+        match *self {
+            Error::Field { ref error, .. } => error.root(),
+            ref err => err,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => err.fmt(f),
+            Error::UnexpectedEof { needed: Some(needed) } => write!(f, "unexpected end of stream, {} more bytes needed", needed),
+            Error::UnexpectedEof { needed: None } => write!(f, "unexpected end of stream"),
+            Error::Validation { field: Some(field), ref message } => write!(f, "validation of {} failed: {}", field, message),
+            Error::Validation { field: None, ref message } => write!(f, "validation failed: {}", message),
+            Error::Limit { limit } => write!(f, "exceeded the limit of {} bytes", limit),
+            Error::Magic { ref expected, ref found } => write!(f, "expected magic {:02x?}, found {:02x?}", expected, found),
+            Error::Field { type_name, field, offset: Some(offset), ref error } => write!(f, "{}.{} at offset 0x{:x}: {}", type_name, field, offset, error),
+            Error::Field { type_name, field, offset: None, ref error } => write!(f, "{}.{}: {}", type_name, field, error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io(..) => "I/O error",
+            Error::UnexpectedEof { .. } => "unexpected end of stream",
+            Error::Validation { .. } => "validation failed",
+            Error::Limit { .. } => "limit exceeded",
+            Error::Magic { .. } => "magic mismatch",
+            Error::Field { ref error, .. } => error.description(),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().map(|e| e.is::<Error>()).unwrap_or(false) {
+            let inner = err.into_inner().expect("checked above");
+            *inner.downcast::<Error>().expect("checked above")
+        } else if err.kind() == io::ErrorKind::UnexpectedEof {
+            Error::UnexpectedEof { needed: None }
+        } else {
+            Error::Io(err)
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(err.kind(), err),
+        }
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let err: io::Error = Error::UnexpectedEof { needed: Some(4) }.into();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    match Error::from(err) {
+        Error::UnexpectedEof { needed: Some(4) } => (),
+        err => panic!("unexpected {:?}", err),
+    }
+
+    let err: io::Error = Error::UnexpectedEof { needed: None }.in_field("Inner", "len", Some(2)).in_field("Outer", "inner", Some(4)).into();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(err.to_string(), "Outer.inner at offset 0x4: Inner.len at offset 0x2: unexpected end of stream");
+    match *Error::from(err).root() {
+        Error::UnexpectedEof { needed: None } => (),
+        ref err => panic!("unexpected {:?}", err),
+    }
+
+    match Error::from(io::Error::new(io::ErrorKind::NotFound, "missing")) {
+        Error::Io(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+        err => panic!("unexpected {:?}", err),
+    }
+}
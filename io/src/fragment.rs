@@ -0,0 +1,172 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+const DEFAULT_SEED: u64 = 0x2545f4914f6cdd1d;
+
+/// A stream for testing that splits reads and writes into small chunks,
+/// and occasionally fails them with `ErrorKind::Interrupted`.
+///
+/// Chunk sizes are chosen pseudo-randomly from a seed, so failures are
+/// reproducible. Correct consumers must retry interrupted operations and
+/// handle short reads and writes.
+pub struct Fragment<T> {
+    inner: T,
+    state: u64,
+    max_chunk: usize,
+    interrupt: u32,
+}
+
+impl<T> Fragment<T> {
+    /// Creates a new `Fragment` with a fixed seed.
+    pub fn new(inner: T) -> Self {
+        Fragment::with_seed(DEFAULT_SEED, inner)
+    }
+
+    /// Creates a new `Fragment` with a specific seed.
+    pub fn with_seed(seed: u64, inner: T) -> Self {
+        Fragment {
+            inner: inner,
+            state: if seed == 0 { DEFAULT_SEED } else { seed },
+            max_chunk: 8,
+            interrupt: 4,
+        }
+    }
+
+    /// Sets the largest chunk that will be passed through in one operation.
+    ///
+    /// Defaults to 8 bytes.
+    pub fn set_max_chunk(&mut self, max_chunk: usize) {
+        self.max_chunk = if max_chunk == 0 { 1 } else { max_chunk };
+    }
+
+    /// Interrupts roughly one in every `interrupt` operations, or never if zero.
+    ///
+    /// Defaults to 4.
+    pub fn set_interrupt(&mut self, interrupt: u32) {
+        self.interrupt = interrupt;
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `Fragment` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn next(&mut self) -> u64 {
+        // xorshift64
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn chunk(&mut self, len: usize) -> io::Result<usize> {
+        if self.interrupt > 0 && self.next() % self.interrupt as u64 == 0 {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "fragment interrupted"))
+        }
+
+        let chunk = (self.next() % self.max_chunk as u64) as usize + 1;
+        Ok(min(chunk, len))
+    }
+}
+
+impl<T: Read> Read for Fragment<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = try!(self.chunk(buf.len()));
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+impl<T: BufRead> BufRead for Fragment<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let len = try!(self.chunk(usize::max_value()));
+        let buf = try!(self.inner.fill_buf());
+        Ok(&buf[..min(len, buf.len())])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<T: Write> Write for Fragment<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = try!(self.chunk(buf.len()));
+        self.inner.write(&buf[..len])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: SeekForward> SeekForward for Fragment<T> {
+    #[inline]
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.inner.seek_forward(offset)
+    }
+}
+
+impl<T: SeekBackward> SeekBackward for Fragment<T> {
+    #[inline]
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        self.inner.seek_backward(offset)
+    }
+}
+
+impl<T: SeekRewind> SeekRewind for Fragment<T> {
+    #[inline]
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.inner.seek_rewind()
+    }
+}
+
+impl<T: SeekAbsolute> SeekAbsolute for Fragment<T> {
+    #[inline]
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.inner.seek_absolute(pos)
+    }
+}
+
+impl<T: SeekEnd> SeekEnd for Fragment<T> {
+    #[inline]
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.inner.seek_end(offset)
+    }
+}
+
+impl<T: Tell> Tell for Fragment<T> {
+    #[inline]
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell()
+    }
+}
+
+#[test]
+fn fragment() {
+    use cursor::Cursor;
+
+    let data: Vec<u8> = (0..0x80).collect();
+
+    let mut w = Fragment::new(Cursor::new(Vec::new()));
+    w.write_all(&data).unwrap();
+    let mut interrupted = false;
+    for _ in 0..0x10 {
+        match w.write(&data) {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => interrupted = true,
+            Err(e) => panic!("{}", e),
+            Ok(len) => assert!(len <= 8),
+        }
+    }
+    assert!(interrupted);
+
+    let mut r = Fragment::with_seed(1, Cursor::new(data.clone()));
+    let mut odata = Vec::new();
+    r.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, data);
+}
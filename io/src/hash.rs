@@ -0,0 +1,277 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::min;
+use seek_forward::{SeekForward, Tell};
+
+/// A hash function or checksum that incrementally digests a stream of bytes.
+pub trait Digest {
+    /// The computed digest.
+    type Output;
+
+    /// Feeds `data` into the digest.
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the digest of all data seen so far.
+    fn finish(&self) -> Self::Output;
+}
+
+impl<'a, D: Digest + ?Sized> Digest for &'a mut D {
+    type Output = D::Output;
+
+    fn update(&mut self, data: &[u8]) {
+        (**self).update(data)
+    }
+
+    fn finish(&self) -> Self::Output {
+        (**self).finish()
+    }
+}
+
+const CRC32_TABLE: [u32; 0x100] = crc32_table(0xedb88320);
+
+const fn crc32_table(poly: u32) -> [u32; 0x100] {
+    let mut table = [0u32; 0x100];
+    let mut i = 0;
+    while i < 0x100 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// The CRC-32 checksum used by zlib, PNG and Ethernet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    /// Creates a new `Crc32` with no data.
+    pub fn new() -> Self {
+        Crc32 {
+            state: !0,
+        }
+    }
+
+    /// Computes the checksum of `data`.
+    pub fn checksum(data: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(data);
+        crc.finish()
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+
+impl Digest for Crc32 {
+    type Output = u32;
+
+    fn update(&mut self, data: &[u8]) {
+        let mut state = self.state;
+        for &b in data {
+            state = CRC32_TABLE[((state ^ b as u32) & 0xff) as usize] ^ (state >> 8);
+        }
+        self.state = state;
+    }
+
+    fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// Digests all bytes read from an underlying stream.
+///
+/// Seeking forward reads through the skipped data so that it is digested
+/// as well. Other seeks would invalidate the digest and are not supported.
+pub struct HashRead<T, H> {
+    inner: T,
+    hasher: H,
+}
+
+impl<T, H> HashRead<T, H> {
+    /// Creates a new `HashRead` that feeds `hasher`.
+    pub fn new(inner: T, hasher: H) -> Self {
+        HashRead {
+            inner: inner,
+            hasher: hasher,
+        }
+    }
+
+    /// Returns a reference to the hasher.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `HashRead` to return the inner stream and hasher.
+    pub fn into_parts(self) -> (T, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<T, H: Digest> HashRead<T, H> {
+    /// Returns the digest of all data read so far.
+    pub fn digest(&self) -> H::Output {
+        self.hasher.finish()
+    }
+}
+
+impl<T: Read, H: Digest> Read for HashRead<T, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read(buf));
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<T: BufRead, H: Digest> BufRead for HashRead<T, H> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0 {
+            // The buffer is unchanged since the caller's fill_buf, so this can't fail
+            if let Ok(buf) = self.inner.fill_buf() {
+                self.hasher.update(&buf[..min(amt, buf.len())]);
+            }
+        }
+
+        self.inner.consume(amt);
+    }
+}
+
+impl<T: Read, H: Digest> SeekForward for HashRead<T, H> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let mut buf = [0u8; 0x200];
+        let mut skipped = 0;
+
+        while skipped < offset {
+            let len = min(offset - skipped, buf.len() as u64) as usize;
+            match try!(self.read(&mut buf[..len])) {
+                0 => break,
+                read => skipped += read as u64,
+            }
+        }
+
+        Ok(skipped)
+    }
+}
+
+impl<T: Tell, H> Tell for HashRead<T, H> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell()
+    }
+}
+
+/// Digests all bytes written to an underlying stream.
+///
+/// Seeking forward writes zeroes so that they are digested as well. Other
+/// seeks would leave gaps in the digest and are not supported.
+pub struct HashWrite<T, H> {
+    inner: T,
+    hasher: H,
+}
+
+impl<T, H> HashWrite<T, H> {
+    /// Creates a new `HashWrite` that feeds `hasher`.
+    pub fn new(inner: T, hasher: H) -> Self {
+        HashWrite {
+            inner: inner,
+            hasher: hasher,
+        }
+    }
+
+    /// Returns a reference to the hasher.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `HashWrite` to return the inner stream and hasher.
+    pub fn into_parts(self) -> (T, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<T, H: Digest> HashWrite<T, H> {
+    /// Returns the digest of all data written so far.
+    pub fn digest(&self) -> H::Output {
+        self.hasher.finish()
+    }
+}
+
+impl<T: Write, H: Digest> Write for HashWrite<T, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = try!(self.inner.write(buf));
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: Write, H: Digest> SeekForward for HashWrite<T, H> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let buf = [0u8; 0x200];
+        let mut remaining = offset;
+
+        while remaining > 0 {
+            let len = min(remaining, buf.len() as u64) as usize;
+            try!(self.write_all(&buf[..len]));
+            remaining -= len as u64;
+        }
+
+        Ok(offset)
+    }
+}
+
+impl<T: Tell, H> Tell for HashWrite<T, H> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell()
+    }
+}
+
+#[test]
+fn crc32() {
+    use std::io::Cursor;
+
+    assert_eq!(Crc32::checksum(b"123456789"), 0xcbf43926);
+
+    let mut read = HashRead::new(Cursor::new(&b"123456789"[..]), Crc32::new());
+    let mut data = [0u8; 4];
+    read.read_exact(&mut data).unwrap();
+    read.consume(1);
+    assert_eq!(read.seek_forward(0x10).unwrap(), 4);
+    assert_eq!(read.digest(), 0xcbf43926);
+
+    let mut write = HashWrite::new(Vec::new(), Crc32::new());
+    write.write_all(b"123456789").unwrap();
+    assert_eq!(write.digest(), 0xcbf43926);
+
+    let mut write = HashWrite::new(Vec::new(), Crc32::new());
+    write.write_all(b"12").unwrap();
+    assert_eq!(write.seek_forward(0x300).unwrap(), 0x300);
+    assert_eq!(write.get_ref().len(), 0x302);
+    assert_eq!(write.digest(), Crc32::checksum(write.get_ref()));
+}
@@ -5,23 +5,97 @@
 extern crate byteorder;
 extern crate uninitialized;
 extern crate resize_slice;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "async")]
+extern crate futures_io;
+#[cfg(feature = "flate2")]
+extern crate flate2;
 
 /// An extension for `Read`ing an exact amount of data.
 pub mod read_exact;
 
+/// An extension for vectored and retrying writes.
+pub mod write_all;
+
 mod seek_forward;
+mod error;
 
 mod buf_seeker;
+mod buf_seek_writer;
 mod region;
+mod slice_region;
+mod window;
 mod align;
+mod tell_ext;
 mod take;
+mod chain;
+mod count;
+mod hash;
+mod copy;
+mod rewind;
+mod peek;
+mod tee;
+mod cursor;
+mod positional;
+mod transform;
+mod vec_writer;
+mod segmented;
+mod either;
+mod null;
+mod stats;
+mod fragment;
+mod cached_position;
+mod bits;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "async")]
+mod async_seek;
+#[cfg(feature = "flate2")]
+mod compress;
 
 pub use seek_forward::{
     SeekRewind, SeekForward, SeekBackward, SeekAbsolute, SeekEnd, Tell,
-    ReadWriteTell, SeekForwardRead, SeekForwardWrite, SeekAbsoluteRewind, SeekAll
+    ReadWriteTell, SeekForwardRead, SeekForwardBufRead, SeekForwardWrite, SeekAbsoluteRewind, SeekAbsoluteForward, SeekAll, StdSeek,
+    SeekBackwardFromAbsolute, SeekBackwardUnsupported, SeekEndUnsupported, SeekEndFromLength
 };
+pub use error::Error;
 pub use read_exact::ReadExactExt;
+pub use write_all::WriteAllExt;
 pub use buf_seeker::BufSeeker;
+pub use buf_seek_writer::BufSeekWriter;
 pub use region::Region;
+pub use slice_region::SliceRegion;
+pub use window::Window;
 pub use align::SeekAlignExt;
+pub use tell_ext::TellExt;
 pub use take::Take;
+pub use chain::Chain;
+pub use count::{CountRead, CountWrite};
+pub use hash::{Digest, Crc32, HashRead, HashWrite};
+pub use copy::copy_exact;
+pub use rewind::RewindableReader;
+pub use peek::PeekRead;
+pub use tee::{TeeRead, BroadcastWrite};
+pub use cursor::Cursor;
+pub use positional::{ReadAt, WriteAt, OffsetCursor};
+pub use transform::Transform;
+pub use vec_writer::VecWriter;
+pub use segmented::SegmentedBuf;
+pub use either::Either;
+pub use null::{Null, Empty};
+pub use stats::{Stats, IoStats};
+pub use fragment::Fragment;
+pub use cached_position::CachedPosition;
+pub use bits::{BitReader, BitWriter};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapStream;
+#[cfg(feature = "async")]
+pub use async_seek::{
+    AsyncSeekRewind, AsyncSeekForward, AsyncSeekBackward, AsyncSeekAbsolute, AsyncSeekEnd, AsyncTell,
+    AsyncSeekAll, AsyncTake, AsyncRegion, AsyncBufSeeker
+};
+#[cfg(feature = "flate2")]
+pub use compress::{DeflateReader, GzReader, DeflateWriter, GzWriter};
+#[cfg(feature = "flate2")]
+pub use flate2::Compression;
@@ -0,0 +1,176 @@
+use std::io::{self, Read, Write, BufRead};
+use std::fs::File;
+use std::cmp::min;
+use memmap2::{Mmap, MmapMut};
+use cursor::Cursor;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// A stream over a memory-mapped file.
+///
+/// `MmapStream<Mmap>` is read-only, while `MmapStream<MmapMut>` also supports
+/// writing within the bounds of the mapping. Writes can't grow the file.
+pub struct MmapStream<M> {
+    inner: Cursor<M>,
+}
+
+impl MmapStream<Mmap> {
+    /// Maps `file` for reading.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, by this or any other process,
+    /// while the mapping is alive.
+    pub unsafe fn open(file: &File) -> io::Result<Self> {
+        Mmap::map(file).map(MmapStream::new)
+    }
+}
+
+impl MmapStream<MmapMut> {
+    /// Maps `file` for reading and writing.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified by any other process or mapping
+    /// while the mapping is alive.
+    pub unsafe fn open_mut(file: &File) -> io::Result<Self> {
+        MmapMut::map_mut(file).map(MmapStream::new)
+    }
+}
+
+impl<M> MmapStream<M> {
+    /// Creates a new `MmapStream` positioned at the start of an existing mapping.
+    pub fn new(map: M) -> Self {
+        MmapStream {
+            inner: Cursor::new(map),
+        }
+    }
+
+    /// Returns a reference to the mapping.
+    pub fn get_ref(&self) -> &M {
+        self.inner.get_ref()
+    }
+
+    /// Unwraps the `MmapStream` to return the mapping.
+    pub fn into_inner(self) -> M {
+        self.inner.into_inner()
+    }
+}
+
+impl<M: AsRef<[u8]>> MmapStream<M> {
+    /// Borrows `len` bytes of the mapping starting at `offset`, without copying.
+    ///
+    /// Returns `None` if the range extends past the end of the mapping.
+    pub fn slice(&self, offset: u64, len: usize) -> Option<&[u8]> {
+        let data = self.inner.get_ref().as_ref();
+        let end = offset.checked_add(len as u64);
+        match end {
+            Some(end) if end <= data.len() as u64 => Some(&data[offset as usize..end as usize]),
+            _ => None,
+        }
+    }
+
+    /// Borrows the unread remainder of the mapping, without copying.
+    pub fn remaining(&self) -> &[u8] {
+        let data = self.inner.get_ref().as_ref();
+        &data[min(self.inner.position(), data.len() as u64) as usize..]
+    }
+}
+
+impl<M: AsRef<[u8]>> Read for MmapStream<M> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<M: AsRef<[u8]>> BufRead for MmapStream<M> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl Write for MmapStream<MmapMut> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let pos = self.inner.position();
+        let len = {
+            let data = self.inner.get_mut().as_mut();
+            let start = min(pos, data.len() as u64) as usize;
+            let len = min(data.len() - start, buf.len());
+            data[start..start + len].copy_from_slice(&buf[..len]);
+            len
+        };
+
+        self.inner.set_position(pos + len as u64);
+        Ok(len)
+    }
+
+    /// Flushes outstanding modifications to the file.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.get_ref().flush()
+    }
+}
+
+impl<M> SeekAbsolute for MmapStream<M> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.inner.seek_absolute(pos)
+    }
+}
+
+impl<M> SeekForward for MmapStream<M> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.inner.seek_forward(offset)
+    }
+}
+
+impl<M> SeekBackward for MmapStream<M> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        self.inner.seek_backward(offset)
+    }
+}
+
+impl<M> SeekRewind for MmapStream<M> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.inner.seek_rewind()
+    }
+}
+
+impl<M: AsRef<[u8]>> SeekEnd for MmapStream<M> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.inner.seek_end(offset)
+    }
+}
+
+impl<M> Tell for MmapStream<M> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell()
+    }
+}
+
+#[test]
+fn mmap_stream() {
+    use std::fs::{self, OpenOptions};
+    use std::env;
+
+    let path = env::temp_dir().join(format!("nue-io-mmap-{}", ::std::process::id()));
+    fs::write(&path, (0..0x20).collect::<Vec<u8>>()).unwrap();
+    let file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+
+    {
+        let mut stream = unsafe { MmapStream::open_mut(&file).unwrap() };
+        stream.seek_end(-4).unwrap();
+        assert_eq!(stream.write(&[0xff; 8]).unwrap(), 4);
+        stream.flush().unwrap();
+    }
+
+    let mut stream = unsafe { MmapStream::open(&file).unwrap() };
+    stream.seek_absolute(0x1c).unwrap();
+    assert_eq!(stream.remaining(), &[0xff; 4]);
+    assert_eq!(stream.slice(0x04, 4).unwrap(), &[4, 5, 6, 7]);
+    assert!(stream.slice(0x1e, 4).is_none());
+
+    drop(stream);
+    fs::remove_file(&path).unwrap();
+}
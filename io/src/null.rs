@@ -0,0 +1,151 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::{min, max};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// A stream that discards everything written to it while keeping track of
+/// position and length, as a file would.
+///
+/// Useful for measuring the encoded size of data without storing it.
+/// Reads always result in EOF.
+#[derive(Clone, Debug, Default)]
+pub struct Null {
+    pos: u64,
+    len: u64,
+}
+
+impl Null {
+    /// Creates a new, empty `Null` stream.
+    pub fn new() -> Self {
+        Null::default()
+    }
+
+    /// Returns the number of bytes the stream would contain.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// A stream that is always at EOF, but may be seeked freely.
+#[derive(Clone, Debug, Default)]
+pub struct Empty {
+    pos: u64,
+}
+
+impl Empty {
+    /// Creates a new `Empty` stream.
+    pub fn new() -> Self {
+        Empty::default()
+    }
+}
+
+fn seek_end(len: u64, offset: i64) -> io::Result<u64> {
+    if offset < 0 {
+        len.checked_sub(offset.wrapping_neg() as u64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position"))
+    } else {
+        Ok(len.saturating_add(offset as u64))
+    }
+}
+
+impl Write for Null {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pos = self.pos.saturating_add(buf.len() as u64);
+        self.len = max(self.len, self.pos);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SeekEnd for Null {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.pos = try!(seek_end(self.len, offset));
+        Ok(self.pos)
+    }
+}
+
+impl SeekEnd for Empty {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.pos = try!(seek_end(0, offset));
+        Ok(self.pos)
+    }
+}
+
+macro_rules! impl_null {
+    ($t:ident) => {
+        impl Read for $t {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Ok(0)
+            }
+        }
+
+        impl BufRead for $t {
+            fn fill_buf(&mut self) -> io::Result<&[u8]> {
+                Ok(&[])
+            }
+
+            fn consume(&mut self, _amt: usize) { }
+        }
+
+        impl SeekAbsolute for $t {
+            fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+                self.pos = pos;
+                Ok(pos)
+            }
+        }
+
+        impl SeekForward for $t {
+            fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+                self.pos = self.pos.saturating_add(offset);
+                Ok(offset)
+            }
+        }
+
+        impl SeekBackward for $t {
+            fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+                let offset = min(offset, self.pos);
+                self.pos -= offset;
+                Ok(offset)
+            }
+        }
+
+        impl SeekRewind for $t {
+            fn seek_rewind(&mut self) -> io::Result<()> {
+                self.pos = 0;
+                Ok(())
+            }
+        }
+
+        impl Tell for $t {
+            fn tell(&mut self) -> io::Result<u64> {
+                Ok(self.pos)
+            }
+        }
+    };
+}
+
+impl_null!(Null);
+impl_null!(Empty);
+
+#[test]
+fn null() {
+    let mut w = Null::new();
+    w.write_all(&[0; 8]).unwrap();
+    w.seek_absolute(2).unwrap();
+    w.write_all(&[0; 2]).unwrap();
+    assert_eq!(w.tell().unwrap(), 4);
+    assert_eq!(w.len(), 8);
+    assert_eq!(w.seek_end(-1).unwrap(), 7);
+
+    let mut r = Empty::new();
+    assert_eq!(r.seek_forward(4).unwrap(), 4);
+    assert_eq!(r.read(&mut [0; 4]).unwrap(), 0);
+    assert!(r.seek_end(-1).is_err());
+}
@@ -0,0 +1,136 @@
+use std::io::{self, Read, BufRead};
+use std::cmp::min;
+use seek_forward::Tell;
+
+/// A reader that allows inspecting upcoming data without consuming it.
+///
+/// Peeked data is held in an internal buffer until it is read.
+pub struct PeekRead<T> {
+    inner: T,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<T> PeekRead<T> {
+    /// Creates a new `PeekRead` around the specified `Read`.
+    pub fn new(inner: T) -> Self {
+        PeekRead {
+            inner: inner,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `PeekRead`, returning the underlying reader.
+    ///
+    /// Note that any peeked data that hasn't been read will be lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn buffered(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn advance(&mut self, amt: usize) {
+        self.pos += amt;
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+        }
+    }
+}
+
+impl<T: Read> PeekRead<T> {
+    /// Returns the next `n` bytes without consuming them.
+    ///
+    /// The result is shorter than `n` only if EOF is reached.
+    pub fn peek(&mut self, n: usize) -> io::Result<&[u8]> {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        let mut len = self.buf.len();
+        if len < n {
+            self.buf.resize(n, 0);
+            while len < n {
+                match self.inner.read(&mut self.buf[len..]) {
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(e) => {
+                        self.buf.truncate(len);
+                        return Err(e)
+                    },
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                }
+            }
+            self.buf.truncate(len);
+        }
+
+        Ok(&self.buf[..min(len, n)])
+    }
+}
+
+impl<T: Read> Read for PeekRead<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.buf.len() {
+            let len = min(buf.len(), self.buf.len() - self.pos);
+            buf[..len].copy_from_slice(&self.buffered()[..len]);
+            self.advance(len);
+            Ok(len)
+        } else {
+            self.inner.read(buf)
+        }
+    }
+}
+
+impl<T: BufRead> BufRead for PeekRead<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos < self.buf.len() {
+            Ok(self.buffered())
+        } else {
+            self.inner.fill_buf()
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.pos < self.buf.len() {
+            self.advance(amt);
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+impl<T: Tell> Tell for PeekRead<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        let buffered = (self.buf.len() - self.pos) as u64;
+        self.inner.tell().map(|v| v - buffered)
+    }
+}
+
+#[test]
+fn peek_read() {
+    let data = b"MAGIC\x01\x02";
+    let mut reader = PeekRead::new(&data[..]);
+
+    assert_eq!(reader.peek(2).unwrap(), b"MA");
+    assert_eq!(reader.peek(5).unwrap(), b"MAGIC");
+
+    let mut odata = [0u8; 3];
+    reader.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, b"MAG");
+    assert_eq!(reader.fill_buf().unwrap(), b"IC");
+    reader.consume(2);
+    assert_eq!(reader.peek(8).unwrap(), b"\x01\x02");
+
+    let mut odata = Vec::new();
+    reader.read_to_end(&mut odata).unwrap();
+    assert_eq!(&odata[..], b"\x01\x02");
+}
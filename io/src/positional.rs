@@ -0,0 +1,197 @@
+use std::io::{self, Read, Write};
+use std::fs::File;
+use std::sync::Arc;
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, Tell};
+
+/// Reads from a specific offset without a shared stream position.
+pub trait ReadAt {
+    /// Reads into `buf` starting at `offset`, returning the number of bytes read.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+}
+
+/// Writes to a specific offset without a shared stream position.
+pub trait WriteAt {
+    /// Writes `buf` starting at `offset`, returning the number of bytes written.
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize>;
+}
+
+impl<'a, T: ReadAt + ?Sized> ReadAt for &'a T {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+}
+
+impl<'a, T: WriteAt + ?Sized> WriteAt for &'a T {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        (**self).write_at(buf, offset)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+}
+
+impl<T: WriteAt + ?Sized> WriteAt for Arc<T> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        (**self).write_at(buf, offset)
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let offset = min(offset, self.len() as u64) as usize;
+        let len = min(self.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self[offset..offset + len]);
+        Ok(len)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+}
+
+/// Backed by `pread`.
+#[cfg(unix)]
+impl ReadAt for File {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        ::std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+}
+
+/// Backed by `pwrite`.
+#[cfg(unix)]
+impl WriteAt for File {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        ::std::os::unix::fs::FileExt::write_at(self, buf, offset)
+    }
+}
+
+/// Note that this moves the file's own position.
+#[cfg(windows)]
+impl ReadAt for File {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        ::std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+}
+
+/// Note that this moves the file's own position.
+#[cfg(windows)]
+impl WriteAt for File {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        ::std::os::windows::fs::FileExt::seek_write(self, buf, offset)
+    }
+}
+
+/// A stream with its own position over a `ReadAt` or `WriteAt` source.
+///
+/// Multiple cursors may share a source, such as `&File` or `Arc<File>`,
+/// without interfering with each other's position.
+/// Wrap in `SeekEndFromLength` to seek relative to a known end.
+pub struct OffsetCursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> OffsetCursor<T> {
+    /// Creates a new `OffsetCursor` positioned at `pos`.
+    pub fn new(inner: T, pos: u64) -> Self {
+        OffsetCursor {
+            inner: inner,
+            pos: pos,
+        }
+    }
+
+    /// Returns a reference to the underlying source.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `OffsetCursor` to return the underlying source.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ReadAt> Read for OffsetCursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read_at(buf, self.pos));
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T: WriteAt> Write for OffsetCursor<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = try!(self.inner.write_at(buf, self.pos));
+        self.pos += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> SeekAbsolute for OffsetCursor<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+impl<T> SeekForward for OffsetCursor<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.pos = self.pos.saturating_add(offset);
+        Ok(offset)
+    }
+}
+
+impl<T> SeekBackward for OffsetCursor<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, self.pos);
+        self.pos -= offset;
+        Ok(offset)
+    }
+}
+
+impl<T> SeekRewind for OffsetCursor<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T> Tell for OffsetCursor<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn offset_cursor() {
+    let data: Vec<u8> = (0..0x20).collect();
+    let mut a = OffsetCursor::new(&data, 0);
+    let mut b = OffsetCursor::new(&data, 0x10);
+
+    let mut odata = [0u8; 4];
+    a.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[..4]);
+    b.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[0x10..0x14]);
+    a.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[4..8]);
+}
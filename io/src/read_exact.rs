@@ -1,31 +1,46 @@
 use std::io::{self, Read};
+use error::Error as NueError;
 
-pub use byteorder::Error;
+/// The error type previously returned by `ReadExactExt::read_exact`.
+///
+/// Short reads are now reported as `io::ErrorKind::UnexpectedEof`.
+#[deprecated(note = "use `std::io::Error` instead")]
+pub type Error = ::byteorder::Error;
 
-/// Extension trait that provides `read_exact` for all `Read` implementations.
+/// Extension trait that provides exact reads for all `Read` implementations.
+///
+/// Use `std::io::Read::read_exact` to fill a buffer, which fails with
+/// `io::ErrorKind::UnexpectedEof` upon a short read.
 pub trait ReadExactExt {
-    /// Reads into the entirety of `buf` or fails with an error.
-    ///
-    /// The `Read` equivalent of `Write::write_all`.
-    /// Retries upon `Interrupted` errors.
-    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error>;
-
     /// Reads as much as possible into `buf` until EOF.
     ///
     /// Retries upon `Interrupted` errors.
     fn read_exact_eof(&mut self, buf: &mut [u8]) -> io::Result<usize>;
-}
 
-impl<R: Read> ReadExactExt for R {
-    #[inline]
-    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
-        match self.read_exact_eof(buf) {
-            Ok(len) if len == buf.len() => Ok(()),
-            Ok(_) => Err(Error::UnexpectedEOF),
-            Err(e) => Err(e.into()),
+    /// Appends exactly `len` bytes to `buf` or fails with an error.
+    ///
+    /// The new space isn't zero-filled before being read into. `buf` is left
+    /// unchanged upon failure.
+    fn read_exact_uninit(&mut self, buf: &mut Vec<u8>, len: usize) -> io::Result<()>;
+
+    /// Reads exactly `len` bytes into a new `Vec` or fails with an error.
+    fn read_vec_exact(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.read_exact_uninit(&mut vec, len).map(|_| vec)
+    }
+
+    /// Reads `expected.len()` bytes, failing with `Error::Magic` if they differ from `expected`.
+    fn expect_exact(&mut self, expected: &[u8]) -> io::Result<()> {
+        let found = try!(self.read_vec_exact(expected.len()));
+        if found == expected {
+            Ok(())
+        } else {
+            Err(NueError::Magic { expected: expected.to_owned(), found: found }.into())
         }
     }
+}
 
+impl<R: Read> ReadExactExt for R {
     #[inline]
     fn read_exact_eof(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let mut n = 0;
@@ -40,4 +55,37 @@ impl<R: Read> ReadExactExt for R {
 
         Ok(n)
     }
+
+    fn read_exact_uninit(&mut self, buf: &mut Vec<u8>, len: usize) -> io::Result<()> {
+        let start = buf.len();
+        let res = match self.by_ref().take(len as u64).read_to_end(buf) {
+            Ok(read) if read < len => Err(NueError::UnexpectedEof { needed: Some((len - read) as u64) }.into()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if res.is_err() {
+            buf.truncate(start);
+        }
+        res
+    }
+}
+
+#[test]
+fn read_vec_exact() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..0x10).collect();
+    let mut cursor = Cursor::new(&data[..]);
+    assert_eq!(cursor.read_vec_exact(8).unwrap(), &data[..8]);
+
+    let mut vec = vec![0xff];
+    assert_eq!(cursor.read_exact_uninit(&mut vec, 0x10).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(vec, [0xff]);
+}
+
+#[test]
+fn expect_exact() {
+    let mut data = &b"RIFFWAVE"[..];
+    data.expect_exact(b"RIFF").unwrap();
+    assert_eq!(data.expect_exact(b"AVI ").unwrap_err().kind(), io::ErrorKind::InvalidData);
 }
@@ -1,15 +1,19 @@
 use std::io::{self, Read, Write, BufRead};
 use std::cmp::min;
 use seek_forward::{SeekForward, SeekAbsolute, Tell, SeekRewind, SeekEnd, SeekBackward};
+use error::Error;
 
 /// Creates an isolated segment of an underlying stream.
 ///
 /// Seeks past the region will be capped, and reaching the end of
 /// the region will result in EOF when reading or writing.
+///
+/// See `Window` for streams that don't support `Tell` and `SeekAbsolute`.
 pub struct Region<T> {
     inner: T,
     start: u64,
     end: u64,
+    strict: bool,
 }
 
 impl<T> Region<T> {
@@ -19,10 +23,60 @@ impl<T> Region<T> {
             inner: inner,
             start: start,
             end: end,
+            strict: false,
+        }
+    }
+
+    /// Creates a new `Region`, failing with `InvalidInput` if the bounds are inverted.
+    pub fn try_new(inner: T, start: u64, end: u64) -> io::Result<Self> {
+        if start > end {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("region start {} is past its end {}", start, end)))
         }
+
+        Ok(Region::new(inner, start, end))
+    }
+
+    /// Creates a new open-ended `Region` that covers the remainder of `inner`
+    /// from the `start` offset onward.
+    pub fn from_start(inner: T, start: u64) -> Self {
+        Region::new(inner, start, u64::MAX)
+    }
+
+    /// Returns whether the region extends to the end of the inner stream.
+    pub fn is_open_ended(&self) -> bool {
+        self.end == u64::MAX
+    }
+
+    /// Sets whether writes past the end of the region fail.
+    ///
+    /// By default they are truncated, resulting in a short write. In strict
+    /// mode a write that doesn't fit fails with `Error::Limit` without writing
+    /// anything, so overflowing a fixed-size section can be detected.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns whether writes past the end of the region fail.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns the length of the region.
+    ///
+    /// An open-ended region has no known length, and extends to `u64::MAX`.
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns whether the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Returns the region bounds.
+    ///
+    /// The end of an open-ended region is `u64::MAX`.
     pub fn region(&self) -> (u64, u64) {
         (self.start, self.end)
     }
@@ -31,6 +85,41 @@ impl<T> Region<T> {
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    /// Creates a nested `Region` that borrows the inner stream.
+    ///
+    /// The offsets are relative to the start of this region. Fails with
+    /// `InvalidInput` if the bounds are inverted or do not fit inside this region.
+    /// The nested region inherits strict mode.
+    pub fn sub_region(&mut self, start: u64, end: u64) -> io::Result<Region<&mut T>> {
+        let len = self.end.saturating_sub(self.start);
+        if start > end || end > len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("sub region {}..{} does not fit in region of length {}", start, end, len)))
+        }
+
+        let mut region = Region::new(&mut self.inner, self.start + start, self.start + end);
+        region.strict = self.strict;
+        Ok(region)
+    }
+}
+
+impl<T: SeekEnd + SeekAbsolute> Region<T> {
+    /// Creates a new `Region`, validating the bounds against the length of `inner`.
+    ///
+    /// Fails with `InvalidInput` if the bounds are inverted or extend past
+    /// the end of the stream. On success the stream is positioned at `start`.
+    pub fn try_new_within(mut inner: T, start: u64, end: u64) -> io::Result<Self> {
+        let len = try!(inner.seek_end(0));
+        let bound = if end == u64::MAX { start } else { end };
+        if bound > len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("region {}..{} extends past the end of the stream at {}", start, end, len)))
+        }
+
+        try!(inner.seek_absolute(start));
+        Region::try_new(inner, start, end)
+    }
 }
 
 impl<T: Tell + SeekAbsolute> Region<T> {
@@ -44,6 +133,12 @@ impl<T: Tell + SeekAbsolute> Region<T> {
         }
     }
 
+    /// Returns the number of bytes between the current position and the end of the region.
+    pub fn remaining(&mut self) -> io::Result<u64> {
+        let pos = try!(self.position());
+        Ok(self.end.saturating_sub(pos))
+    }
+
     fn limit(&mut self, len: u64) -> io::Result<u64> {
         let pos = try!(self.position());
         let end = self.end;
@@ -79,7 +174,9 @@ impl<T: Write + Tell + SeekAbsolute> Write for Region<T> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let len = try!(self.limit(buf.len() as u64)) as usize;
 
-        if len == 0 {
+        if self.strict && len < buf.len() {
+            Err(Error::Limit { limit: len as u64 }.into())
+        } else if len == 0 {
             Ok(0)
         } else {
             self.inner.write(&buf[..len])
@@ -106,7 +203,7 @@ impl<T: SeekForward + Tell + SeekAbsolute> SeekForward for Region<T> {
 
 impl<T: Tell + SeekBackward> SeekBackward for Region<T> {
     fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
-        let off = try!(self.inner.tell()).saturating_sub(self.start);;
+        let off = try!(self.inner.tell()).saturating_sub(self.start);
         let offset = min(off, offset);
         self.inner.seek_backward(offset)
     }
@@ -119,7 +216,14 @@ impl<T: SeekAbsolute> SeekRewind for Region<T> {
 }
 
 impl<T: SeekAbsolute> SeekEnd for Region<T> {
+    /// Seeks relative to the end of the region.
+    ///
+    /// Open-ended regions have no known end, and fail with `InvalidInput`.
     fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        if self.is_open_ended() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot seek from the end of an open-ended region"))
+        }
+
         self.inner.seek_absolute((self.end as i64 + offset) as u64).map(|v| v - self.start)
     }
 }
@@ -147,10 +251,10 @@ impl<T: BufRead + Tell + SeekAbsolute> BufRead for Region<T> {
 
 #[cfg(test)]
 mod tests {
-    use std::io::{Cursor, Read};
+    use std::io::Read;
     use super::Region;
-    use read_exact::ReadExactExt;
-    use seek_forward::{SeekAll, SeekAbsolute};
+    use cursor::Cursor;
+    use seek_forward::{SeekAbsolute, SeekEnd};
 
     fn data(count: usize) -> Vec<u8> {
         use std::iter::{repeat};
@@ -160,7 +264,7 @@ mod tests {
     #[test]
     fn region() {
         let data = data(0x100);
-        let cursor = SeekAll::new(Cursor::new(data.clone()));
+        let cursor = Cursor::new(data.clone());
 
         let mut region = Region::new(cursor, 0x40, 0x80);
         let mut odata = vec![0u8; 0x40];
@@ -175,4 +279,62 @@ mod tests {
         region.read_exact(&mut odata[..]).unwrap();
         assert_eq!(&odata[..], &data[0x40..0x80]);
     }
+
+    #[test]
+    fn open_region() {
+        let data = data(0x100);
+        let cursor = Cursor::new(data.clone());
+
+        let mut region = Region::from_start(cursor, 0xf0);
+        let mut odata = Vec::new();
+        region.read_to_end(&mut odata).unwrap();
+        assert_eq!(&odata[..], &data[0xf0..]);
+        assert!(region.seek_end(0).is_err());
+    }
+
+    #[test]
+    fn strict_region() {
+        use std::io::Write;
+
+        let cursor = Cursor::new(vec![0u8; 0x10]);
+        let mut region = Region::new(cursor, 4, 8);
+        region.set_strict(true);
+        region.write_all(&[1, 2]).unwrap();
+        assert_eq!(region.remaining().unwrap(), 2);
+
+        let err = region.write(&[3, 4, 5]).unwrap_err();
+        assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+        assert_eq!(region.remaining().unwrap(), 2);
+        region.write_all(&[3, 4]).unwrap();
+        assert_eq!(region.into_inner().into_inner(), [0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn region_bounds() {
+        let data = data(0x20);
+
+        assert!(Region::try_new(Cursor::new(data.clone()), 8, 4).is_err());
+        assert!(Region::try_new_within(Cursor::new(data.clone()), 0x10, 0x30).is_err());
+        assert!(Region::try_new_within(Cursor::new(data.clone()), 0x30, u64::MAX).is_err());
+
+        let region = Region::try_new_within(Cursor::new(data.clone()), 0x10, 0x20).unwrap();
+        assert_eq!(region.len(), 0x10);
+        assert!(!region.is_empty());
+        assert!(Region::new(Cursor::new(data), 4, 4).is_empty());
+    }
+
+    #[test]
+    fn sub_region() {
+        let data = data(0x100);
+        let cursor = Cursor::new(data.clone());
+
+        let mut region = Region::new(cursor, 0x40, 0x80);
+        assert!(region.sub_region(0x20, 0x50).is_err());
+        assert!(region.sub_region(0x20, 0x10).is_err());
+
+        let mut sub = region.sub_region(0x10, 0x20).unwrap();
+        let mut odata = Vec::new();
+        sub.read_to_end(&mut odata).unwrap();
+        assert_eq!(&odata[..], &data[0x50..0x60]);
+    }
 }
@@ -0,0 +1,157 @@
+use std::io::{self, Read};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, Tell};
+
+const DEFAULT_WINDOW: usize = 0x400 * 0x40;
+
+/// A reader that records data read from a non-seekable stream so that it
+/// can be rewound and read again.
+///
+/// At least the most recent `window` bytes are retained. Seeking further
+/// back than has been retained fails with `InvalidInput`.
+pub struct RewindableReader<T> {
+    inner: T,
+    buf: Vec<u8>,
+    pos: usize,
+    window: usize,
+    discarded: u64,
+}
+
+impl<T> RewindableReader<T> {
+    /// Creates a new `RewindableReader` around the specified `Read`.
+    pub fn new(inner: T) -> Self {
+        RewindableReader::with_window(DEFAULT_WINDOW, inner)
+    }
+
+    /// Creates a `RewindableReader` that retains at least `window` bytes.
+    pub fn with_window(window: usize, inner: T) -> Self {
+        RewindableReader {
+            inner: inner,
+            buf: Vec::new(),
+            pos: 0,
+            window: window,
+            discarded: 0,
+        }
+    }
+
+    /// Returns the number of bytes guaranteed to be retained.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Unwraps the `RewindableReader`, returning the underlying reader.
+    ///
+    /// Note that any data that has been rewound over will be lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn trim(&mut self) {
+        // Trimming is deferred until twice the window is buffered to amortize the shift
+        if self.buf.len() > self.window.saturating_mul(2) {
+            let excess = min(self.buf.len() - self.window, self.pos);
+            self.buf.drain(..excess);
+            self.pos -= excess;
+            self.discarded += excess as u64;
+        }
+    }
+
+    fn out_of_window() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, "seek past the start of the retained window")
+    }
+}
+
+impl<T: Read> Read for RewindableReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.buf.len() {
+            let len = min(buf.len(), self.buf.len() - self.pos);
+            buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        } else {
+            let read = try!(self.inner.read(buf));
+            self.buf.extend_from_slice(&buf[..read]);
+            self.pos += read;
+            self.trim();
+            Ok(read)
+        }
+    }
+}
+
+impl<T: Read> SeekForward for RewindableReader<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let buffered = min((self.buf.len() - self.pos) as u64, offset);
+        self.pos += buffered as usize;
+
+        let mut buf = [0u8; 0x200];
+        let mut skipped = buffered;
+        while skipped < offset {
+            let len = min(offset - skipped, buf.len() as u64) as usize;
+            match try!(self.read(&mut buf[..len])) {
+                0 => break,
+                read => skipped += read as u64,
+            }
+        }
+
+        Ok(skipped)
+    }
+}
+
+impl<T> SeekBackward for RewindableReader<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        if offset > self.pos as u64 {
+            return Err(Self::out_of_window())
+        }
+
+        self.pos -= offset as usize;
+        Ok(offset)
+    }
+}
+
+impl<T> SeekRewind for RewindableReader<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        if self.discarded > 0 {
+            return Err(Self::out_of_window())
+        }
+
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T: Read> SeekAbsolute for RewindableReader<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        let current = self.discarded + self.pos as u64;
+        if pos >= current {
+            self.seek_forward(pos - current).map(|v| current + v)
+        } else {
+            self.seek_backward(current - pos).map(|_| pos)
+        }
+    }
+}
+
+impl<T> Tell for RewindableReader<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.discarded + self.pos as u64)
+    }
+}
+
+#[test]
+fn rewindable_reader() {
+    let data: Vec<u8> = (0..0x40).collect();
+    let mut reader = RewindableReader::with_window(0x08, &data[..]);
+
+    let mut odata = [0u8; 0x04];
+    reader.read_exact(&mut odata).unwrap();
+    reader.seek_rewind().unwrap();
+    reader.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[..0x04]);
+
+    assert_eq!(reader.seek_forward(0x20).unwrap(), 0x20);
+    assert!(reader.seek_rewind().is_err());
+    assert_eq!(reader.seek_backward(0x08).unwrap(), 0x08);
+    reader.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[0x1c..0x20]);
+    assert_eq!(reader.tell().unwrap(), 0x20);
+    assert!(reader.seek_absolute(0x04).is_err());
+}
@@ -1,4 +1,5 @@
-use std::io::{self, Read, Write, BufRead, Seek, SeekFrom, copy, sink, repeat};
+use std::io::{self, Read, Write, BufRead, Seek, SeekFrom, IoSlice, copy, sink};
+use std::cmp::min;
 
 /// A limited form of seeking that can only be reset from the beginning.
 ///
@@ -133,10 +134,20 @@ impl<T: SeekEnd + ?Sized> SeekEnd for Box<T> {
 }
 
 /// A forward seeking wrapper around a `Read` type.
+///
+/// Prefer `SeekForwardBufRead` when the inner type implements `BufRead`.
 pub struct SeekForwardRead<T> {
     inner: T,
 }
 
+/// A forward seeking wrapper around a `BufRead` type.
+///
+/// Unlike `SeekForwardRead`, skipped data is consumed directly from the
+/// inner buffer rather than being copied out.
+pub struct SeekForwardBufRead<T> {
+    inner: T,
+}
+
 /// A forward seeking wrapper around a `Write` type.
 pub struct SeekForwardWrite<T> {
     inner: T,
@@ -147,11 +158,56 @@ pub struct SeekAbsoluteRewind<T> {
     inner: T,
 }
 
+/// An absolute seeking wrapper around a `Tell + SeekForward` type.
+///
+/// Seeks behind the current position fail with `ErrorKind::Unsupported`.
+pub struct SeekAbsoluteForward<T> {
+    inner: T,
+}
+
 /// A wrapper that decomposes `Seek` into its individual traits.
 pub struct SeekAll<T> {
     inner: T,
 }
 
+/// A wrapper that implements `std::io::Seek` in terms of the individual seek traits.
+///
+/// Relative seeks are performed with `Tell` and `SeekAbsolute`, so the inner
+/// stream doesn't need to support `SeekForward` or `SeekBackward`. It must
+/// support all of `Tell`, `SeekAbsolute` and `SeekEnd` though; forward-only
+/// streams can be wrapped in `SeekAbsoluteForward` and `SeekEndUnsupported`,
+/// so that only `SeekFrom::Start` and `SeekFrom::Current` moving forward succeed.
+pub struct StdSeek<T> {
+    inner: T,
+}
+
+/// A backward seeking wrapper around a `Tell + SeekAbsolute` type.
+pub struct SeekBackwardFromAbsolute<T> {
+    inner: T,
+}
+
+/// A wrapper that implements `SeekEnd` for a `SeekAbsolute` type of known length.
+pub struct SeekEndFromLength<T> {
+    inner: T,
+    len: u64,
+}
+
+/// A wrapper that implements `SeekBackward` for streams that can't seek backward.
+///
+/// Backward seeks fail with `ErrorKind::Unsupported`. This allows wrappers that
+/// can usually satisfy them on their own, such as `BufSeeker` with a lookback
+/// window, to be used over pipes and other unseekable streams.
+pub struct SeekBackwardUnsupported<T> {
+    inner: T,
+}
+
+/// A wrapper that implements `SeekEnd` for streams that can't seek from their end.
+///
+/// Seeks from the end fail with `ErrorKind::Unsupported`.
+pub struct SeekEndUnsupported<T> {
+    inner: T,
+}
+
 /// A wrapper that implements `Tell` for streams that don't support it.
 pub struct ReadWriteTell<T> {
     inner: T,
@@ -168,13 +224,54 @@ impl<T: Read> SeekForward for SeekForwardRead<T> {
     }
 }
 
+impl<T: BufRead> SeekForward for SeekForwardBufRead<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let mut skipped = 0;
+        while skipped < offset {
+            let len = match self.inner.fill_buf() {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+                Ok(buf) => min(buf.len() as u64, offset - skipped) as usize,
+            };
+
+            if len == 0 {
+                break
+            }
+
+            self.inner.consume(len);
+            skipped += len as u64;
+        }
+
+        Ok(skipped)
+    }
+}
+
+static ZEROES: [u8; 0x2000] = [0; 0x2000];
+const ZEROES_SLICES: usize = 8;
+
 impl<T: Write> SeekForward for SeekForwardWrite<T> {
+    /// Writes `offset` zero bytes, in vectored chunks of up to 64KiB.
     fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
-        if offset == 0 {
-            Ok(0)
-        } else {
-            copy(&mut repeat(0).take(offset), self)
+        let slices = [IoSlice::new(&ZEROES); ZEROES_SLICES];
+        let mut remaining = offset;
+
+        while remaining > 0 {
+            let count = min(remaining / ZEROES.len() as u64, ZEROES_SLICES as u64) as usize;
+            let res = if count > 0 {
+                self.inner.write_vectored(&slices[..count])
+            } else {
+                self.inner.write(&ZEROES[..remaining as usize])
+            };
+
+            match res {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write padding")),
+                Ok(len) => remaining -= len as u64,
+            }
         }
+
+        Ok(offset)
     }
 }
 
@@ -196,6 +293,17 @@ impl<T: Tell + SeekForward + SeekRewind> SeekAbsolute for SeekAbsoluteRewind<T>
     }
 }
 
+impl<T: Tell + SeekForward> SeekAbsolute for SeekAbsoluteForward<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        let tell = try!(self.inner.tell());
+        if pos < tell {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "stream cannot seek backward"))
+        }
+
+        self.inner.seek_forward(pos - tell).map(|v| tell + v)
+    }
+}
+
 impl<T> Tell for ReadWriteTell<T> {
     #[inline]
     fn tell(&mut self) -> io::Result<u64> {
@@ -280,26 +388,59 @@ impl<T: SeekAbsolute> SeekAbsolute for ReadWriteTell<T> {
     }
 }
 
-/*impl<T: Tell + SeekForward + SeekBackward + SeekAbsolute + SeekEnd> Seek for T {
+impl<T: Tell + SeekAbsolute + SeekEnd> Seek for StdSeek<T> {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         match pos {
             SeekFrom::Start(pos) => self.inner.seek_absolute(pos),
-            SeekFrom::Current(offset) if offset > 0 =>
-                self.inner.seek_forward(offset as u64).and_then(|_| self.inner.tell()),
-            SeekFrom::Current(offset) if offset == 0 => self.inner.tell(),
-            SeekFrom::Current(offset) => self.inner.seek_backward(-offset as u64).and_then(|_| self.inner.tell()),
-            SeekFrom::End(offset) => self.seek_end(offset),
+            SeekFrom::Current(0) => self.inner.tell(),
+            SeekFrom::Current(offset) => {
+                let pos = try!(self.inner.tell());
+                let pos = if offset < 0 {
+                    pos.checked_sub(offset.wrapping_neg() as u64)
+                } else {
+                    pos.checked_add(offset as u64)
+                };
+
+                match pos {
+                    Some(pos) => self.inner.seek_absolute(pos),
+                    None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position")),
+                }
+            },
+            SeekFrom::End(offset) => self.inner.seek_end(offset),
         }
     }
-}*/
+}
 
-/*impl<T: Tell + SeekAbsolute> SeekBackward for _<T> {
-    #[inline]
+impl<T: Tell + SeekAbsolute> SeekBackward for SeekBackwardFromAbsolute<T> {
     fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
-        let pos = try!(self.inner.tell()).saturating_sub(-offset as u64);
+        let pos = try!(self.inner.tell());
+        self.inner.seek_absolute(pos.saturating_sub(offset)).map(|v| pos.saturating_sub(v))
+    }
+}
+
+impl<T> SeekBackward for SeekBackwardUnsupported<T> {
+    fn seek_backward(&mut self, _offset: u64) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "stream cannot seek backward"))
+    }
+}
+
+impl<T> SeekEnd for SeekEndUnsupported<T> {
+    fn seek_end(&mut self, _offset: i64) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "stream cannot seek from its end"))
+    }
+}
+
+impl<T: SeekAbsolute> SeekEnd for SeekEndFromLength<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let pos = if offset < 0 {
+            self.len.saturating_sub(offset.wrapping_neg() as u64)
+        } else {
+            self.len.saturating_add(offset as u64)
+        };
+
         self.inner.seek_absolute(pos)
     }
-}*/
+}
 
 impl<T: Seek> Tell for SeekAll<T> {
     #[inline]
@@ -332,7 +473,7 @@ impl<T: Seek> SeekRewind for SeekAll<T> {
 impl<T: Seek> SeekBackward for SeekAll<T> {
     #[inline]
     fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
-        self.inner.seek(SeekFrom::Current(-(offset as i64)))
+        self.inner.seek(SeekFrom::Current(-(offset as i64))).map(|_| offset)
     }
 }
 
@@ -436,6 +577,14 @@ impl_seek!(SeekForwardRead => SeekBackward);
 impl_seek!(SeekForwardRead => BufRead);
 impl_seek!(SeekForwardRead => Read);
 
+impl_seek!(SeekForwardBufRead => SeekRewind);
+impl_seek!(SeekForwardBufRead => Tell);
+impl_seek!(SeekForwardBufRead => SeekAbsolute);
+impl_seek!(SeekForwardBufRead => SeekEnd);
+impl_seek!(SeekForwardBufRead => SeekBackward);
+impl_seek!(SeekForwardBufRead => BufRead);
+impl_seek!(SeekForwardBufRead => Read);
+
 impl_seek!(SeekForwardWrite => SeekRewind);
 impl_seek!(SeekForwardWrite => Tell);
 impl_seek!(SeekForwardWrite => SeekAbsolute);
@@ -452,10 +601,65 @@ impl_seek!(SeekAbsoluteRewind => Read);
 impl_seek!(SeekAbsoluteRewind => Write);
 impl_seek!(SeekAbsoluteRewind => BufRead);
 
+impl_seek!(SeekAbsoluteForward => SeekRewind);
+impl_seek!(SeekAbsoluteForward => Tell);
+impl_seek!(SeekAbsoluteForward => SeekForward);
+impl_seek!(SeekAbsoluteForward => SeekEnd);
+impl_seek!(SeekAbsoluteForward => SeekBackward);
+impl_seek!(SeekAbsoluteForward => Read);
+impl_seek!(SeekAbsoluteForward => Write);
+impl_seek!(SeekAbsoluteForward => BufRead);
+
 impl_seek!(SeekAll => BufRead);
 impl_seek!(SeekAll => Read);
 impl_seek!(SeekAll => Write);
 
+impl_seek!(SeekBackwardFromAbsolute => SeekRewind);
+impl_seek!(SeekBackwardFromAbsolute => Tell);
+impl_seek!(SeekBackwardFromAbsolute => SeekForward);
+impl_seek!(SeekBackwardFromAbsolute => SeekAbsolute);
+impl_seek!(SeekBackwardFromAbsolute => SeekEnd);
+impl_seek!(SeekBackwardFromAbsolute => BufRead);
+impl_seek!(SeekBackwardFromAbsolute => Read);
+impl_seek!(SeekBackwardFromAbsolute => Write);
+
+impl_seek!(SeekEndFromLength => SeekRewind);
+impl_seek!(SeekEndFromLength => Tell);
+impl_seek!(SeekEndFromLength => SeekForward);
+impl_seek!(SeekEndFromLength => SeekAbsolute);
+impl_seek!(SeekEndFromLength => SeekBackward);
+impl_seek!(SeekEndFromLength => BufRead);
+impl_seek!(SeekEndFromLength => Read);
+impl_seek!(SeekEndFromLength => Write);
+
+impl_seek!(SeekBackwardUnsupported => SeekRewind);
+impl_seek!(SeekBackwardUnsupported => Tell);
+impl_seek!(SeekBackwardUnsupported => SeekForward);
+impl_seek!(SeekBackwardUnsupported => SeekAbsolute);
+impl_seek!(SeekBackwardUnsupported => SeekEnd);
+impl_seek!(SeekBackwardUnsupported => BufRead);
+impl_seek!(SeekBackwardUnsupported => Read);
+impl_seek!(SeekBackwardUnsupported => Write);
+
+impl_seek!(SeekEndUnsupported => SeekRewind);
+impl_seek!(SeekEndUnsupported => Tell);
+impl_seek!(SeekEndUnsupported => SeekForward);
+impl_seek!(SeekEndUnsupported => SeekAbsolute);
+impl_seek!(SeekEndUnsupported => SeekBackward);
+impl_seek!(SeekEndUnsupported => BufRead);
+impl_seek!(SeekEndUnsupported => Read);
+impl_seek!(SeekEndUnsupported => Write);
+
+impl_seek!(StdSeek => SeekRewind);
+impl_seek!(StdSeek => Tell);
+impl_seek!(StdSeek => SeekForward);
+impl_seek!(StdSeek => SeekAbsolute);
+impl_seek!(StdSeek => SeekEnd);
+impl_seek!(StdSeek => SeekBackward);
+impl_seek!(StdSeek => BufRead);
+impl_seek!(StdSeek => Read);
+impl_seek!(StdSeek => Write);
+
 impl<T> SeekForwardRead<T> {
     /// Creates a new `SeekForwardRead`.
     pub fn new(inner: T) -> Self {
@@ -465,6 +669,15 @@ impl<T> SeekForwardRead<T> {
     }
 }
 
+impl<T> SeekForwardBufRead<T> {
+    /// Creates a new `SeekForwardBufRead`.
+    pub fn new(inner: T) -> Self {
+        SeekForwardBufRead {
+            inner: inner,
+        }
+    }
+}
+
 impl<T> SeekForwardWrite<T> {
     /// Creates a new `SeekForwardWrite`.
     pub fn new(inner: T) -> Self {
@@ -483,6 +696,15 @@ impl<T> SeekAbsoluteRewind<T> {
     }
 }
 
+impl<T> SeekAbsoluteForward<T> {
+    /// Creates a new `SeekAbsoluteForward`.
+    pub fn new(inner: T) -> Self {
+        SeekAbsoluteForward {
+            inner: inner,
+        }
+    }
+}
+
 impl<T> ReadWriteTell<T> {
     /// Creates a new `ReadWriteTell`.
     pub fn new(inner: T) -> Self {
@@ -501,3 +723,137 @@ impl<T> SeekAll<T> {
         }
     }
 }
+
+impl<T> SeekBackwardFromAbsolute<T> {
+    /// Creates a new `SeekBackwardFromAbsolute`.
+    pub fn new(inner: T) -> Self {
+        SeekBackwardFromAbsolute {
+            inner: inner,
+        }
+    }
+}
+
+impl<T> SeekBackwardUnsupported<T> {
+    /// Creates a new `SeekBackwardUnsupported`.
+    pub fn new(inner: T) -> Self {
+        SeekBackwardUnsupported {
+            inner: inner,
+        }
+    }
+}
+
+impl<T> SeekEndUnsupported<T> {
+    /// Creates a new `SeekEndUnsupported`.
+    pub fn new(inner: T) -> Self {
+        SeekEndUnsupported {
+            inner: inner,
+        }
+    }
+}
+
+impl<T> SeekEndFromLength<T> {
+    /// Creates a new `SeekEndFromLength` for a stream that is `len` bytes long.
+    pub fn new(inner: T, len: u64) -> Self {
+        SeekEndFromLength {
+            inner: inner,
+            len: len,
+        }
+    }
+
+    /// Returns the length of the stream.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the stream is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> StdSeek<T> {
+    /// Creates a new `StdSeek`.
+    pub fn new(inner: T) -> Self {
+        StdSeek {
+            inner: inner,
+        }
+    }
+
+    /// Unwraps the `StdSeek` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[test]
+fn std_seek() {
+    use std::io::Cursor;
+    use region::Region;
+
+    let data: Vec<u8> = (0..0x20).collect();
+    let region = Region::new(SeekAll::new(Cursor::new(&data[..])), 0x08, 0x18);
+    let mut seek = StdSeek::new(region);
+
+    assert_eq!(seek.seek(SeekFrom::End(-4)).unwrap(), 0x0c);
+    assert_eq!(seek.seek(SeekFrom::Current(-8)).unwrap(), 0x04);
+    assert!(seek.seek(SeekFrom::Current(-8)).is_err());
+
+    let mut odata = [0u8; 4];
+    seek.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[0x0c..0x10]);
+}
+
+#[test]
+fn std_seek_forward() {
+    let data: Vec<u8> = (0..0x20).collect();
+    let stream = ReadWriteTell::new(SeekForwardRead::new(&data[..]));
+    let mut seek = StdSeek::new(SeekEndUnsupported::new(SeekAbsoluteForward::new(stream)));
+
+    assert_eq!(seek.seek(SeekFrom::Current(4)).unwrap(), 0x04);
+    assert_eq!(seek.seek(SeekFrom::Start(0x08)).unwrap(), 0x08);
+    assert_eq!(seek.seek(SeekFrom::Current(-4)).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    assert_eq!(seek.seek(SeekFrom::Start(0)).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    assert_eq!(seek.seek(SeekFrom::End(0)).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    assert_eq!(seek.seek(SeekFrom::Current(0)).unwrap(), 0x08);
+
+    let mut odata = [0u8; 4];
+    seek.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[0x08..0x0c]);
+}
+
+#[test]
+fn seek_from_absolute() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..0x20).collect();
+    let mut stream = SeekEndFromLength::new(SeekBackwardFromAbsolute::new(ReadWriteTell::new(SeekAll::new(Cursor::new(&data[..])))), 0x20);
+
+    assert_eq!(stream.seek_end(-4).unwrap(), 0x1c);
+    assert_eq!(stream.seek_backward(8).unwrap(), 8);
+    assert_eq!(stream.tell().unwrap(), 0x14);
+    assert_eq!(stream.seek_backward(0x40).unwrap(), 0x14);
+}
+
+#[test]
+fn seek_forward_buf_read() {
+    use std::io::{Cursor, BufReader};
+
+    let data: Vec<u8> = (0..0x40).collect();
+    let mut stream = SeekForwardBufRead::new(BufReader::with_capacity(0x10, Cursor::new(&data[..])));
+
+    assert_eq!(stream.seek_forward(0x18).unwrap(), 0x18);
+    let mut odata = [0u8; 4];
+    stream.read_exact(&mut odata).unwrap();
+    assert_eq!(&odata, &data[0x18..0x1c]);
+    assert_eq!(stream.seek_forward(0x40).unwrap(), 0x24);
+}
+
+#[test]
+fn seek_forward_write() {
+    let mut stream = SeekForwardWrite::new(vec![0xffu8]);
+
+    assert_eq!(stream.seek_forward(0x12345).unwrap(), 0x12345);
+    let data = stream.inner;
+    assert_eq!(data.len(), 0x12346);
+    assert!(data[1..].iter().all(|&b| b == 0));
+}
@@ -0,0 +1,226 @@
+use std::io::{self, Read, Write, BufRead, IoSlice};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// An in-memory stream over a sequence of separately allocated chunks.
+///
+/// Data assembled from several buffers can be read as one stream without
+/// first being copied into a contiguous allocation. Writes overwrite existing
+/// chunks in place, and data written past the end is appended to the last chunk.
+#[derive(Clone, Debug, Default)]
+pub struct SegmentedBuf {
+    chunks: Vec<Vec<u8>>,
+    ends: Vec<u64>,
+    pos: u64,
+}
+
+impl SegmentedBuf {
+    /// Creates a new, empty `SegmentedBuf`.
+    pub fn new() -> Self {
+        SegmentedBuf::default()
+    }
+
+    /// Appends a chunk to the end of the buffer.
+    pub fn push(&mut self, chunk: Vec<u8>) {
+        let end = self.len() + chunk.len() as u64;
+        self.chunks.push(chunk);
+        self.ends.push(end);
+    }
+
+    /// Returns the total length of all chunks.
+    pub fn len(&self) -> u64 {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    /// Returns whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the chunks making up the buffer.
+    pub fn chunks(&self) -> &[Vec<u8>] {
+        &self.chunks
+    }
+
+    /// Borrows the chunks for a vectored write.
+    pub fn io_slices(&self) -> Vec<IoSlice> {
+        self.chunks.iter().map(|chunk| IoSlice::new(chunk)).collect()
+    }
+
+    /// Unwraps the `SegmentedBuf` to return its chunks.
+    pub fn into_chunks(self) -> Vec<Vec<u8>> {
+        self.chunks
+    }
+
+    /// Concatenates the chunks into a single buffer.
+    pub fn into_vec(self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(self.len() as usize);
+        for chunk in &self.chunks {
+            vec.extend_from_slice(chunk);
+        }
+        vec
+    }
+
+    /// Returns the index of the chunk containing `pos`, and the offset into it.
+    fn locate(&self, pos: u64) -> Option<(usize, usize)> {
+        let index = self.ends.partition_point(|&end| end <= pos);
+        if index < self.chunks.len() {
+            let start = if index == 0 { 0 } else { self.ends[index - 1] };
+            Some((index, (pos - start) as usize))
+        } else {
+            None
+        }
+    }
+
+    fn append(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let gap = self.pos - self.len();
+        if gap > usize::max_value() as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "position exceeds addressable memory"))
+        }
+
+        if self.chunks.is_empty() {
+            self.push(Vec::new());
+        }
+
+        let last = self.chunks.len() - 1;
+        let chunk = &mut self.chunks[last];
+        let new_len = chunk.len() + gap as usize;
+        chunk.resize(new_len, 0);
+        chunk.extend_from_slice(buf);
+        self.ends[last] += gap + buf.len() as u64;
+        self.pos += buf.len() as u64;
+        Ok(buf.len())
+    }
+}
+
+impl From<Vec<Vec<u8>>> for SegmentedBuf {
+    fn from(chunks: Vec<Vec<u8>>) -> Self {
+        let mut buf = SegmentedBuf::new();
+        for chunk in chunks {
+            buf.push(chunk);
+        }
+        buf
+    }
+}
+
+impl<'a> From<Vec<IoSlice<'a>>> for SegmentedBuf {
+    fn from(slices: Vec<IoSlice<'a>>) -> Self {
+        slices.iter().map(|slice| slice.to_vec()).collect::<Vec<_>>().into()
+    }
+}
+
+impl Read for SegmentedBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = {
+            let data = try!(self.fill_buf());
+            let len = min(data.len(), buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        };
+
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for SegmentedBuf {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(match self.locate(self.pos) {
+            Some((index, offset)) => &self.chunks[index][offset..],
+            None => &[],
+        })
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt as u64;
+    }
+}
+
+impl Write for SegmentedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.locate(self.pos) {
+            Some((index, offset)) => {
+                let chunk = &mut self.chunks[index][offset..];
+                let len = min(chunk.len(), buf.len());
+                chunk[..len].copy_from_slice(&buf[..len]);
+                self.pos += len as u64;
+                Ok(len)
+            },
+            None => self.append(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SeekAbsolute for SegmentedBuf {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+impl SeekForward for SegmentedBuf {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.pos = self.pos.saturating_add(offset);
+        Ok(offset)
+    }
+}
+
+impl SeekBackward for SegmentedBuf {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, self.pos);
+        self.pos -= offset;
+        Ok(offset)
+    }
+}
+
+impl SeekRewind for SegmentedBuf {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl SeekEnd for SegmentedBuf {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let len = self.len();
+        self.pos = if offset < 0 {
+            match len.checked_sub(offset.wrapping_neg() as u64) {
+                Some(pos) => pos,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")),
+            }
+        } else {
+            len.saturating_add(offset as u64)
+        };
+
+        Ok(self.pos)
+    }
+}
+
+impl Tell for SegmentedBuf {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn segmented_buf() {
+    let mut buf = SegmentedBuf::from(vec![b"hel".to_vec(), Vec::new(), b"lo w".to_vec()]);
+    buf.push(b"orld".to_vec());
+    assert_eq!(buf.len(), 11);
+
+    let mut odata = String::new();
+    buf.read_to_string(&mut odata).unwrap();
+    assert_eq!(odata, "hello world");
+
+    buf.seek_absolute(2).unwrap();
+    buf.write_all(b"LLO").unwrap();
+    buf.seek_end(1).unwrap();
+    buf.write_all(b"!").unwrap();
+    assert_eq!(buf.chunks().len(), 4);
+    assert_eq!(buf.io_slices().iter().map(|s| s.len()).sum::<usize>(), 13);
+    assert_eq!(buf.into_vec(), b"heLLO world\0!");
+}
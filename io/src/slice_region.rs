@@ -0,0 +1,193 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// An isolated segment of an in-memory buffer.
+///
+/// Behaves like a `Region` over a `Cursor`, but tracks its own position so
+/// reads don't go through `Tell` and `SeekAbsolute` on every call.
+/// Reading is supported for any `AsRef<[u8]>` buffer such as `&[u8]`,
+/// and writing for `AsMut<[u8]>` buffers such as `&mut [u8]`.
+///
+/// Seeks past the region will be capped, and reaching the end of
+/// the region will result in EOF when reading or writing.
+#[derive(Clone, Debug)]
+pub struct SliceRegion<T> {
+    inner: T,
+    start: usize,
+    end: usize,
+    pos: usize,
+}
+
+impl<T: AsRef<[u8]>> SliceRegion<T> {
+    /// Creates a new `SliceRegion` at the specified offsets of `inner`.
+    ///
+    /// The bounds are clamped to the length of the buffer.
+    pub fn new(inner: T, start: usize, end: usize) -> Self {
+        let end = min(end, inner.as_ref().len());
+        SliceRegion {
+            inner: inner,
+            start: min(start, end),
+            end: end,
+            pos: 0,
+        }
+    }
+
+    /// Borrows the unread remainder of the region, without copying.
+    pub fn remaining(&self) -> &[u8] {
+        &self.inner.as_ref()[self.start + self.pos..self.end]
+    }
+
+    /// Creates a nested `SliceRegion` that borrows the buffer.
+    ///
+    /// The offsets are relative to the start of this region. Fails with
+    /// `InvalidInput` if the bounds are inverted or do not fit inside this region.
+    pub fn sub_region(&self, start: usize, end: usize) -> io::Result<SliceRegion<&[u8]>> {
+        let len = self.len();
+        if start > end || end > len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("sub region {}..{} does not fit in region of length {}", start, end, len)))
+        }
+
+        Ok(SliceRegion::new(self.inner.as_ref(), self.start + start, self.start + end))
+    }
+}
+
+impl<T> SliceRegion<T> {
+    /// Returns the region bounds.
+    pub fn region(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    /// Returns the length of the region.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns whether the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns a reference to the underlying buffer.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `SliceRegion` to return the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for SliceRegion<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = {
+            let data = self.remaining();
+            let len = min(data.len(), buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        };
+
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl<T: AsRef<[u8]>> BufRead for SliceRegion<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = min(self.pos + amt, self.len());
+    }
+}
+
+impl<T: AsMut<[u8]>> Write for SliceRegion<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = self.start + self.pos;
+        let len = min(self.end - start, buf.len());
+        self.inner.as_mut()[start..start + len].copy_from_slice(&buf[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> SeekAbsolute for SliceRegion<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = min(pos, self.len() as u64) as usize;
+        Ok(self.pos as u64)
+    }
+}
+
+impl<T> SeekForward for SliceRegion<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, (self.len() - self.pos) as u64);
+        self.pos += offset as usize;
+        Ok(offset)
+    }
+}
+
+impl<T> SeekBackward for SliceRegion<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, self.pos as u64);
+        self.pos -= offset as usize;
+        Ok(offset)
+    }
+}
+
+impl<T> SeekRewind for SliceRegion<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T> SeekEnd for SliceRegion<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let len = self.len() as u64;
+        let pos = if offset < 0 {
+            match len.checked_sub(offset.wrapping_neg() as u64) {
+                Some(pos) => pos,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")),
+            }
+        } else {
+            len
+        };
+
+        self.seek_absolute(pos)
+    }
+}
+
+impl<T> Tell for SliceRegion<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos as u64)
+    }
+}
+
+#[test]
+fn slice_region() {
+    let mut data: Vec<u8> = (0..0x20).collect();
+
+    {
+        let mut region = SliceRegion::new(&mut data[..], 0x10, 0x18);
+        region.seek_end(-2).unwrap();
+        assert_eq!(region.write(&[0xff; 4]).unwrap(), 2);
+    }
+
+    let mut region = SliceRegion::new(&data[..], 0x10, 0x18);
+    assert_eq!(region.seek_forward(0x10).unwrap(), 8);
+    region.seek_absolute(4).unwrap();
+    let mut odata = Vec::new();
+    region.read_to_end(&mut odata).unwrap();
+    assert_eq!(odata, [0x14, 0x15, 0xff, 0xff]);
+
+    let mut sub = region.sub_region(2, 4).unwrap();
+    assert!(region.sub_region(4, 10).is_err());
+    assert_eq!(sub.fill_buf().unwrap(), &[0x12, 0x13]);
+}
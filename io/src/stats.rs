@@ -0,0 +1,182 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::{min, max};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// Counters collected by `Stats`.
+///
+/// Operation sizes are the buffer lengths requested by the caller, while
+/// byte counts are the amounts actually transferred.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// The number of `read` calls.
+    pub reads: u64,
+    /// The number of `write` calls.
+    pub writes: u64,
+    /// The number of seek operations, including `tell`.
+    pub seeks: u64,
+    /// The number of `flush` calls.
+    pub flushes: u64,
+    /// The total number of bytes read or consumed.
+    pub bytes_read: u64,
+    /// The total number of bytes written.
+    pub bytes_written: u64,
+    /// The smallest `read` requested, or zero if there have been none.
+    pub smallest_read: usize,
+    /// The largest `read` requested.
+    pub largest_read: usize,
+    /// The smallest `write` requested, or zero if there have been none.
+    pub smallest_write: usize,
+    /// The largest `write` requested.
+    pub largest_write: usize,
+}
+
+/// Records statistics about the operations performed on a stream.
+///
+/// Useful for diagnosing codecs that issue many small reads or redundant seeks.
+pub struct Stats<T> {
+    inner: T,
+    stats: IoStats,
+}
+
+impl<T> Stats<T> {
+    /// Creates a new `Stats` with all counters at zero.
+    pub fn new(inner: T) -> Self {
+        Stats {
+            inner: inner,
+            stats: IoStats::default(),
+        }
+    }
+
+    /// Returns the statistics collected so far.
+    pub fn stats(&self) -> &IoStats {
+        &self.stats
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset_stats(&mut self) {
+        self.stats = IoStats::default();
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    ///
+    /// Operations through this reference will not be recorded.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the `Stats` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+fn record(count: &mut u64, smallest: &mut usize, largest: &mut usize, len: usize) {
+    *smallest = if *count == 0 { len } else { min(*smallest, len) };
+    *largest = max(*largest, len);
+    *count += 1;
+}
+
+impl<T: Read> Read for Stats<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        record(&mut self.stats.reads, &mut self.stats.smallest_read, &mut self.stats.largest_read, buf.len());
+        let read = try!(self.inner.read(buf));
+        self.stats.bytes_read += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T: BufRead> BufRead for Stats<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.stats.bytes_read += amt as u64;
+    }
+}
+
+impl<T: Write> Write for Stats<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        record(&mut self.stats.writes, &mut self.stats.smallest_write, &mut self.stats.largest_write, buf.len());
+        let written = try!(self.inner.write(buf));
+        self.stats.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stats.flushes += 1;
+        self.inner.flush()
+    }
+}
+
+impl<T: SeekForward> SeekForward for Stats<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        self.stats.seeks += 1;
+        self.inner.seek_forward(offset)
+    }
+}
+
+impl<T: SeekBackward> SeekBackward for Stats<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        self.stats.seeks += 1;
+        self.inner.seek_backward(offset)
+    }
+}
+
+impl<T: SeekRewind> SeekRewind for Stats<T> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.stats.seeks += 1;
+        self.inner.seek_rewind()
+    }
+}
+
+impl<T: SeekAbsolute> SeekAbsolute for Stats<T> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.stats.seeks += 1;
+        self.inner.seek_absolute(pos)
+    }
+}
+
+impl<T: SeekEnd> SeekEnd for Stats<T> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.stats.seeks += 1;
+        self.inner.seek_end(offset)
+    }
+}
+
+impl<T: Tell> Tell for Stats<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.stats.seeks += 1;
+        self.inner.tell()
+    }
+}
+
+#[test]
+fn stats() {
+    use cursor::Cursor;
+
+    let mut r = Stats::new(Cursor::new(vec![0u8; 0x10]));
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf[..1]).unwrap();
+    r.read_exact(&mut buf).unwrap();
+    r.seek_forward(4).unwrap();
+    r.tell().unwrap();
+    let mut rest = Vec::new();
+    r.read_to_end(&mut rest).unwrap();
+
+    let stats = *r.stats();
+    assert_eq!(stats.bytes_read, 12);
+    assert_eq!(stats.seeks, 2);
+    assert_eq!(stats.smallest_read, 1);
+    assert!(stats.largest_read >= 4);
+    assert_eq!(stats.writes, 0);
+
+    r.reset_stats();
+    assert_eq!(*r.stats(), IoStats::default());
+}
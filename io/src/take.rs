@@ -1,14 +1,19 @@
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Write, BufRead};
 use std::cmp::min;
-use seek_forward::{SeekForward, Tell};
+use seek_forward::{SeekForward, SeekBackward, Tell};
 
 /// Wraps around a stream to limit the length of the underlying stream.
 ///
 /// This implementation differs from `std::io::Take` in that it also allows writes,
 /// and seeking forward is allowed if the underlying stream supports it.
+///
+/// Seeking backward is bounded by the start of the limited scope, and
+/// returns the reversed bytes to the limit.
 pub struct Take<T> {
     inner: T,
     limit: u64,
+    pos: u64,
+    relative: bool,
 }
 
 impl<T> Take<T> {
@@ -17,8 +22,59 @@ impl<T> Take<T> {
         Take {
             inner: inner,
             limit: limit,
+            pos: 0,
+            relative: false,
+        }
+    }
+
+    /// Creates a new `Take` with `limit` bytes, whose `Tell` reports the
+    /// position relative to the start of the limited scope.
+    pub fn relative(inner: T, limit: u64) -> Self {
+        Take {
+            relative: true,
+            .. Take::new(inner, limit)
         }
     }
+
+    /// Returns the number of bytes transferred since the start of the limited scope.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Returns the number of bytes remaining before the limit is reached.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Replaces the remaining limit with `limit` bytes, starting a new limited scope.
+    ///
+    /// Useful for reusing a `Take` across frames of a protocol.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+        self.pos = 0;
+    }
+
+    fn advance(&mut self, amt: u64) {
+        self.limit -= amt;
+        self.pos += amt;
+    }
+
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    ///
+    /// Transferring data through this reference does not affect the limit.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the `Take` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
 }
 
 impl<T: Write> Write for Take<T> {
@@ -31,7 +87,7 @@ impl<T: Write> Write for Take<T> {
 
         let buf = &buf[..limit as usize];
         let inner = try!(self.inner.write(buf));
-        self.limit -= inner as u64;
+        self.advance(inner as u64);
         Ok(inner)
     }
 
@@ -50,21 +106,106 @@ impl<T: Read> Read for Take<T> {
 
         let buf = &mut buf[..limit as usize];
         let inner = try!(self.inner.read(buf));
-        self.limit -= inner as u64;
+        self.advance(inner as u64);
         Ok(inner)
     }
 }
 
+impl<T: BufRead> BufRead for Take<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.limit == 0 {
+            return Ok(&[])
+        }
+
+        let buf = try!(self.inner.fill_buf());
+        let len = min(self.limit, buf.len() as u64) as usize;
+        Ok(&buf[..len])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = min(self.limit, amt as u64);
+        self.advance(amt);
+        self.inner.consume(amt as usize);
+    }
+}
+
 impl<T: SeekForward> SeekForward for Take<T> {
     fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
         let res = try!(self.inner.seek_forward(min(offset, self.limit)));
-        self.limit -= res;
+        self.advance(res);
+        Ok(res)
+    }
+}
+
+impl<T: SeekBackward> SeekBackward for Take<T> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let res = try!(self.inner.seek_backward(min(offset, self.pos)));
+        self.limit += res;
+        self.pos -= res;
         Ok(res)
     }
 }
 
 impl<T: Tell> Tell for Take<T> {
+    /// Returns the position of the inner stream, or the position within
+    /// the limited scope if created with `Take::relative`.
     fn tell(&mut self) -> io::Result<u64> {
-        self.inner.tell()
+        if self.relative {
+            Ok(self.pos)
+        } else {
+            self.inner.tell()
+        }
     }
 }
+
+#[test]
+fn take_limit() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..0x10).collect();
+    let mut take = Take::new(Cursor::new(&data[..]), 4);
+
+    let mut odata = Vec::new();
+    take.read_to_end(&mut odata).unwrap();
+    assert_eq!(&odata[..], &data[..4]);
+    assert_eq!(take.limit(), 0);
+
+    take.set_limit(4);
+    odata.clear();
+    take.read_to_end(&mut odata).unwrap();
+    assert_eq!(&odata[..], &data[4..8]);
+    assert_eq!(take.into_inner().position(), 8);
+}
+
+#[test]
+fn take_buf_read() {
+    use std::io::Cursor;
+
+    let data = b"abc\ndef\n";
+    let mut take = Take::new(Cursor::new(&data[..]), 6);
+
+    let mut line = String::new();
+    take.read_line(&mut line).unwrap();
+    assert_eq!(line, "abc\n");
+    line.clear();
+    take.read_line(&mut line).unwrap();
+    assert_eq!(line, "de");
+    assert_eq!(take.limit(), 0);
+}
+
+#[test]
+fn take_relative() {
+    use std::io::Cursor;
+    use seek_forward::SeekAll;
+
+    let data: Vec<u8> = (0..0x10).collect();
+    let mut cursor = SeekAll::new(Cursor::new(&data[..]));
+    cursor.seek_forward(4).unwrap();
+    let mut take = Take::relative(cursor, 8);
+
+    let mut odata = [0u8; 6];
+    take.read_exact(&mut odata).unwrap();
+    assert_eq!(take.tell().unwrap(), 6);
+    assert_eq!(take.seek_backward(8).unwrap(), 6);
+    assert_eq!(take.limit(), 8);
+}
@@ -0,0 +1,179 @@
+use std::io::{self, Read, Write};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// A reader that copies all data read into a secondary writer.
+///
+/// Seeking moves both streams together, and requires both to support it.
+pub struct TeeRead<R, W> {
+    inner: R,
+    tee: W,
+}
+
+impl<R, W> TeeRead<R, W> {
+    /// Creates a new `TeeRead` that copies from `inner` into `tee`.
+    pub fn new(inner: R, tee: W) -> Self {
+        TeeRead {
+            inner: inner,
+            tee: tee,
+        }
+    }
+
+    /// Returns a reference to the secondary writer.
+    pub fn tee(&self) -> &W {
+        &self.tee
+    }
+
+    /// Unwraps the `TeeRead` to return the reader and writer.
+    pub fn into_parts(self) -> (R, W) {
+        (self.inner, self.tee)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeRead<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read(buf));
+        try!(self.tee.write_all(&buf[..read]));
+        Ok(read)
+    }
+}
+
+impl<R: SeekForward, W: SeekForward> SeekForward for TeeRead<R, W> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let skipped = try!(self.inner.seek_forward(offset));
+        try!(self.tee.seek_forward(skipped));
+        Ok(skipped)
+    }
+}
+
+impl<R: SeekBackward, W: SeekBackward> SeekBackward for TeeRead<R, W> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let reversed = try!(self.inner.seek_backward(offset));
+        try!(self.tee.seek_backward(reversed));
+        Ok(reversed)
+    }
+}
+
+impl<R: SeekRewind, W: SeekRewind> SeekRewind for TeeRead<R, W> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        try!(self.inner.seek_rewind());
+        self.tee.seek_rewind()
+    }
+}
+
+impl<R: SeekAbsolute, W: SeekAbsolute> SeekAbsolute for TeeRead<R, W> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        let pos = try!(self.inner.seek_absolute(pos));
+        try!(self.tee.seek_absolute(pos));
+        Ok(pos)
+    }
+}
+
+impl<R: Tell, W> Tell for TeeRead<R, W> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.inner.tell()
+    }
+}
+
+/// A writer that duplicates all data written into two streams.
+///
+/// Seeking moves both streams together, and requires both to support it.
+/// Positions are reported from the first stream.
+pub struct BroadcastWrite<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> BroadcastWrite<A, B> {
+    /// Creates a new `BroadcastWrite` that writes to both `first` and `second`.
+    pub fn new(first: A, second: B) -> Self {
+        BroadcastWrite {
+            first: first,
+            second: second,
+        }
+    }
+
+    /// Returns references to both streams.
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Unwraps the `BroadcastWrite` to return both streams.
+    pub fn into_parts(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Write, B: Write> Write for BroadcastWrite<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = try!(self.first.write(buf));
+        try!(self.second.write_all(&buf[..written]));
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        try!(self.first.flush());
+        self.second.flush()
+    }
+}
+
+impl<A: SeekForward, B: SeekForward> SeekForward for BroadcastWrite<A, B> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let skipped = try!(self.first.seek_forward(offset));
+        try!(self.second.seek_forward(skipped));
+        Ok(skipped)
+    }
+}
+
+impl<A: SeekBackward, B: SeekBackward> SeekBackward for BroadcastWrite<A, B> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let reversed = try!(self.first.seek_backward(offset));
+        try!(self.second.seek_backward(reversed));
+        Ok(reversed)
+    }
+}
+
+impl<A: SeekRewind, B: SeekRewind> SeekRewind for BroadcastWrite<A, B> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        try!(self.first.seek_rewind());
+        self.second.seek_rewind()
+    }
+}
+
+impl<A: SeekAbsolute, B: SeekAbsolute> SeekAbsolute for BroadcastWrite<A, B> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        let pos = try!(self.first.seek_absolute(pos));
+        try!(self.second.seek_absolute(pos));
+        Ok(pos)
+    }
+}
+
+impl<A: SeekEnd, B: SeekEnd> SeekEnd for BroadcastWrite<A, B> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let pos = try!(self.first.seek_end(offset));
+        try!(self.second.seek_end(offset));
+        Ok(pos)
+    }
+}
+
+impl<A: Tell, B> Tell for BroadcastWrite<A, B> {
+    fn tell(&mut self) -> io::Result<u64> {
+        self.first.tell()
+    }
+}
+
+#[test]
+fn tee() {
+    use hash::{Crc32, HashWrite};
+
+    let data: Vec<u8> = (0..0x20).collect();
+    let mut read = TeeRead::new(&data[..], Vec::new());
+    let mut odata = [0u8; 0x10];
+    read.read_exact(&mut odata).unwrap();
+    assert_eq!(&read.tee()[..], &data[..0x10]);
+
+    let mut write = BroadcastWrite::new(Vec::new(), HashWrite::new(::std::io::sink(), Crc32::new()));
+    write.write_all(&data).unwrap();
+    let (first, second) = write.into_parts();
+    assert_eq!(first, data);
+    assert_eq!(second.digest(), Crc32::checksum(&data));
+}
@@ -0,0 +1,45 @@
+use std::io;
+use seek_forward::Tell;
+
+/// An extension trait for checking the position of a stream.
+pub trait TellExt: Tell {
+    /// Fails with `InvalidData` unless the stream is positioned at `expected`.
+    fn expect_position(&mut self, expected: u64) -> io::Result<()> {
+        let pos = try!(self.tell());
+        if pos == expected {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("expected stream position {:#x}, found {:#x}", expected, pos)))
+        }
+    }
+
+    /// Fails with `InvalidData` unless the stream is positioned at a multiple of `alignment`.
+    fn assert_aligned(&mut self, alignment: u64) -> io::Result<()> {
+        let pos = try!(self.tell());
+        if pos % alignment == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("expected stream position aligned to {:#x}, found {:#x}", alignment, pos)))
+        }
+    }
+}
+
+impl<T: Tell + ?Sized> TellExt for T { }
+
+#[test]
+fn tell_ext() {
+    use cursor::Cursor;
+    use seek_forward::SeekAbsolute;
+
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    cursor.seek_absolute(0x18).unwrap();
+    cursor.expect_position(0x18).unwrap();
+    cursor.assert_aligned(8).unwrap();
+
+    let err = cursor.expect_position(0x10).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "expected stream position 0x10, found 0x18");
+    assert!(cursor.assert_aligned(0x10).is_err());
+}
@@ -0,0 +1,141 @@
+use std::io::{self, Read, Write};
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// Transforms bytes passing through a stream, such as with an XOR mask or stream cipher.
+///
+/// The closure is called with the stream position of the first byte and the
+/// data to be transformed in place. It should depend only on the position,
+/// since data may be revisited after seeking or a partial write. Any state it
+/// keeps must be reset when given position 0 after `seek_rewind`.
+pub struct Transform<T, F> {
+    inner: T,
+    transform: F,
+    pos: u64,
+    buf: Vec<u8>,
+}
+
+impl<T, F: FnMut(u64, &mut [u8])> Transform<T, F> {
+    /// Creates a new `Transform` over a stream positioned at its start.
+    pub fn new(inner: T, transform: F) -> Self {
+        Transform::with_position(inner, 0, transform)
+    }
+
+    /// Creates a new `Transform` over a stream currently positioned at `pos`.
+    pub fn with_position(inner: T, pos: u64, transform: F) -> Self {
+        Transform {
+            inner: inner,
+            transform: transform,
+            pos: pos,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl<T, F> Transform<T, F> {
+    /// Returns a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the `Transform` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read, F: FnMut(u64, &mut [u8])> Read for Transform<T, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read(buf));
+        (self.transform)(self.pos, &mut buf[..read]);
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T: Write, F: FnMut(u64, &mut [u8])> Write for Transform<T, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.clear();
+        self.buf.extend_from_slice(buf);
+        (self.transform)(self.pos, &mut self.buf);
+
+        let written = try!(self.inner.write(&self.buf));
+        self.pos += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: SeekForward, F> SeekForward for Transform<T, F> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let skipped = try!(self.inner.seek_forward(offset));
+        self.pos += skipped;
+        Ok(skipped)
+    }
+}
+
+impl<T: SeekBackward, F> SeekBackward for Transform<T, F> {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let reversed = try!(self.inner.seek_backward(offset));
+        self.pos -= reversed;
+        Ok(reversed)
+    }
+}
+
+impl<T: SeekRewind, F> SeekRewind for Transform<T, F> {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        try!(self.inner.seek_rewind());
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<T: SeekAbsolute, F> SeekAbsolute for Transform<T, F> {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.pos = try!(self.inner.seek_absolute(pos));
+        Ok(self.pos)
+    }
+}
+
+impl<T: SeekEnd, F> SeekEnd for Transform<T, F> {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.pos = try!(self.inner.seek_end(offset));
+        Ok(self.pos)
+    }
+}
+
+impl<T, F> Tell for Transform<T, F> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn transform_xor() {
+    use cursor::Cursor;
+
+    let key = b"key";
+    let xor = |pos: u64, buf: &mut [u8]| {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b ^= key[(pos as usize + i) % key.len()];
+        }
+    };
+
+    let mut w = Transform::new(Cursor::new(Vec::new()), xor);
+    w.write_all(b"hello world").unwrap();
+    let data = w.into_inner().into_inner();
+    assert!(&data[..] != b"hello world");
+
+    let mut r = Transform::new(Cursor::new(data), xor);
+    r.seek_absolute(6).unwrap();
+    let mut odata = String::new();
+    r.read_to_string(&mut odata).unwrap();
+    assert_eq!(odata, "world");
+
+    r.seek_rewind().unwrap();
+    odata.clear();
+    r.read_to_string(&mut odata).unwrap();
+    assert_eq!(odata, "hello world");
+}
@@ -0,0 +1,143 @@
+use std::io::{self, Write};
+use std::cmp::min;
+use seek_forward::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+
+/// An in-memory writer that can write anywhere in a growable buffer.
+///
+/// Seeking past the end extends the buffer with zeroes immediately, so space
+/// reserved for a header that is filled in later is always part of the output.
+#[derive(Clone, Debug, Default)]
+pub struct VecWriter {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl VecWriter {
+    /// Creates a new, empty `VecWriter`.
+    pub fn new() -> Self {
+        VecWriter::from_vec(Vec::new())
+    }
+
+    /// Creates a new, empty `VecWriter` with at least `capacity` bytes allocated.
+    pub fn with_capacity(capacity: usize) -> Self {
+        VecWriter::from_vec(Vec::with_capacity(capacity))
+    }
+
+    /// Creates a `VecWriter` over existing data, positioned at its start.
+    pub fn from_vec(buf: Vec<u8>) -> Self {
+        VecWriter {
+            buf: buf,
+            pos: 0,
+        }
+    }
+
+    /// Returns the current length of the buffer.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Returns a reference to the buffer.
+    pub fn get_ref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+
+    /// Unwraps the `VecWriter` to return the buffer.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn set_position(&mut self, pos: u64) -> io::Result<u64> {
+        if pos > usize::max_value() as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "position exceeds addressable memory"))
+        }
+
+        self.pos = pos as usize;
+        if self.buf.len() < self.pos {
+            self.buf.resize(self.pos, 0);
+        }
+
+        Ok(pos)
+    }
+}
+
+impl Write for VecWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let overlap = min(self.buf.len() - self.pos, buf.len());
+        self.buf[self.pos..self.pos + overlap].copy_from_slice(&buf[..overlap]);
+        self.buf.extend_from_slice(&buf[overlap..]);
+        self.pos += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SeekAbsolute for VecWriter {
+    fn seek_absolute(&mut self, pos: u64) -> io::Result<u64> {
+        self.set_position(pos)
+    }
+}
+
+impl SeekForward for VecWriter {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let pos = self.pos as u64;
+        self.set_position(pos.saturating_add(offset)).map(|_| offset)
+    }
+}
+
+impl SeekBackward for VecWriter {
+    fn seek_backward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = min(offset, self.pos as u64);
+        self.pos -= offset as usize;
+        Ok(offset)
+    }
+}
+
+impl SeekRewind for VecWriter {
+    fn seek_rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl SeekEnd for VecWriter {
+    fn seek_end(&mut self, offset: i64) -> io::Result<u64> {
+        let len = self.buf.len() as u64;
+        let pos = if offset < 0 {
+            match len.checked_sub(offset.wrapping_neg() as u64) {
+                Some(pos) => pos,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")),
+            }
+        } else {
+            len.saturating_add(offset as u64)
+        };
+
+        self.set_position(pos)
+    }
+}
+
+impl Tell for VecWriter {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(self.pos as u64)
+    }
+}
+
+#[test]
+fn vec_writer() {
+    let mut w = VecWriter::new();
+    w.seek_forward(4).unwrap();
+    assert_eq!(w.len(), 4);
+    w.write_all(b"body").unwrap();
+    w.seek_rewind().unwrap();
+    w.write_all(&[8, 0]).unwrap();
+    w.seek_end(2).unwrap();
+    assert_eq!(w.tell().unwrap(), 10);
+    assert_eq!(w.into_inner(), b"\x08\0\0\0body\0\0");
+}
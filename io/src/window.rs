@@ -0,0 +1,140 @@
+use std::io::{self, Read, Write, BufRead};
+use std::cmp::min;
+use seek_forward::{SeekForward, Tell};
+use error::Error;
+
+/// Creates an isolated segment of an underlying stream, without requiring
+/// the stream to support `Tell` or `SeekAbsolute`.
+///
+/// Unlike `Region`, `Window` tracks its own position relative to where the
+/// inner stream was when the `Window` was created. The first access skips
+/// forward to `start`, and reaching `end` results in EOF when reading or writing.
+///
+/// Wrap plain streams in `SeekForwardRead` or `SeekForwardWrite` to provide
+/// the required `SeekForward`.
+pub struct Window<T> {
+    inner: T,
+    start: u64,
+    end: u64,
+    pos: u64,
+}
+
+impl<T> Window<T> {
+    /// Creates a new `Window` at the specified offsets from the current position of `inner`.
+    pub fn new(inner: T, start: u64, end: u64) -> Self {
+        Window {
+            inner: inner,
+            start: start,
+            end: end,
+            pos: 0,
+        }
+    }
+
+    /// Returns the window bounds.
+    pub fn region(&self) -> (u64, u64) {
+        (self.start, self.end)
+    }
+
+    /// Unwraps the `Window` to return the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: SeekForward> Window<T> {
+    fn position(&mut self) -> io::Result<u64> {
+        while self.pos < self.start {
+            let offset = self.start - self.pos;
+            match try!(self.inner.seek_forward(offset)) {
+                0 => return Err(Error::UnexpectedEof { needed: Some(offset) }.into()),
+                skipped => self.pos += skipped,
+            }
+        }
+
+        Ok(self.pos)
+    }
+
+    fn limit(&mut self, len: u64) -> io::Result<u64> {
+        let pos = try!(self.position());
+
+        Ok(min(self.end.saturating_sub(pos), len))
+    }
+}
+
+impl<T: Read + SeekForward> Read for Window<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = try!(self.limit(buf.len() as u64)) as usize;
+
+        if len == 0 {
+            Ok(0)
+        } else {
+            let read = try!(self.inner.read(&mut buf[..len]));
+            self.pos += read as u64;
+            Ok(read)
+        }
+    }
+}
+
+impl<T: Write + SeekForward> Write for Window<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = try!(self.limit(buf.len() as u64)) as usize;
+
+        if len == 0 {
+            Ok(0)
+        } else {
+            let written = try!(self.inner.write(&buf[..len]));
+            self.pos += written as u64;
+            Ok(written)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: BufRead + SeekForward> BufRead for Window<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let pos = try!(self.position());
+        let end = self.end;
+
+        let buf = try!(self.inner.fill_buf());
+        let len = min(end.saturating_sub(pos), buf.len() as u64) as usize;
+
+        Ok(&buf[..len])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.pos += amt as u64;
+    }
+}
+
+impl<T: SeekForward> SeekForward for Window<T> {
+    fn seek_forward(&mut self, offset: u64) -> io::Result<u64> {
+        let offset = try!(self.limit(offset));
+        let skipped = try!(self.inner.seek_forward(offset));
+        self.pos += skipped;
+        Ok(skipped)
+    }
+}
+
+impl<T> Tell for Window<T> {
+    fn tell(&mut self) -> io::Result<u64> {
+        Ok(min(self.end, self.pos).saturating_sub(self.start))
+    }
+}
+
+#[test]
+fn window() {
+    use std::io::Cursor;
+    use seek_forward::SeekForwardRead;
+
+    let data: Vec<u8> = (0..0x20).collect();
+    let mut window = Window::new(SeekForwardRead::new(Cursor::new(&data[..])), 0x08, 0x10);
+
+    let mut odata = Vec::new();
+    window.read_to_end(&mut odata).unwrap();
+    assert_eq!(&odata[..], &data[0x08..0x10]);
+    assert_eq!(window.tell().unwrap(), 0x08);
+}
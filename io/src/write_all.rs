@@ -0,0 +1,71 @@
+use std::io::{self, Write, IoSlice};
+
+/// Extension trait that provides vectored and retrying writes for all `Write` implementations.
+pub trait WriteAllExt {
+    /// Writes the entirety of every buffer in `bufs` in sequence, or fails with an error.
+    ///
+    /// The buffers are handed to `Write::write_vectored` together, so streams that
+    /// support vectored I/O may write them with a single call.
+    /// Retries upon `Interrupted` errors.
+    fn write_all_slices(&mut self, bufs: &[&[u8]]) -> io::Result<()>;
+
+    /// Writes a portion of `buf`, returning the number of bytes written.
+    ///
+    /// Retries upon `Interrupted` errors.
+    fn write_retry(&mut self, buf: &[u8]) -> io::Result<usize>;
+}
+
+impl<W: Write> WriteAllExt for W {
+    fn write_all_slices(&mut self, bufs: &[&[u8]]) -> io::Result<()> {
+        let mut slices: Vec<_> = bufs.iter().map(|buf| IoSlice::new(buf)).collect();
+        let mut slices = &mut slices[..];
+        IoSlice::advance_slices(&mut slices, 0);
+
+        while !slices.is_empty() {
+            match self.write_vectored(slices) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")),
+                Ok(len) => IoSlice::advance_slices(&mut slices, len),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn write_retry(&mut self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            match self.write(buf) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                res => return res,
+            }
+        }
+    }
+}
+
+#[test]
+fn write_all_slices() {
+    struct Stutter(Vec<u8>, bool);
+
+    impl Write for Stutter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.1 = !self.1;
+            if self.1 {
+                Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+            } else {
+                self.0.extend(buf.iter().take(3));
+                Ok(buf.len().min(3))
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut w = Stutter(Vec::new(), false);
+    w.write_all_slices(&[b"head", b"", b"er", b"payload"]).unwrap();
+    assert_eq!(&w.0[..], b"headerpayload");
+    assert_eq!(w.write_retry(b"xy").unwrap(), 2);
+}
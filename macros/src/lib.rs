@@ -7,7 +7,8 @@
 //! ## `#[packed]`
 //!
 //! Applies `#[repr(Packed)]` while also ensuring that all members safely allow
-//! unaligned access.
+//! unaligned access. Each member must be `Unaligned` and is also asserted to have an
+//! alignment of `1`, which catches fieldless enums with a wider `#[repr(..)]`.
 //!
 //! ```
 //! #![feature(plugin, custom_derive, custom_attribute)]
@@ -26,11 +27,38 @@
 //! # }
 //! ```
 //!
+//! ### `#[packed(align = "N")]`
+//!
+//! Applies `#[repr(C, packed(N))]` instead, implementing `PackedAlign` rather than
+//! `Packed`. Every member must have an alignment of at most `N`. Partially packed
+//! types may contain padding, so they cannot be `Pod`.
+//!
+//! ```
+//! #![feature(plugin, custom_derive, custom_attribute)]
+//! #![plugin(nue_macros)]
+//!
+//! extern crate nue;
+//! use nue::{PackedAlign, Un};
+//!
+//! # fn main() {
+//! #[packed(align = "2")]
+//! struct Data(u16, u8, Un<u32>);
+//!
+//! assert_eq!(Data::ALIGN, 2);
+//! # }
+//! ```
+//!
 //! ## `#[derive(Pod)]`
 //!
 //! Marks a struct as `pod::Pod`. It must only contain other `Pod` members, and
 //! the type must be packed.
 //!
+//! ### Unions
+//!
+//! The syntax extensions can't be applied to unions. Use `nue::unsafe_packed!(union ..)`
+//! and `nue::unsafe_pod_union!` instead, which assert that every member is as large as
+//! the union.
+//!
 //! ### `#[derive(PodPacked)]`
 //!
 //! Marks a struct as `pod::Pod`, and also applies the `#[packed]`
@@ -55,11 +83,346 @@
 //! # }
 //! ```
 //!
+//! ### Enums
+//!
+//! C-like enums with a primitive `#[repr(..)]` may also derive `Pod`, and are `Unaligned`
+//! when represented by a single byte. Not every bit pattern is a valid enum though, so they
+//! are only `pod::CheckedPod`, which is limited to conversions that check for one of the
+//! enum's discriminants. Decoding other bytes fails rather than producing an invalid value.
+//! Such enums can't be members of a `Pod` struct.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate pod;
+//! # extern crate nue;
+//! use nue::Decode;
+//!
+//! # fn main() {
+//! #[derive(Pod, Copy, Clone, PartialEq, Debug)]
+//! #[repr(u8)]
+//! enum Kind {
+//!     Data = 1,
+//!     Ack = 2,
+//! }
+//!
+//! assert_eq!(Kind::decode_slice(&[2]).unwrap(), Kind::Ack);
+//! assert!(Kind::decode_slice(&[3]).is_err());
+//! # }
+//! ```
+//!
 //! ## `#[derive(NueEncode, NueDecode)]`
 //!
-//! Implements `nue::Encode` and `nue::Decode` on the struct.
+//! Implements `nue::Encode` and `nue::Decode` on the struct or enum.
 //! All fields must also implement `Encode` / `Decode` (or be skipped by a `nue` attribute).
 //!
+//! ### Enums
+//!
+//! Enums are coded as an integer discriminant tag followed by the fields of the variant.
+//! The tag is the variant's index, or its discriminant for C-like enums, and its type is taken from `#[nue(tag_type = "..")]`,
+//! then the enum's `#[repr(..)]`, defaulting to `u8`. Decoding an unknown tag fails, unless a variant is marked `other`.
+//! The derived `Options` are a `nue::TagOptions`, which can supply a tag stored elsewhere.
+//!
+//! Attributes on the fields of struct variants may refer to the other fields of the
+//! variant through `self`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode, Be};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(tag_type = "u16")]
+//! enum Record {
+//!     Empty,
+//!     Point(u8, u8),
+//!     Named { id: Be<u32> },
+//! }
+//!
+//! let data = Record::Named { id: Be::new(5) };
+//! let cmp = &[2, 0, 0, 0, 0, 5];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Record::decode_slice(cmp).unwrap(), data);
+//! assert_eq!(Record::decode_slice(&[1, 0, 2, 3]).unwrap(), Record::Point(2, 3));
+//! assert!(Record::decode_slice(&[2, 0]).is_err());
+//! # }
+//! ```
+//!
+//! Variants take their own `nue` attributes:
+//!
+//! - `discriminant = "..."` encodes a different tag for the variant. Variants after it
+//!   without one are numbered from it.
+//! - `align = "N"` pads after the tag so that the fields start on an `N` byte boundary.
+//! - `cond = "..."` codes the fields only if the expression holds, and otherwise decodes
+//!   them as `Default::default()`. It can't refer to the fields themselves.
+//! - `other` makes the variant a catch-all that decodes any unknown tag into its first
+//!   field instead of failing, followed by its other fields. A trailing `Vec<u8>` captures
+//!   the rest of the payload. Encoding writes the first field as the tag.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! enum Message {
+//!     #[nue(discriminant = "0x10")]
+//!     Ping,
+//!     #[nue(align = "4")]
+//!     Data(u32),
+//!     #[nue(cond = "false")]
+//!     Reserved(u8),
+//!     #[nue(other)]
+//!     Unknown(u8, Vec<u8>),
+//! }
+//!
+//! let cmp = &[0x11, 0, 0, 0, 5, 0, 0, 0];
+//! assert_eq!(&Message::Data(5).encode_vec().unwrap(), cmp);
+//! assert_eq!(Message::decode_slice(cmp).unwrap(), Message::Data(5));
+//! assert_eq!(Message::decode_slice(&[0x12]).unwrap(), Message::Reserved(0));
+//! assert_eq!(Message::decode_slice(&[0x20, 1]).unwrap(), Message::Unknown(0x20, vec![1]));
+//! # }
+//! ```
+//!
+//! ### Errors
+//!
+//! A failure while decoding a field is reported as a `nue::Error::Field`, naming the type
+//! and field along with the field's offset from the start of the value. Errors from nested
+//! types are wrapped in turn, and `nue::Error::root` retrieves the original error.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Decode, Be};
+//!
+//! # fn main() {
+//! #[derive(NueDecode)]
+//! struct Header {
+//!     kind: u8,
+//!     len: Be<u32>,
+//! }
+//!
+//! let err = Header::decode_slice(&[1, 0, 0]).err().unwrap();
+//! assert_eq!(err.to_string(), "Header.len at offset 0x1: unexpected end of stream, 2 more bytes needed");
+//! # }
+//! ```
+//!
+//! ### `#[nue(magic = "...")]`
+//!
+//! Writes a constant signature before the fields when encoding, and fails with
+//! `InvalidData` when decoding if it doesn't match. May also be used on individual fields,
+//! where the signature precedes the field.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode, Le};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode)]
+//! #[nue(magic = "b\"RIFF\"")]
+//! struct Header {
+//!     len: Le<u32>,
+//!     #[nue(magic = "b\"WAVE\"")]
+//!     version: u8,
+//! }
+//!
+//! let data = Header { len: Le::new(1), version: 2 };
+//! let cmp = b"RIFF\x01\0\0\0WAVE\x02";
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert!(Header::decode_slice(cmp).is_ok());
+//! assert!(Header::decode_slice(b"RIFX\x01\0\0\0WAVE\x02").is_err());
+//! # }
+//! ```
+//!
+//! ### `#[nue(endian = "...")]`
+//!
+//! Codes plain `u16`, `u32`, `u64`, and other multi-byte primitive fields in the given
+//! byte order, which is one of `"little"`, `"big"`, or `"native"`. This also applies to
+//! the tag of an enum. Fields using `Le` / `Be` wrappers are unaffected.
+//!
+//! The `endian` attribute may also be applied to individual fields, overriding the
+//! byte order of the type for mixed-endian formats.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(endian = "big")]
+//! struct Header {
+//!     kind: u8,
+//!     len: u32,
+//!     #[nue(endian = "little")]
+//!     crc: u16,
+//! }
+//!
+//! let data = Header { kind: 1, len: 0x0203, crc: 0x0405 };
+//! let cmp = &[1, 0, 0, 2, 3, 5, 4];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Header::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! ### `#[nue(byte_order = "...")]`
+//!
+//! Selects the byte order of the type's primitive fields at runtime, from an expression
+//! evaluating to a `nue::Endianness`. This is typically derived from a byte order mark
+//! decoded as an earlier field, which can be referred to through `self`. It takes
+//! precedence over the type's `endian`, and only applies to structs.
+//!
+//! Fields with their own `endian` attribute are unaffected, so primitive fields read
+//! before the byte order is known must have one.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode, Endianness};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(byte_order = "if self.bom == *b\"II\" { Endianness::Little } else { Endianness::Big }")]
+//! struct Tiff {
+//!     bom: [u8; 2],
+//!     version: u16,
+//!     offset: u32,
+//! }
+//!
+//! let data = Tiff { bom: *b"MM", version: 42, offset: 8 };
+//! let cmp = &[b'M', b'M', 0, 42, 0, 0, 0, 8];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Tiff::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! ### `#[nue(option = "name: Type")]`
+//!
+//! Generates an options struct for a struct, with a public field for each `option`.
+//! `NueEncode` names it after the type with an `EncodeOptions` suffix, and `NueDecode` with
+//! `DecodeOptions`. The options passed to `encode_options` or `decode_options` are available
+//! to field attributes as `options`, and the defaults come from `Default`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::Decode;
+//!
+//! # fn main() {
+//! #[derive(NueDecode)]
+//! #[nue(option = "version: u8", option = "len: usize")]
+//! struct Record {
+//!     #[nue(count = "options.len")]
+//!     name: String,
+//!     #[nue(cond = "options.version > 1")]
+//!     flags: u8,
+//! }
+//!
+//! let record = Record::decode_slice_options(b"nue\x01", RecordDecodeOptions { version: 2, len: 3 }).unwrap();
+//! assert_eq!(record.name, "nue");
+//! assert_eq!(record.flags, 1);
+//! # }
+//! ```
+//!
+//! ### `#[nue(bound = "...")]`
+//!
+//! By default, every type parameter used by a field must be `Pod`, except those that only
+//! appear in `PhantomData` fields or fields marked `skip_bound`. `bound` replaces those
+//! bounds with the given where clause, which may be empty. `nue_enc` and `nue_dec` can provide separate
+//! bounds for each derive.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue_enc(bound = "T: Encode")]
+//! #[nue_dec(bound = "T: Decode")]
+//! struct Named<T> {
+//!     #[nue(count = "1")]
+//!     name: String,
+//!     value: T,
+//! }
+//!
+//! let data = Named { name: "a".into(), value: String::from("bc") };
+//! let cmp = b"abc";
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Named::<String>::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! ### `#[nue(pad_to = "...")]`
+//!
+//! Pads the encoding with zeros up to a fixed size, counted from the start of the value,
+//! and skips over the padding when decoding. A value that is already larger fails with
+//! `nue::Error::Limit`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(pad_to = "4")]
+//! struct Record {
+//!     kind: u8,
+//!     len: u8,
+//! }
+//!
+//! let data = Record { kind: 1, len: 2 };
+//! let cmp = &[1, 2, 0, 0];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Record::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! ### `#[nue(before_encode = "...", after_decode = "...")]`
+//!
+//! Calls a function around the derived coding. `before_encode` is called with `&self`
+//! before anything is written, and `after_decode` with `&mut` the decoded value before
+//! it is validated. Both return an `io::Result<()>`, and an error aborts the coding.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use std::io;
+//! use nue::{Encode, Decode, Error};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! #[nue(before_encode = "Version::check", after_decode = "Version::upgrade")]
+//! struct Version {
+//!     major: u8,
+//!     minor: u8,
+//! }
+//!
+//! impl Version {
+//!     fn check(&self) -> io::Result<()> {
+//!         if self.major == 0 {
+//!             Err(Error::Validation { field: Some("major"), message: "unversioned".into() }.into())
+//!         } else {
+//!             Ok(())
+//!         }
+//!     }
+//!
+//!     fn upgrade(&mut self) -> io::Result<()> {
+//!         if self.major == 0 {
+//!             self.major = 1;
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! assert!(Version { major: 0, minor: 1 }.encode_vec().is_err());
+//! assert_eq!(Version::decode_slice(&[0, 1]).unwrap(), Version { major: 1, minor: 1 });
+//! # }
+//! ```
+//!
 //! ### `#[nue(...)]`, `#[nue_enc(...)]`, `#[nue_dec(...)]`
 //!
 //! Additional coding options may be provided per field using the `nue` attributes.
@@ -140,6 +503,88 @@
 //! # }
 //! ```
 //!
+//! #### `restore_position`
+//!
+//! Seeks back to where the field started once it has been decoded, so that the following
+//! fields are read from there. Combined with `skip`, this reads data found elsewhere in the
+//! stream, such as through an offset in a table. It doesn't affect encoding.
+//!
+//! The stream is buffered to allow seeking back, so the underlying reader is left at the
+//! furthest position read.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::Decode;
+//!
+//! # fn main() {
+//! #[derive(NueDecode)]
+//! struct Entry {
+//!     name_offset: u8,
+//!     #[nue(skip = "(self.name_offset - 1) as u64", count = "2", restore_position)]
+//!     name: String,
+//!     flags: u8,
+//! }
+//!
+//! let entry = Entry::decode_slice(&[3, 1, 0, b'h', b'i']).unwrap();
+//! assert_eq!(entry.name, "hi");
+//! assert_eq!(entry.flags, 1);
+//! # }
+//! ```
+//!
+//! #### `peek`
+//!
+//! Decodes the field without consuming it, so that the following fields read the same bytes
+//! again, as with `restore_position`. The field isn't encoded, as its bytes are expected to
+//! be written by the fields that follow.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode)]
+//! struct Packet {
+//!     #[nue(peek)]
+//!     kind: u8,
+//!     #[nue(count = "3")]
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let packet = Packet::decode_slice(&[2, 5, 6]).unwrap();
+//! assert_eq!(packet.kind, 2);
+//! assert_eq!(packet.payload, [2, 5, 6]);
+//! assert_eq!(&packet.encode_vec().unwrap(), &[2, 5, 6]);
+//! # }
+//! ```
+//!
+//! #### `position`
+//!
+//! Decodes the field as the current offset from the start of the value, converted with
+//! `as` from a `u64`. Nothing is read from the stream, and the field isn't encoded.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Chunk {
+//!     len: u8,
+//!     #[nue(position)]
+//!     data_start: u64,
+//!     #[nue(count = "self.len as usize")]
+//!     data: Vec<u8>,
+//! }
+//!
+//! let chunk = Chunk::decode_slice(&[2, 5, 6]).unwrap();
+//! assert_eq!(chunk, Chunk { len: 2, data_start: 1, data: vec![5, 6] });
+//! assert_eq!(&chunk.encode_vec().unwrap(), &[2, 5, 6]);
+//! # }
+//! ```
+//!
 //! #### `cond`
 //!
 //! Conditionally encodes or decodes the field. If the condition is not met,
@@ -191,6 +636,91 @@
 //! # }
 //! ```
 //!
+//! #### `since`
+//!
+//! Codes the field only in versions of the format that satisfy the predicate, typically a
+//! comparison against an earlier version field. It behaves like `cond`, uses `default` in
+//! older versions, and must hold alongside any `cond` on the same field.
+//! `NueEncodedSize` leaves the field out of the size in older versions.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode, EncodedSize};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+//! struct Header {
+//!     version: u8,
+//!     len: u8,
+//!     #[nue(since = "self.version >= 3", default = "0xff")]
+//!     flags: u8,
+//! }
+//!
+//! let data = Header { version: 2, len: 4, flags: 0xff };
+//! assert_eq!(&data.encode_vec().unwrap(), &[2, 4]);
+//! assert_eq!(data.encoded_size(), 2);
+//! assert_eq!(Header::decode_slice(&[2, 4]).unwrap(), data);
+//!
+//! let data = Header { version: 3, len: 4, flags: 1 };
+//! assert_eq!(&data.encode_vec().unwrap(), &[3, 4, 1]);
+//! assert_eq!(data.encoded_size(), 3);
+//! assert_eq!(Header::decode_slice(&[3, 4, 1]).unwrap(), data);
+//! # }
+//! ```
+//!
+//! #### `ignore`
+//!
+//! Leaves the field out of the encoding entirely. It's decoded as `Default::default()`,
+//! or as the given expression with `ignore = "..."`, and its type parameters aren't
+//! required to be `Pod`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use std::rc::Rc;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Data {
+//!     len: u8,
+//!     #[nue(ignore)]
+//!     cache: Option<Rc<u8>>,
+//!     #[nue(ignore = "self.len as usize * 2")]
+//!     size: usize,
+//! }
+//!
+//! let data = Data { len: 3, cache: Some(Rc::new(3)), size: 6 };
+//! assert_eq!(&data.encode_vec().unwrap(), &[3]);
+//! assert_eq!(Data::decode_slice(&[3]).unwrap(), Data { len: 3, cache: None, size: 6 });
+//! # }
+//! ```
+//!
+//! #### `if_eof`
+//!
+//! Uses the expression as the value of the field when the stream ends before it, instead
+//! of failing. Fields added to the end of a format by later versions can then still be
+//! decoded from older data. It doesn't affect encoding.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::Decode;
+//!
+//! # fn main() {
+//! #[derive(NueDecode)]
+//! struct Header {
+//!     version: u8,
+//!     #[nue(if_eof = "0")]
+//!     flags: u8,
+//! }
+//!
+//! assert_eq!(Header::decode_slice(&[1]).unwrap().flags, 0);
+//! assert_eq!(Header::decode_slice(&[2, 3]).unwrap().flags, 3);
+//! # }
+//! ```
+//!
 //! #### `limit`
 //!
 //! Limits the amount of bytes that can be consumed or written during coding.
@@ -236,6 +766,441 @@
 //! assert_eq!(data.1, 5);
 //! # }
 //! ```
+//!
+//! #### `through`
+//!
+//! Extends `limit` from this field through the named later field, so that the fields share a
+//! single budget of bytes. Each field gets whatever the earlier ones left, and `consume` on
+//! the last field uses up the rest of the group. Groups can't overlap or contain `bits` fields.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Envelope {
+//!     len: u8,
+//!     #[nue(limit = "self.len", through = "body")]
+//!     kind: u8,
+//!     #[nue(consume = "true")]
+//!     body: Vec<u8>,
+//!     trailer: u8,
+//! }
+//!
+//! let data = Envelope { len: 4, kind: 1, body: vec![2, 3], trailer: 9 };
+//! assert_eq!(&data.encode_vec().unwrap(), &[4, 1, 2, 3, 0, 9]);
+//! assert_eq!(Envelope::decode_slice(&[4, 1, 2, 3, 4, 9]).unwrap().body, [2, 3, 4]);
+//! # }
+//! ```
+//!
+//! #### `count`
+//!
+//! Decodes exactly the given number of elements into a `Vec` field, or bytes into a `String`,
+//! rather than reading until the end of the stream. Encoding writes the elements as usual.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::Decode;
+//!
+//! # fn main() {
+//! #[derive(NueDecode)]
+//! struct Data {
+//!     len: u8,
+//!     #[nue(count = "self.len")]
+//!     items: Vec<u8>,
+//!     trailer: u8,
+//! }
+//!
+//! let data = Data::decode_slice(&[2, 5, 6, 7]).unwrap();
+//! assert_eq!(&data.items, &[5, 6]);
+//! assert_eq!(data.trailer, 7);
+//! # }
+//! ```
+//!
+//! #### `count_of`
+//!
+//! Marks an integer field as the length of another field. Encoding writes the `len()` of
+//! the named field in its place, and decoding uses it as the `count` of that field.
+//! Encoding fails if the length doesn't fit in the integer type.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Data {
+//!     #[nue(count_of = "name")]
+//!     name_len: u8,
+//!     name: String,
+//!     trailer: u8,
+//! }
+//!
+//! let data = Data { name_len: 0, name: "nue".into(), trailer: 1 };
+//! let cmp = &[3, b'n', b'u', b'e', 1];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Data::decode_slice(cmp).unwrap().name, "nue");
+//! # }
+//! ```
+//!
+//! #### `value`
+//!
+//! Encodes the result of the expression in place of the field's stored value, such as a
+//! length or flags derived from other fields. The expression must have the field's type.
+//! Decoding fills the field as usual.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Record {
+//!     #[nue(value = "self.body.len() as u8")]
+//!     len: u8,
+//!     #[nue(count = "self.len")]
+//!     body: Vec<u8>,
+//! }
+//!
+//! let data = Record { len: 0, body: vec![1, 2, 3] };
+//! let cmp = &[3, 1, 2, 3];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Record::decode_slice(cmp).unwrap().len, 3);
+//! # }
+//! ```
+//!
+//! #### `terminator`
+//!
+//! Codes a `Vec<T>` field as its elements followed by the terminator element, instead of
+//! relying on a count. Decoding stops at the first element equal to the terminator, which
+//! is consumed but not included. Encoding fails if an element equals the terminator.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Data {
+//!     #[nue(terminator = "0xff")]
+//!     items: Vec<u8>,
+//!     trailer: u8,
+//! }
+//!
+//! let data = Data { items: vec![1, 2], trailer: 3 };
+//! let cmp = &[1, 2, 0xff, 3];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Data::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! #### `until`
+//!
+//! Decodes the elements of a `Vec<T>` field until the expression is true, with `last`
+//! referring to the element just read, which is included. Encoding fails unless the
+//! expression holds for the last element only, so the field can't be empty.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Varint {
+//!     #[nue(until = "*last & 0x80 == 0")]
+//!     bytes: Vec<u8>,
+//!     trailer: u8,
+//! }
+//!
+//! let cmp = &[0x81, 0x82, 0x03, 4];
+//! let data = Varint::decode_slice(cmp).unwrap();
+//! assert_eq!(&data.bytes, &[0x81, 0x82, 0x03]);
+//! assert_eq!(data.trailer, 4);
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! # }
+//! ```
+//!
+//! #### `checksum`, `over`
+//!
+//! Computes the field as a checksum of the bytes from the start of the `over` field, or the
+//! first field, up to the checksum itself. Encoding writes the checksum in place of the
+//! field's value, and decoding fails if it doesn't match. `crc32` uses `nue::Crc32`, and any
+//! other value names a type implementing `nue::Digest` and `Default`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode, Crc32};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode)]
+//! #[nue(endian = "little")]
+//! struct Block {
+//!     len: u8,
+//!     data: [u8; 4],
+//!     #[nue(checksum = "crc32", over = "data")]
+//!     crc: u32,
+//! }
+//!
+//! let data = Block { len: 4, data: *b"1234", crc: 0 }.encode_vec().unwrap();
+//! assert_eq!(&data[5..], &Crc32::checksum(b"1234").to_le_bytes());
+//! assert_eq!(Block::decode_slice(&data).unwrap().crc, Crc32::checksum(b"1234"));
+//! # }
+//! ```
+//!
+//! #### `skip_bound`
+//!
+//! Doesn't require the type parameters used by this field to be `Pod`, such as for
+//! marker types that are coded some other way.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use std::marker::PhantomData;
+//! use nue::Encode;
+//!
+//! struct Tagged<T>(u8, PhantomData<T>);
+//!
+//! impl<T> Encode for Tagged<T> {
+//!     type Options = ();
+//!
+//!     fn encode<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+//!         self.0.encode(w)
+//!     }
+//! }
+//!
+//! # fn main() {
+//! #[derive(NueEncode)]
+//! struct Data<T, U> {
+//!     #[nue(skip_bound)]
+//!     tagged: Tagged<T>,
+//!     marker: PhantomData<U>,
+//! }
+//!
+//! let data = Data::<String, Vec<u8>> { tagged: Tagged(1, PhantomData), marker: PhantomData };
+//! assert_eq!(&data.encode_vec().unwrap(), &[1]);
+//! # }
+//! ```
+//!
+//! #### `with`
+//!
+//! Codes the field with the `encode` and `decode` functions of the given module instead of
+//! its `Encode` / `Decode` implementations, which the field type then doesn't need.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! mod bcd {
+//!     use std::io::{self, Read, Write};
+//!     use nue::{Encode, Decode};
+//!
+//!     pub fn encode<W: Write>(value: &u8, w: &mut W) -> io::Result<()> {
+//!         ((value / 10) << 4 | value % 10).encode(w)
+//!     }
+//!
+//!     pub fn decode<R: Read>(r: &mut R) -> io::Result<u8> {
+//!         u8::decode(r).map(|b| (b >> 4) * 10 + (b & 0xf))
+//!     }
+//! }
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode)]
+//! struct Data {
+//!     #[nue(with = "bcd")]
+//!     minutes: u8,
+//! }
+//!
+//! let data = Data { minutes: 42 };
+//! assert_eq!(&data.encode_vec().unwrap(), &[0x42]);
+//! assert_eq!(Data::decode_slice(&[0x17]).unwrap().minutes, 17);
+//! # }
+//! ```
+//!
+//! #### `map`, `unmap`
+//!
+//! Converts the field from its wire representation after decoding, and back before encoding.
+//! Each is called with one argument: `map` takes the decoded wire value, and `unmap` takes a
+//! reference to the field. The wire type is inferred from them, so a byte order must be part
+//! of that type rather than set with `endian`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode)]
+//! struct Extent {
+//!     #[nue(map = "|sectors: u8| sectors as u64 * 512", unmap = "|bytes: &u64| (*bytes / 512) as u8")]
+//!     offset: u64,
+//! }
+//!
+//! let data = Extent { offset: 0x400 };
+//! assert_eq!(&data.encode_vec().unwrap(), &[2]);
+//! assert_eq!(Extent::decode_slice(&[3]).unwrap().offset, 0x600);
+//! # }
+//! ```
+//!
+//! #### `tag`
+//!
+//! Codes an enum field without its discriminant tag, which is instead provided by the
+//! expression, usually an earlier field. Encoding fails if the variant doesn't match the tag.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! enum Payload {
+//!     Byte(u8),
+//!     Pair(u8, u8),
+//! }
+//!
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Packet {
+//!     kind: u8,
+//!     len: u8,
+//!     #[nue(tag = "self.kind")]
+//!     payload: Payload,
+//! }
+//!
+//! let data = Packet { kind: 1, len: 2, payload: Payload::Pair(3, 4) };
+//! let cmp = &[1, 2, 3, 4];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Packet::decode_slice(cmp).unwrap(), data);
+//!
+//! let data = Packet { kind: 0, len: 2, payload: Payload::Pair(3, 4) };
+//! assert!(data.encode_vec().is_err());
+//! # }
+//! ```
+//!
+//! #### `bits`
+//!
+//! Packs an integer or `bool` field into the given number of bits, most significant bit
+//! first. Adjacent `bits` fields share bytes, and the last byte of a run is padded with zero
+//! bits. Signed integers are stored in two's complement and sign extended when decoded.
+//! Encoding fails if a value doesn't fit in its width, and `bits` can't be combined with
+//! other attributes.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Flags {
+//!     #[nue(bits = 4)]
+//!     version: u8,
+//!     #[nue(bits = 3)]
+//!     kind: u8,
+//!     #[nue(bits = 1)]
+//!     last: bool,
+//!     len: u8,
+//! }
+//!
+//! let data = Flags { version: 4, kind: 5, last: true, len: 2 };
+//! let cmp = &[0x4b, 2];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Flags::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! #### `varint`
+//!
+//! Codes an integer field as a variable-length LEB128 `nue::Varint`, with signed integers
+//! sign extended. This is commonly combined with `count_of` for a length prefix, and applies
+//! to each element of a `terminator` or `until` field. Decoding fails if the value doesn't
+//! fit in the field's type.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, Decode};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+//! struct Record {
+//!     #[nue(varint)]
+//!     delta: i32,
+//!     #[nue(varint, count_of = "data")]
+//!     len: u32,
+//!     data: Vec<u8>,
+//! }
+//!
+//! let data = Record { delta: -2, len: 2, data: vec![1, 2] };
+//! let cmp = &[0x7e, 2, 1, 2];
+//! assert_eq!(&data.encode_vec().unwrap(), cmp);
+//! assert_eq!(Record::decode_slice(cmp).unwrap(), data);
+//! # }
+//! ```
+//!
+//! ## `#[derive(NueDecodeRef)]`
+//!
+//! Implements `nue::DecodeRef` on a struct with a lifetime parameter, decoding it from a
+//! byte slice without copying. Fields whose types have a lifetime, such as `&str`, `&[T]` or
+//! `&T` of an `Unaligned` POD type, borrow from the slice, and others are decoded with `Decode`.
+//!
+//! Only the field attributes that don't seek are supported: `cond`, `default`, `assert`,
+//! `skip`, `count`, `count_of`, `tag`, `magic`, `endian`, `varint` and `position`.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::DecodeRef;
+//!
+//! # fn main() {
+//! #[derive(NueDecodeRef)]
+//! struct Entry<'a> {
+//!     #[nue(count_of = "name")]
+//!     len: u8,
+//!     name: &'a str,
+//!     data: &'a [u8],
+//! }
+//!
+//! let buffer = [2, b'h', b'i', 1, 2];
+//! let entry = Entry::decode_ref(&mut &buffer[..]).unwrap();
+//! assert_eq!(entry.name, "hi");
+//! assert_eq!(entry.data, [1, 2]);
+//! # }
+//! ```
+//!
+//! ## `#[derive(NueEncodedSize)]`
+//!
+//! Implements `nue::EncodedSize`, computing the size that `NueEncode` would write from the
+//! same `nue` and `nue_enc` attributes, without encoding anything. Fields coded `with` a
+//! module are encoded to a `nue::Null` stream to measure them.
+//!
+//! ```
+//! # #![feature(plugin, custom_derive, custom_attribute)] #![plugin(nue_macros)]
+//! # extern crate nue;
+//! use nue::{Encode, EncodedSize};
+//!
+//! # fn main() {
+//! #[derive(NueEncode, NueEncodedSize)]
+//! struct Data {
+//!     kind: u8,
+//!     #[nue(align = "4")]
+//!     len: u32,
+//!     name: String,
+//! }
+//!
+//! let data = Data { kind: 1, len: 3, name: "nue".into() };
+//! assert_eq!(data.encoded_size(), 11);
+//! assert_eq!(data.encoded_size(), data.encode_vec().unwrap().len() as u64);
+//! # }
+//! ```
 
 extern crate rustc;
 extern crate nue_codegen;
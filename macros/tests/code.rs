@@ -46,3 +46,887 @@ fn encode_decode() {
     let pod2_decoded = Decode::decode(&mut buffer2).unwrap();
     assert_eq!(&pod2, &pod2_decoded);
 }
+
+#[test]
+fn encode_decode_enum() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[repr(u8)]
+    enum Tagged {
+        A,
+        B(u8, Un<u16>),
+        C {
+            _0: u8,
+            #[nue(cond = "self._0 == 1", default = "0")]
+            _1: u8,
+        },
+    }
+
+    let b = Tagged::B(1, 2u16.unaligned());
+    let c = Tagged::C { _0: 0, _1: 0 };
+
+    assert_eq!(Tagged::A.encode_vec().unwrap(), [0]);
+    assert_eq!(Decode::decode_slice(&[0][..]).ok(), Some(Tagged::A));
+
+    let buffer = b.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 4);
+    assert_eq!(buffer[0], 1);
+    assert_eq!(&Tagged::decode_slice(&buffer).unwrap(), &b);
+
+    assert_eq!(Tagged::decode_slice(&[2, 0]).unwrap(), c);
+    assert!(Tagged::decode_slice(&[3]).is_err());
+}
+
+#[test]
+fn external_tag() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(tag_type = "u16")]
+    enum Body {
+        Empty,
+        Value(Un<u32>),
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Record {
+        kind: u8,
+        _pad: u8,
+        #[nue(tag = "self.kind")]
+        body: Body,
+    }
+
+    let record = Record { kind: 1, _pad: 0, body: Body::Value(7u32.unaligned()) };
+    let buffer = record.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 6);
+    assert_eq!(&Record::decode_slice(&buffer).unwrap(), &record);
+
+    assert_eq!(Record::decode_slice(&[0, 0]).unwrap().body, Body::Empty);
+    assert!(Record::decode_slice(&[2, 0]).is_err());
+}
+
+#[test]
+fn count() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct List {
+        len: u8,
+        #[nue(count = "self.len")]
+        items: Vec<Un<u16>>,
+        #[nue(count = "2")]
+        trailer: Vec<u8>,
+    }
+
+    let list = List { len: 2, items: vec![1u16.unaligned(), 2u16.unaligned()], trailer: vec![3, 4] };
+    let buffer = list.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 7);
+    assert_eq!(&List::decode_slice(&buffer).unwrap(), &list);
+    assert!(List::decode_slice(&buffer[..6]).is_err());
+}
+
+#[test]
+fn count_of() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    enum Chunk {
+        Items {
+            #[nue(count_of = "items")]
+            len: u8,
+            items: Vec<u8>,
+            end: u8,
+        },
+    }
+
+    let chunk = Chunk::Items { len: 0, items: vec![5, 6, 7], end: 8 };
+    let buffer = chunk.encode_vec().unwrap();
+    assert_eq!(buffer, [0, 3, 5, 6, 7, 8]);
+    assert_eq!(Chunk::decode_slice(&buffer).unwrap(), Chunk::Items { len: 3, items: vec![5, 6, 7], end: 8 });
+
+    let chunk = Chunk::Items { len: 0, items: vec![0; 300], end: 8 };
+    assert_eq!(chunk.encode_vec().unwrap_err().kind(), ::std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn magic() {
+    use std::io::ErrorKind;
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(magic = "b\"NUE\"")]
+    enum Versioned {
+        V0,
+        V1 {
+            #[nue(magic = "&[0xff]")]
+            value: u8,
+        },
+    }
+
+    let v1 = Versioned::V1 { value: 2 };
+    let buffer = v1.encode_vec().unwrap();
+    assert_eq!(buffer, b"NUE\x01\xff\x02");
+    assert_eq!(Versioned::decode_slice(&buffer).unwrap(), v1);
+    assert_eq!(Versioned::decode_slice(b"NUF\x00").unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn endian() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(endian = "big")]
+    struct Big {
+        a: u16,
+        b: i32,
+        #[nue(count_of = "c")]
+        len: u16,
+        c: Vec<u8>,
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(endian = "little", tag_type = "u16")]
+    enum Little {
+        _A,
+        B(u32),
+        C {
+            #[nue(endian = "big")]
+            big: u32,
+            little: u16,
+        },
+    }
+
+    let big = Big { a: 0x0102, b: -2, len: 1, c: vec![3] };
+    let buffer = big.encode_vec().unwrap();
+    assert_eq!(buffer, [1, 2, 0xff, 0xff, 0xff, 0xfe, 0, 1, 3]);
+    assert_eq!(Big::decode_slice(&buffer).unwrap(), big);
+
+    let little = Little::B(0x01020304);
+    let buffer = little.encode_vec().unwrap();
+    assert_eq!(buffer, [1, 0, 4, 3, 2, 1]);
+    assert_eq!(Little::decode_slice(&buffer).unwrap(), little);
+
+    let mixed = Little::C { big: 0x01020304, little: 0x0506 };
+    let buffer = mixed.encode_vec().unwrap();
+    assert_eq!(buffer, [2, 0, 1, 2, 3, 4, 6, 5]);
+    assert_eq!(Little::decode_slice(&buffer).unwrap(), mixed);
+}
+
+mod nibbles {
+    use std::io::{self, Read, Write};
+    use nue::{Encode, Decode};
+
+    pub fn encode<W: Write>(value: &(u8, u8), w: &mut W) -> io::Result<()> {
+        (value.0 << 4 | value.1 & 0xf).encode(w)
+    }
+
+    pub fn decode<R: Read>(r: &mut R) -> io::Result<(u8, u8)> {
+        u8::decode(r).map(|b| (b >> 4, b & 0xf))
+    }
+}
+
+#[test]
+fn varint() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Message {
+        #[nue(varint)]
+        id: i32,
+        #[nue(varint, count_of = "body")]
+        len: u32,
+        body: Vec<u8>,
+        #[nue(varint, terminator = "0")]
+        refs: Vec<u64>,
+    }
+
+    let message = Message { id: -2, len: 130, body: vec![7; 130], refs: vec![300, 1] };
+    let buffer = message.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 137);
+    assert_eq!(message.encoded_size(), 137);
+    assert_eq!(buffer[..3], [0x7e, 0x82, 0x01]);
+    assert_eq!(buffer[133..], [0xac, 0x02, 0x01, 0]);
+    assert_eq!(Message::decode_slice(&buffer).unwrap(), message);
+
+    assert!(Message::decode_slice(&[0x80, 0x80, 0x80, 0x80, 0x10, 0]).is_err());
+}
+
+#[test]
+fn byte_order() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    #[nue(byte_order = "if self.bom == *b\"II\" { nue::Endianness::Little } else { nue::Endianness::Big }")]
+    struct Header {
+        bom: [u8; 2],
+        version: u16,
+        #[nue(endian = "little")]
+        flags: u16,
+        offset: u32,
+    }
+
+    let big = Header { bom: *b"MM", version: 42, flags: 1, offset: 8 };
+    let buffer = big.encode_vec().unwrap();
+    assert_eq!(buffer, [b'M', b'M', 0, 42, 1, 0, 0, 0, 0, 8]);
+    assert_eq!(big.encoded_size(), 10);
+    assert_eq!(Header::decode_slice(&buffer).unwrap(), big);
+
+    let little = Header { bom: *b"II", ..big };
+    let buffer = little.encode_vec().unwrap();
+    assert_eq!(buffer, [b'I', b'I', 42, 0, 1, 0, 8, 0, 0, 0]);
+    assert_eq!(Header::decode_slice(&buffer).unwrap(), little);
+}
+
+#[test]
+fn with() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Packed {
+        #[nue(with = "nibbles")]
+        pair: (u8, u8),
+        next: u8,
+    }
+
+    let packed = Packed { pair: (1, 2), next: 3 };
+    let buffer = packed.encode_vec().unwrap();
+    assert_eq!(buffer, [0x12, 3]);
+    assert_eq!(Packed::decode_slice(&buffer).unwrap(), packed);
+}
+
+#[test]
+fn map() {
+    use nue::Be;
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Timeout {
+        #[nue(map = "|ms: Be<u16>| ms.get() as f64 / 1000.0", unmap = "|secs: &f64| Be::new((*secs * 1000.0) as u16)")]
+        secs: f64,
+        #[nue(count = "2", map = "|name: Vec<u8>| name.into_iter().map(char::from).collect()", unmap = "|name: &Vec<char>| name.iter().map(|&c| c as u8).collect::<Vec<u8>>()")]
+        name: Vec<char>,
+    }
+
+    let timeout = Timeout { secs: 1.5, name: vec!['r', 'x'] };
+    let buffer = timeout.encode_vec().unwrap();
+    assert_eq!(buffer, [0x05, 0xdc, b'r', b'x']);
+    assert_eq!(Timeout::decode_slice(&buffer).unwrap(), timeout);
+}
+
+#[test]
+fn restore_position() {
+    #[derive(NueDecode, PartialEq, Debug)]
+    struct Table {
+        count: u8,
+        #[nue(skip = "2", count = "self.count", restore_position)]
+        strings: Vec<u16>,
+        #[nue(count = "self.count")]
+        offsets: Vec<u8>,
+    }
+
+    let table = Table::decode_slice(&[2, 5, 6, 1, 0, 2, 0]).unwrap();
+    assert_eq!(table, Table {
+        count: 2,
+        strings: vec![u16::from_le(1), u16::from_le(2)],
+        offsets: vec![5, 6],
+    });
+}
+
+#[test]
+fn peek() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Packet {
+        #[nue(peek)]
+        kind: u8,
+        #[nue(count = "3")]
+        payload: Vec<u8>,
+    }
+
+    let packet = Packet::decode_slice(&[2, 5, 6]).unwrap();
+    assert_eq!(packet, Packet { kind: 2, payload: vec![2, 5, 6] });
+    assert_eq!(packet.encode_vec().unwrap(), [2, 5, 6]);
+    assert_eq!(packet.encoded_size(), 3);
+}
+
+#[test]
+fn terminator() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(endian = "big")]
+    struct Strings {
+        #[nue(terminator = "0")]
+        name: Vec<u8>,
+        #[nue(terminator = "0xffff")]
+        codes: Vec<u16>,
+    }
+
+    let strings = Strings { name: b"nue".to_vec(), codes: vec![1, 0x100] };
+    let buffer = strings.encode_vec().unwrap();
+    assert_eq!(buffer, [b'n', b'u', b'e', 0, 0, 1, 1, 0, 0xff, 0xff]);
+    assert_eq!(Strings::decode_slice(&buffer).unwrap(), strings);
+
+    assert!(Strings { name: vec![0], codes: vec![] }.encode_vec().is_err());
+    assert!(Strings::decode_slice(&[b'n', 0, 0, 1]).is_err());
+}
+
+#[test]
+fn until() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Chunk {
+        flags: u8,
+        len: u8,
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Chunks {
+        #[nue(until = "last.flags & 0x80 == 0")]
+        chunks: Vec<Chunk>,
+    }
+
+    let chunks = Chunks { chunks: vec![Chunk { flags: 0x81, len: 2 }, Chunk { flags: 0x01, len: 3 }] };
+    let buffer = chunks.encode_vec().unwrap();
+    assert_eq!(buffer, [0x81, 2, 0x01, 3]);
+    assert_eq!(Chunks::decode_slice(&[0x81, 2, 0x01, 3, 0x00, 4]).unwrap(), chunks);
+
+    assert!(Chunks { chunks: vec![Chunk { flags: 0x81, len: 2 }] }.encode_vec().is_err());
+    assert!(Chunks { chunks: vec![] }.encode_vec().is_err());
+    assert!(Chunks::decode_slice(&[0x81, 2]).is_err());
+}
+
+#[test]
+fn if_eof() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Versioned {
+        version: u8,
+        #[nue(if_eof = "0x100")]
+        extra: u16,
+        #[nue(if_eof = "Vec::new()", count = "2")]
+        tail: Vec<u8>,
+    }
+
+    assert_eq!(Versioned::decode_slice(&[1]).unwrap(), Versioned { version: 1, extra: 0x100, tail: vec![] });
+    assert_eq!(Versioned::decode_slice(&[2, 3, 0]).unwrap(), Versioned { version: 2, extra: u16::from_le(3), tail: vec![] });
+    assert!(Versioned::decode_slice(&[2, 3]).is_err());
+
+    let versioned = Versioned { version: 3, extra: 4, tail: vec![5, 6] };
+    assert_eq!(Versioned::decode_slice(&versioned.encode_vec().unwrap()).unwrap(), versioned);
+}
+
+#[test]
+fn checksum() {
+    use nue::{Digest, Crc32};
+
+    #[derive(Default)]
+    struct Sum(u8);
+
+    impl Digest for Sum {
+        type Output = u8;
+
+        fn update(&mut self, data: &[u8]) {
+            for &b in data {
+                self.0 = self.0.wrapping_add(b);
+            }
+        }
+
+        fn finish(&self) -> u8 { self.0 }
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Summed {
+        kind: u8,
+        #[nue(align = "2")]
+        payload: u8,
+        #[nue(checksum = "Sum")]
+        sum: u8,
+    }
+
+    let summed = Summed { kind: 1, payload: 2, sum: 3 };
+    let buffer = Summed { sum: 0, ..summed }.encode_vec().unwrap();
+    assert_eq!(buffer, [1, 0, 2, 3]);
+    assert_eq!(Summed::decode_slice(&buffer).unwrap(), summed);
+    assert!(Summed::decode_slice(&[1, 0, 2, 4]).is_err());
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(endian = "big")]
+    struct Framed {
+        len: u8,
+        #[nue(count = "self.len")]
+        data: Vec<u8>,
+        #[nue(checksum = "crc32", over = "data")]
+        crc: u32,
+    }
+
+    let framed = Framed { len: 3, data: b"nue".to_vec(), crc: Crc32::checksum(b"nue") };
+    let buffer = framed.encode_vec().unwrap();
+    assert_eq!(&buffer[4..], &framed.crc.to_be_bytes());
+    assert_eq!(Framed::decode_slice(&buffer).unwrap(), framed);
+}
+
+#[test]
+fn encoded_size() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueEncodedSize)]
+    #[nue(magic = "b\"NS\"")]
+    struct Sizes {
+        flags: u8,
+        #[nue(skip = "1", align = "4")]
+        len: u16,
+        #[nue(cond = "self.flags & 1 != 0")]
+        extra: u32,
+        #[nue(limit = "4", consume = "true")]
+        name: String,
+        #[nue(terminator = "0")]
+        path: Vec<u8>,
+        #[nue(tag = "1")]
+        choice: Choice,
+    }
+
+    #[derive(NueEncode, NueEncodedSize)]
+    #[nue(tag_type = "u16")]
+    enum Choice {
+        Empty,
+        Byte(u8),
+    }
+
+    let mut sizes = Sizes { flags: 0, len: 2, extra: 3, name: "nue".into(), path: vec![1, 2], choice: Choice::Byte(4) };
+    for &flags in &[0, 1] {
+        sizes.flags = flags;
+        assert_eq!(sizes.encoded_size(), sizes.encode_vec().unwrap().len() as u64);
+    }
+    assert_eq!(Choice::Empty.encoded_size(), 2);
+    assert_eq!(Choice::Byte(0).encoded_size(), 3);
+}
+
+#[test]
+fn options() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(option = "version: u16")]
+    #[nue_dec(option = "len: Option<usize>")]
+    struct Versioned {
+        #[nue(cond = "options.version >= 2")]
+        id: u8,
+        #[nue_dec(count = "options.len.unwrap_or(1)")]
+        items: Vec<u8>,
+    }
+
+    let versioned = Versioned { id: 1, items: vec![2, 3] };
+    assert_eq!(versioned.encode_vec().unwrap(), [2, 3]);
+    let buffer = versioned.encode_vec_options(VersionedEncodeOptions { version: 2 }).unwrap();
+    assert_eq!(buffer, [1, 2, 3]);
+
+    let options = VersionedDecodeOptions { version: 2, len: Some(2) };
+    assert_eq!(Versioned::decode_slice_options(&buffer, options.clone()).unwrap(), versioned);
+    assert_eq!(Versioned::decode_slice(&buffer).unwrap(), Versioned { id: 0, items: vec![1] });
+}
+
+#[test]
+fn bound() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue_enc(bound = "T: Encode, T::Options: Clone")]
+    #[nue_dec(bound = "T: Decode, T::Options: Clone")]
+    struct Counted<T> {
+        #[nue(count_of = "items")]
+        len: u8,
+        items: Vec<T>,
+    }
+
+    let counted = Counted { len: 0, items: vec![Counted::<u8> { len: 0, items: vec![7] }] };
+    let buffer = counted.encode_vec().unwrap();
+    assert_eq!(buffer, [1, 1, 7]);
+    assert_eq!(Counted::<Counted<u8>>::decode_slice(&buffer).unwrap().items[0].items, [7]);
+}
+
+#[test]
+fn phantom() {
+    use std::marker::PhantomData;
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Marked<T, U> {
+        value: T,
+        _marker: PhantomData<U>,
+    }
+
+    let marked = Marked::<u16, String> { value: 1, _marker: PhantomData };
+    let buffer = marked.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(Marked::<u16, String>::decode_slice(&buffer).unwrap(), marked);
+}
+
+#[test]
+fn field_errors() {
+    use nue::Error;
+
+    #[derive(NueDecode, Debug)]
+    struct Inner {
+        _kind: u8,
+        #[nue(assert = "self._len < 4")]
+        _len: u8,
+    }
+
+    #[derive(NueDecode, Debug)]
+    enum Outer {
+        Empty,
+        Nested(u16, Inner),
+    }
+
+    let err = Error::from(Outer::decode_slice(&[1, 0, 0, 2, 8]).unwrap_err());
+    assert!(err.to_string().starts_with("Outer::Nested.1 at offset 0x3: Inner._len at offset 0x1: validation of _len failed"));
+    match *err.root() {
+        Error::Validation { field: Some("_len"), .. } => (),
+        ref err => panic!("unexpected {:?}", err),
+    }
+
+    assert_eq!(Outer::decode_slice(&[1, 0]).unwrap_err().kind(), ::std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn ignore() {
+    use std::cell::Cell;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, Debug)]
+    #[nue_dec(bound = "T: Default")]
+    struct Cached<T> {
+        value: u16,
+        #[nue(ignore)]
+        hits: Cell<u32>,
+        #[nue(ignore = "T::default()")]
+        _label: T,
+    }
+
+    let cached = Cached { value: 0x0102, hits: Cell::new(4), _label: "label".to_owned() };
+    let buffer = cached.encode_vec().unwrap();
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(nue::EncodedSize::encoded_size(&cached), 2);
+
+    let decoded = Cached::<String>::decode_slice(&buffer).unwrap();
+    assert_eq!(decoded.value, 0x0102);
+    assert_eq!(decoded.hits.get(), 0);
+    assert_eq!(decoded._label, "");
+}
+
+#[test]
+fn pod_enum() {
+    use nue::{Pod, CheckedPod};
+
+    #[derive(Pod, Copy, Clone, PartialEq, Debug)]
+    #[repr(u8)]
+    enum Kind {
+        Data = 1,
+        Ack,
+    }
+
+    #[derive(Pod, Copy, Clone, PartialEq, Debug)]
+    #[repr(i16)]
+    enum Wide {
+        Low = -1,
+        High = 0x100,
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Packet {
+        kind: Kind,
+        len: u8,
+    }
+
+    assert_eq!(Kind::decode_slice(&[2]).unwrap(), Kind::Ack);
+    assert!(Kind::decode_slice(&[0]).is_err());
+    assert_eq!(Kind::try_copy_from(&[1]), Some(Kind::Data));
+    assert_eq!(Kind::try_copy_from(&[3]), None);
+    assert_eq!(Wide::decode_slice(&0x100i16.as_slice()).unwrap(), Wide::High);
+    assert!(Wide::decode_slice(&0x101i16.as_slice()).is_err());
+    assert_eq!(Wide::Low.encode_vec().unwrap(), (-1i16).as_slice());
+    assert_eq!(Packet::decode_slice(&[1, 4]).unwrap(), Packet { kind: Kind::Data, len: 4 });
+    assert!(Packet::decode_slice(&[3, 4]).is_err());
+}
+
+#[test]
+fn pad_to() {
+    use nue::{EncodedSize, Error};
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    #[nue(magic = "b\"R\"", pad_to = "6")]
+    struct Record {
+        kind: u8,
+        #[nue(count = "self.kind as usize")]
+        data: Vec<u8>,
+    }
+
+    let records = [Record { kind: 1, data: vec![7] }, Record { kind: 2, data: vec![8, 9] }];
+    let mut buffer = Vec::new();
+    for record in &records {
+        record.encode(&mut buffer).unwrap();
+        assert_eq!(record.encoded_size(), 6);
+    }
+    assert_eq!(buffer, b"R\x01\x07\0\0\0R\x02\x08\x09\0\0");
+
+    let mut cursor = Cursor::new(&buffer[..]);
+    assert_eq!(Record::decode(&mut cursor).unwrap(), records[0]);
+    assert_eq!(Record::decode(&mut cursor).unwrap(), records[1]);
+
+    let oversized = Record { kind: 5, data: vec![0; 5] };
+    match Error::from(oversized.encode_vec().unwrap_err()) {
+        Error::Limit { limit: 6 } => (),
+        err => panic!("unexpected {:?}", err),
+    }
+}
+
+#[test]
+fn hooks() {
+    use std::io;
+    use std::cell::Cell;
+
+    thread_local!(static ENCODED: Cell<usize> = Cell::new(0));
+
+    fn count<T>(_: &T) -> io::Result<()> {
+        ENCODED.with(|encoded| encoded.set(encoded.get() + 1));
+        Ok(())
+    }
+
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    #[nue(magic = "b\"H\"", before_encode = "count", after_decode = "Header::swap")]
+    struct Header {
+        #[nue(endian = "little")]
+        legacy: u16,
+    }
+
+    impl Header {
+        fn swap(&mut self) -> io::Result<()> {
+            self.legacy = self.legacy.swap_bytes();
+            Ok(())
+        }
+    }
+
+    let buffer = Header { legacy: 0x0102 }.encode_vec().unwrap();
+    assert_eq!(buffer, b"H\x02\x01");
+    assert_eq!(ENCODED.with(|encoded| encoded.get()), 1);
+    assert_eq!(Header::decode_slice(&buffer).unwrap(), Header { legacy: 0x0201 });
+}
+
+#[test]
+fn position() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    #[nue(magic = "b\"P\"")]
+    struct Entry {
+        #[nue(position)]
+        start: usize,
+        id: u16,
+        #[nue(position)]
+        end: u32,
+        flag: u8,
+    }
+
+    let entry = Entry::decode_slice(&[b'P', 1, 0, 9]).unwrap();
+    assert_eq!(entry, Entry { start: 1, id: 1, end: 3, flag: 9 });
+    assert_eq!(entry.encode_vec().unwrap(), [b'P', 1, 0, 9]);
+    assert_eq!(entry.encoded_size(), 4);
+}
+
+#[test]
+fn decode_ref() {
+    use nue::{DecodeRef, Error, Be};
+
+    #[derive(NueDecodeRef, PartialEq, Debug)]
+    struct Header<'a> {
+        magic: &'a [u8; 2],
+        #[nue(endian = "big")]
+        count: u16,
+    }
+
+    #[derive(NueDecodeRef, PartialEq, Debug)]
+    #[nue(magic = "b\"R\"")]
+    struct Record<'a> {
+        header: Header<'a>,
+        #[nue(count = "self.header.count")]
+        values: &'a [Be<u16>],
+        #[nue(position)]
+        name_start: usize,
+        #[nue(skip = "1", count = "2")]
+        name: &'a str,
+    }
+
+    let buffer = b"RNU\x00\x02\x00\x01\x00\x02_ok";
+    let mut data = &buffer[..];
+    let record = Record::decode_ref(&mut data).unwrap();
+    assert!(data.is_empty());
+    assert_eq!(record.header, Header { magic: b"NU", count: 2 });
+    assert_eq!(record.values.iter().map(|v| v.get()).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(record.values.as_ptr() as *const u8, buffer[5..].as_ptr());
+    assert_eq!(record.name_start, 9);
+    assert_eq!(record.name, "ok");
+
+    match Error::from(Record::decode_ref(&mut &buffer[..10]).unwrap_err()) {
+        Error::Field { type_name: "Record", field: "name", offset: Some(10), .. } => (),
+        err => panic!("unexpected {:?}", err),
+    }
+}
+
+#[test]
+fn bits() {
+    use nue::{EncodedSize, Error};
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Header {
+        #[nue(bits = 4)]
+        version: u8,
+        #[nue(bits = 3)]
+        kind: u8,
+        #[nue(bits = 1)]
+        last: bool,
+        #[nue(endian = "big")]
+        len: u16,
+        #[nue(bits = 10)]
+        id: u16,
+    }
+
+    let header = Header { version: 4, kind: 5, last: true, len: 0x102, id: 0x3ff };
+    let buffer = header.encode_vec().unwrap();
+    assert_eq!(buffer, [0x4b, 1, 2, 0xff, 0xc0]);
+    assert_eq!(header.encoded_size(), 5);
+    assert_eq!(Header::decode_slice(&buffer).unwrap(), header);
+
+    assert!(Header { version: 0x10, ..header }.encode_vec().is_err());
+    match Error::from(Header::decode_slice(&buffer[..4]).unwrap_err()) {
+        Error::Field { type_name: "Header", field: "id", offset: Some(3), .. } => (),
+        err => panic!("unexpected {:?}", err),
+    }
+}
+
+#[test]
+fn signed_bits() {
+    #[derive(NueEncode, NueDecode, PartialEq, Debug)]
+    struct Delta {
+        #[nue(bits = 4)]
+        x: i8,
+        #[nue(bits = 4)]
+        y: i8,
+    }
+
+    let delta = Delta { x: -3, y: 7 };
+    let buffer = delta.encode_vec().unwrap();
+    assert_eq!(buffer, [0xd7]);
+    assert_eq!(Delta::decode_slice(&buffer).unwrap(), delta);
+    assert_eq!(Delta::decode_slice(&[0x8f]).unwrap(), Delta { x: -8, y: -1 });
+
+    assert!(Delta { x: 8, y: 0 }.encode_vec().is_err());
+    assert!(Delta { x: -9, y: 0 }.encode_vec().is_err());
+}
+
+#[test]
+fn since() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Header {
+        version: u8,
+        len: u8,
+        #[nue(since = "self.version >= 3", default = "0xff")]
+        flags: u8,
+        #[nue(since = "self.version >= 2", cond = "self.len > 0")]
+        data: u8,
+    }
+
+    let header = Header { version: 1, len: 1, flags: 0xff, data: 0 };
+    assert_eq!(header.encode_vec().unwrap(), [1, 1]);
+    assert_eq!(header.encoded_size(), 2);
+    assert_eq!(Header::decode_slice(&[1, 1]).unwrap(), header);
+
+    let header = Header { version: 2, len: 1, flags: 0xff, data: 5 };
+    assert_eq!(header.encode_vec().unwrap(), [2, 1, 5]);
+    assert_eq!(header.encoded_size(), 3);
+    assert_eq!(Header::decode_slice(&[2, 1, 5]).unwrap(), header);
+
+    let header = Header { version: 3, len: 0, flags: 1, data: 0 };
+    assert_eq!(header.encode_vec().unwrap(), [3, 0, 1]);
+    assert_eq!(header.encoded_size(), 3);
+    assert_eq!(Header::decode_slice(&[3, 0, 1]).unwrap(), header);
+}
+
+#[test]
+fn variant_attrs() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    enum Message {
+        #[nue(discriminant = "0x10")]
+        Ping,
+        Pong,
+        #[nue(align = "4")]
+        Data(u32),
+        #[nue(cond = "false")]
+        Reserved(u8),
+    }
+
+    assert_eq!(Message::Ping.encode_vec().unwrap(), [0x10]);
+    assert_eq!(Message::Pong.encode_vec().unwrap(), [0x11]);
+    assert_eq!(Message::decode_slice(&[0x11]).unwrap(), Message::Pong);
+    assert!(Message::decode_slice(&[0]).is_err());
+
+    let buffer = Message::Data(5).encode_vec().unwrap();
+    assert_eq!(buffer, [0x12, 0, 0, 0, 5, 0, 0, 0]);
+    assert_eq!(Message::Data(5).encoded_size(), 8);
+    assert_eq!(Message::decode_slice(&buffer).unwrap(), Message::Data(5));
+
+    assert_eq!(Message::Reserved(7).encode_vec().unwrap(), [0x13]);
+    assert_eq!(Message::Reserved(7).encoded_size(), 1);
+    assert_eq!(Message::decode_slice(&[0x13]).unwrap(), Message::Reserved(0));
+}
+
+#[test]
+fn other() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    enum Record {
+        Empty,
+        Point(u8, u8),
+        #[nue(other)]
+        Unknown(u8, Vec<u8>),
+    }
+
+    assert_eq!(Record::decode_slice(&[1, 2, 3]).unwrap(), Record::Point(2, 3));
+    assert_eq!(Record::decode_slice(&[7]).unwrap(), Record::Unknown(7, vec![]));
+
+    let unknown = Record::Unknown(9, vec![1, 2, 3]);
+    let buffer = unknown.encode_vec().unwrap();
+    assert_eq!(buffer, [9, 1, 2, 3]);
+    assert_eq!(unknown.encoded_size(), 4);
+    assert_eq!(Record::decode_slice(&buffer).unwrap(), unknown);
+    assert_eq!(Record::Empty.encode_vec().unwrap(), [0]);
+}
+
+#[test]
+fn limit_group() {
+    use nue::{EncodedSize, Error};
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Envelope {
+        len: u8,
+        #[nue(limit = "self.len", through = "body")]
+        kind: u8,
+        #[nue(consume = "true")]
+        body: Vec<u8>,
+        trailer: u8,
+    }
+
+    let envelope = Envelope { len: 4, kind: 1, body: vec![2, 3], trailer: 9 };
+    let buffer = envelope.encode_vec().unwrap();
+    assert_eq!(buffer, [4, 1, 2, 3, 0, 9]);
+    assert_eq!(envelope.encoded_size(), 6);
+    assert_eq!(Envelope::decode_slice(&buffer).unwrap(), Envelope { body: vec![2, 3, 0], ..envelope });
+
+    assert!(Envelope { len: 2, ..envelope }.encode_vec().is_err());
+    match Error::from(Envelope::decode_slice(&[4, 1, 2]).unwrap_err()) {
+        Error::Field { type_name: "Envelope", field: "trailer", .. } => (),
+        err => panic!("unexpected {:?}", err),
+    }
+}
+
+#[test]
+fn value() {
+    use nue::EncodedSize;
+
+    #[derive(NueEncode, NueDecode, NueEncodedSize, PartialEq, Debug)]
+    struct Record {
+        #[nue(value = "self.body.len() as u8")]
+        len: u8,
+        #[nue(count = "self.len")]
+        body: Vec<u8>,
+    }
+
+    let record = Record { len: 0, body: vec![1, 2, 3] };
+    let buffer = record.encode_vec().unwrap();
+    assert_eq!(buffer, [3, 1, 2, 3]);
+    assert_eq!(record.encoded_size(), 4);
+    assert_eq!(Record::decode_slice(&buffer).unwrap(), Record { len: 3, ..record });
+}
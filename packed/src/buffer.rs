@@ -0,0 +1,117 @@
+use alloc::alloc::{self, Layout};
+use core::ops::{Deref, DerefMut};
+use core::slice::{from_raw_parts, from_raw_parts_mut};
+use core::mem::align_of;
+use core::fmt;
+
+/// A zero-initialized byte buffer with a guaranteed starting alignment.
+///
+/// Casting a byte buffer to an aligned type can only succeed if the buffer
+/// happens to be aligned; allocating it through `AlignedBuffer` ensures that it is.
+pub struct AlignedBuffer {
+    ptr: *mut u8,
+    len: usize,
+    align: usize,
+}
+
+/// Allocates a zeroed buffer of `len` bytes starting at a multiple of `align`.
+///
+/// # Panics
+///
+/// Panics if `align` is not a power of two.
+pub fn alloc_aligned(len: usize, align: usize) -> AlignedBuffer {
+    AlignedBuffer::new(len, align)
+}
+
+impl AlignedBuffer {
+    /// Allocates a zeroed buffer of `len` bytes starting at a multiple of `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn new(len: usize, align: usize) -> Self {
+        let layout = Layout::from_size_align(len, align).expect("invalid buffer alignment");
+
+        let ptr = if len == 0 {
+            align as *mut u8
+        } else {
+            let ptr = unsafe { alloc::alloc_zeroed(layout) };
+            if ptr.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+            ptr
+        };
+
+        AlignedBuffer {
+            ptr: ptr,
+            len: len,
+            align: align,
+        }
+    }
+
+    /// Allocates a zeroed buffer of `len` bytes suitably aligned for `T`.
+    pub fn for_type<T>(len: usize) -> Self {
+        AlignedBuffer::new(len, align_of::<T>())
+    }
+
+    /// The alignment of the start of the buffer.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        unsafe { from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Clone for AlignedBuffer {
+    fn clone(&self) -> Self {
+        let mut buf = AlignedBuffer::new(self.len, self.align);
+        buf.copy_from_slice(self);
+        buf
+    }
+}
+
+impl fmt::Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe { alloc::dealloc(self.ptr, Layout::from_size_align_unchecked(self.len, self.align)) }
+        }
+    }
+}
+
+unsafe impl Send for AlignedBuffer { }
+unsafe impl Sync for AlignedBuffer { }
+
+#[test]
+fn aligned_buffer() {
+    use {Aligned, Un};
+
+    let mut buf = AlignedBuffer::for_type::<u64>(0x10);
+    assert_eq!(buf.as_ptr() as usize % 8, 0);
+    assert_eq!(buf.len(), 0x10);
+
+    buf[8] = 1;
+    let u = unsafe { &*(buf[8..].as_ptr() as *const Un<u64>) };
+    assert_eq!(u64::as_aligned(u).cloned(), Some(u64::from_le(1)));
+
+    assert_eq!(alloc_aligned(0, 0x1000).len(), 0);
+}
@@ -1,19 +1,77 @@
 #![deny(missing_docs)]
+#![no_std]
 #![cfg_attr(feature = "unstable", feature(optin_builtin_traits))]
 
 //! A safe approach to using `#[repr(packed)]` data.
 //!
 //! See `nue_macros` for the automagic `#[packed]` attribute.
+//!
+//! This crate is `no_std`. `AlignedBuffer` requires an allocator, and is only
+//! available with the `alloc` feature, which is enabled by default.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(test)]
+#[macro_use]
+extern crate std;
+
+use core::mem::{transmute, replace, uninitialized, forget};
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicI8, AtomicU8};
+use core::slice::{from_raw_parts, from_raw_parts_mut};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::cmp::Ordering;
+use core::fmt;
+
+use core::mem::{align_of, size_of};
+
+#[cfg(feature = "alloc")]
+mod buffer;
 
-use std::mem::{transmute, replace, uninitialized, forget};
-use std::marker::PhantomData;
+#[cfg(feature = "alloc")]
+pub use buffer::{AlignedBuffer, alloc_aligned};
 
-use std::mem::align_of;
+#[doc(hidden)]
+pub use core::mem as __mem;
+#[doc(hidden)]
+pub use core::ptr as __ptr;
 
 /// A marker trait indicating that a type has an alignment of `1`.
 ///
 /// In general, only applies to `()`, `bool`, `i8`, `u8`, and any types that
 /// contain only members of these types.
+///
+/// # Safety
+///
+/// With the `unstable` feature this is an auto trait, and every primitive with
+/// a larger alignment is excluded. Types that have no fields but still carry an
+/// alignment, such as `#[repr(u32)]` enums, are not caught by this and must be
+/// opted out manually with a negative impl.
+///
+/// `unsafe_unaligned!`, `unsafe_packed!` and the `#[packed]` attribute assert
+/// that the type and each of its fields actually has an alignment of `1`, so
+/// such an enum fails to compile as a member of a packed type. On stable,
+/// prefer them over a bare impl.
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate packed;
+///
+/// #[repr(u32)]
+/// #[derive(Copy, Clone)]
+/// enum Kind { A, B }
+///
+/// unsafe impl packed::Unaligned for Kind { }
+///
+/// #[repr(C, packed)]
+/// struct Data(u8, Kind);
+///
+/// unsafe_packed!(Data: u8, Kind);
+/// # fn main() { }
+/// ```
 pub unsafe trait Unaligned { }
 
 /// A type alias that represents the unaligned type of `T`.
@@ -22,8 +80,7 @@ pub type Un<T> = <T as Aligned>::Unaligned;
 /// A marker trait indicating that a type has an alignment over `1`,
 /// and is therefore not safe to use in an unaligned context.
 pub unsafe trait Aligned: Sized {
-    /// An unaligned representation of this type. Usually a u8 array of the
-    /// same size.
+    /// An unaligned representation of this type. Usually `UnalignedBytes<Self>`.
     type Unaligned: Unaligned + Sized + Copy;
 
     /// Determines whether an unaligned representation of this type is aligned.
@@ -97,9 +154,24 @@ pub unsafe trait Aligned: Sized {
     /// Copies a value from its unaligned representation.
     #[inline]
     unsafe fn from_unaligned(u: Self::Unaligned) -> Self {
-        let mut s: Self = uninitialized();
-        forget(replace(s.as_unaligned_mut(), u));
-        s
+        Self::read_unaligned(&u)
+    }
+
+    /// Reads a value from a pointer to its unaligned representation.
+    ///
+    /// The pointer does not need to be aligned, but must otherwise be valid for reads.
+    #[inline]
+    unsafe fn read_unaligned(ptr: *const Self::Unaligned) -> Self {
+        ptr::read_unaligned(ptr as *const Self)
+    }
+
+    /// Writes a value to a pointer to its unaligned representation, without
+    /// dropping the old value.
+    ///
+    /// The pointer does not need to be aligned, but must otherwise be valid for writes.
+    #[inline]
+    unsafe fn write_unaligned(ptr: *mut Self::Unaligned, value: Self) {
+        ptr::write_unaligned(ptr as *mut Self, value)
     }
 
     #[doc(hidden)]
@@ -110,11 +182,334 @@ pub unsafe trait Aligned: Sized {
 ///
 /// This means that all its members are packed or have an alignment of `1`,
 /// and its memory layout is guaranteed to be in member declaration order.
-pub unsafe trait Packed: Unaligned {
+pub unsafe trait Packed: Unaligned + Sized {
+    /// The size of the type in bytes.
+    ///
+    /// Since packed types contain no padding, this is also the exact size of
+    /// its encoded representation.
+    const SIZE: usize = size_of::<Self>();
+
     #[doc(hidden)]
     fn __assert_unaligned() { }
 }
 
+/// A marker trait indicating that a type is `#[repr(packed(N))]`, where `N` is `ALIGN`.
+///
+/// All its members have an alignment of at most `ALIGN`, so they may be safely
+/// borrowed, and its memory layout is guaranteed to be in member declaration order.
+/// Unlike fully packed types, partially packed types may still contain padding.
+///
+/// All `Packed` types are `PackedAlign` with an `ALIGN` of `1`.
+pub unsafe trait PackedAlign: Sized {
+    /// The maximum alignment of the type and its members.
+    const ALIGN: usize;
+}
+
+unsafe impl<T: Packed> PackedAlign for T {
+    const ALIGN: usize = 1;
+}
+
+/// The unaligned byte representation of `T`.
+///
+/// It has the same size as `T`, with an alignment of `1`. Use it as the
+/// `Aligned::Unaligned` type of your own types rather than spelling out a byte
+/// array of the correct size.
+///
+/// It is only `Packed`, and its bytes can only be borrowed, for primitive types
+/// that are known to contain no padding.
+///
+/// ```
+/// use packed::{Aligned, Un, UnalignedBytes};
+///
+/// #[derive(Copy, Clone)]
+/// struct Data(u32, u16, u16);
+///
+/// unsafe impl Aligned for Data {
+///     type Unaligned = UnalignedBytes<Data>;
+/// }
+///
+/// let data = Data(1, 2, 3).unaligned();
+/// assert_eq!(data.get().1, 2);
+/// assert_eq!(std::mem::size_of::<Un<Data>>(), std::mem::size_of::<Data>());
+/// assert_eq!(std::mem::align_of::<Un<Data>>(), 1);
+/// ```
+#[repr(C, packed)]
+pub struct UnalignedBytes<T>(T);
+
+impl<T> UnalignedBytes<T> {
+    /// Creates the unaligned representation of a value.
+    #[inline]
+    pub fn new(v: T) -> Self {
+        UnalignedBytes(v)
+    }
+
+    /// Copies the value out of its unaligned representation.
+    #[inline]
+    pub fn get(&self) -> T where T: Copy {
+        self.0
+    }
+
+    /// Replaces the value.
+    #[inline]
+    pub fn set(&mut self, v: T) where T: Copy {
+        self.0 = v
+    }
+
+    /// Borrows the underlying bytes of the value.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] where Self: Packed {
+        unsafe { from_raw_parts(self as *const _ as *const u8, size_of::<T>()) }
+    }
+
+    /// Mutably borrows the underlying bytes of the value.
+    ///
+    /// Unsafe because not every byte pattern is a valid `T`.
+    #[inline]
+    pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+        from_raw_parts_mut(self as *mut _ as *mut u8, size_of::<T>())
+    }
+}
+
+impl<T> Deref for UnalignedBytes<T> where Self: Packed {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<T: Copy> Clone for UnalignedBytes<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy> Copy for UnalignedBytes<T> { }
+
+impl<T: Copy + Default> Default for UnalignedBytes<T> {
+    #[inline]
+    fn default() -> Self {
+        UnalignedBytes(T::default())
+    }
+}
+
+impl<T> From<T> for UnalignedBytes<T> {
+    #[inline]
+    fn from(v: T) -> Self {
+        UnalignedBytes(v)
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for UnalignedBytes<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+impl<T: Copy + PartialEq> PartialEq for UnalignedBytes<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Copy + Eq> Eq for UnalignedBytes<T> { }
+
+impl<T: Copy + PartialOrd> PartialOrd for UnalignedBytes<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.get().partial_cmp(&other.get())
+    }
+}
+
+impl<T: Copy + Ord> Ord for UnalignedBytes<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.get().cmp(&other.get())
+    }
+}
+
+impl<T: Copy + Hash> Hash for UnalignedBytes<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        self.get().hash(h)
+    }
+}
+
+unsafe impl<T> Unaligned for UnalignedBytes<T> { }
+
+/// Implements `Unaligned` for a type, asserting that it has an alignment of `1`
+/// and that the provided field types are also `Unaligned`.
+///
+/// A stable alternative to writing the `unsafe impl` by hand. All fields of the
+/// type must be listed.
+///
+/// ```
+/// #[macro_use]
+/// extern crate packed;
+///
+/// #[repr(C)]
+/// struct Data(u8, bool);
+///
+/// unsafe_unaligned!(Data: u8, bool);
+/// # fn main() { }
+/// ```
+#[macro_export]
+macro_rules! unsafe_unaligned {
+    ($t:ty: $($f:ty),*) => {
+        unsafe impl $crate::Unaligned for $t { }
+
+        const _: () = {
+            assert!($crate::__mem::align_of::<$t>() == 1, concat!(stringify!($t), " is not unaligned"));
+            $(assert!($crate::__mem::align_of::<$f>() == 1, concat!(stringify!($f), " is not unaligned"));)*
+
+            #[allow(dead_code)]
+            fn __assert_unaligned() {
+                fn assert<T: $crate::Unaligned>() { }
+
+                $(assert::<$f>();)*
+            }
+        };
+    };
+}
+
+/// Implements `Packed` and `Unaligned` for a type, asserting that the provided
+/// field types are `Unaligned` and that their sizes add up to the size of the type.
+///
+/// The type must be `#[repr(C)]` or `#[repr(packed)]`, and all fields must be
+/// listed in declaration order.
+///
+/// Partially packed types may instead be marked as `PackedAlign` by prefixing
+/// the type with `packed(N)`, which asserts that no member has an alignment
+/// larger than `N`.
+///
+/// Unions are marked by prefixing the type with `union`, which instead asserts
+/// that every member is as large as the union itself.
+///
+/// ```
+/// #[macro_use]
+/// extern crate packed;
+/// use packed::{Un, PackedAlign};
+///
+/// #[repr(C)]
+/// struct Data(u8, Un<u32>);
+///
+/// unsafe_packed!(Data: u8, Un<u32>);
+///
+/// #[repr(C, packed(2))]
+/// struct Data2(u16, u8, Un<u32>);
+///
+/// unsafe_packed!(packed(2) Data2: u16, u8, Un<u32>);
+///
+/// #[repr(C)]
+/// union Overlay {
+///     bytes: [u8; 4],
+///     value: Un<u32>,
+/// }
+///
+/// unsafe_packed!(union Overlay: [u8; 4], Un<u32>);
+///
+/// # fn main() {
+/// assert_eq!(Data2::ALIGN, 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! unsafe_packed {
+    (union $t:ty: $($f:ty),+) => {
+        unsafe impl $crate::Unaligned for $t { }
+
+        const _: () = {
+            assert!($crate::__mem::align_of::<$t>() == 1, concat!(stringify!($t), " is not packed"));
+            $(assert!($crate::__mem::align_of::<$f>() == 1, concat!(stringify!($f), " is not unaligned"));)+
+            $(assert!($crate::__mem::size_of::<$f>() == $crate::__mem::size_of::<$t>(), concat!(stringify!($f), " does not fill ", stringify!($t)));)+
+        };
+
+        unsafe impl $crate::Packed for $t {
+            fn __assert_unaligned() {
+                fn assert<T: $crate::Unaligned>() { }
+
+                $(assert::<$f>();)+
+            }
+        }
+    };
+    (packed($n:expr) $t:ty: $($f:ty),*) => {
+        const _: () = {
+            assert!($crate::__mem::align_of::<$t>() <= $n, concat!(stringify!($t), " is not packed"));
+            $(assert!($crate::__mem::align_of::<$f>() <= $n, concat!(stringify!($f), " is not packed"));)*
+        };
+
+        unsafe impl $crate::PackedAlign for $t {
+            const ALIGN: usize = $n;
+        }
+    };
+    ($t:ty: $($f:ty),*) => {
+        unsafe impl $crate::Unaligned for $t { }
+
+        const _: () = {
+            assert!($crate::__mem::align_of::<$t>() == 1, concat!(stringify!($t), " is not packed"));
+            $(assert!($crate::__mem::align_of::<$f>() == 1, concat!(stringify!($f), " is not unaligned"));)*
+        };
+
+        unsafe impl $crate::Packed for $t {
+            fn __assert_unaligned() {
+                fn assert<T: $crate::Unaligned>() { }
+
+                $(assert::<$f>();)*
+
+                let _ = $crate::__mem::transmute::<$t, [u8; 0 $(+ $crate::__mem::size_of::<$f>())*]>;
+            }
+        }
+    };
+}
+
+/// Copies a field out of, or assigns a field into, a packed struct.
+///
+/// Borrowing a field of a `#[repr(packed)]` struct may create an unaligned
+/// reference, which is undefined behaviour. This macro instead accesses the
+/// field through an unaligned pointer read or write. The field type must be `Copy`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate packed;
+///
+/// #[repr(C, packed)]
+/// struct Data {
+///     flag: u8,
+///     value: u32,
+/// }
+///
+/// # fn main() {
+/// let mut data = Data { flag: 0, value: 1 };
+/// assert_eq!(packed_field!(data.value), 1);
+///
+/// packed_field!(data.value = 5);
+/// assert_eq!(packed_field!(data.value), 5);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! packed_field {
+    ($base:ident $(. $field:tt)+ = $value:expr) => {
+        unsafe { $crate::__write_field($crate::__ptr::addr_of_mut!($base $(. $field)+), $value) }
+    };
+    ($base:ident $(. $field:tt)+) => {
+        unsafe { $crate::__read_field($crate::__ptr::addr_of!($base $(. $field)+)) }
+    };
+}
+
+#[doc(hidden)]
+#[inline]
+pub unsafe fn __read_field<T: Copy>(ptr: *const T) -> T {
+    ptr::read_unaligned(ptr)
+}
+
+#[doc(hidden)]
+#[inline]
+pub unsafe fn __write_field<T: Copy>(ptr: *mut T, value: T) {
+    ptr::write_unaligned(ptr, value)
+}
+
 #[cfg(feature = "unstable")]
 mod impls {
     use super::Unaligned;
@@ -133,10 +528,29 @@ mod impls {
     impl !Unaligned for u64 { }
     impl !Unaligned for isize { }
     impl !Unaligned for usize { }
-    impl<T> !Unaligned for *const T { }
-    impl<T> !Unaligned for *mut T { }
-    impl<'a, T> !Unaligned for &'a T { }
-    impl<'a, T> !Unaligned for &'a mut T { }
+    impl !Unaligned for i128 { }
+    impl !Unaligned for u128 { }
+    impl<T: ?Sized> !Unaligned for *const T { }
+    impl<T: ?Sized> !Unaligned for *mut T { }
+    impl<'a, T: ?Sized> !Unaligned for &'a T { }
+    impl<'a, T: ?Sized> !Unaligned for &'a mut T { }
+
+    macro_rules! fn_impl {
+        ($($x:ident),*) => {
+            impl<R, $($x),*> !Unaligned for fn($($x),*) -> R { }
+            impl<R, $($x),*> !Unaligned for extern "C" fn($($x),*) -> R { }
+            impl<R, $($x),*> !Unaligned for unsafe fn($($x),*) -> R { }
+            impl<R, $($x),*> !Unaligned for unsafe extern "C" fn($($x),*) -> R { }
+        };
+    }
+
+    fn_impl! { }
+    fn_impl! { A }
+    fn_impl! { A, B }
+    fn_impl! { A, B, C }
+    fn_impl! { A, B, C, D }
+    fn_impl! { A, B, C, D, E }
+    fn_impl! { A, B, C, D, E, F }
 }
 
 #[cfg(not(feature = "unstable"))]
@@ -150,14 +564,10 @@ mod impls {
 }
 
 unsafe impl<T> Unaligned for PhantomData<T> { }
-
-macro_rules! aligned_assert {
-    ($t:ident) => {
-        unsafe fn __assert_unaligned() {
-            ::std::mem::forget(::std::mem::transmute::<$t, $t::Unaligned>(::std::mem::uninitialized()));
-        }
-    };
-}
+// Atomics aren't `Aligned`, as a plain value can't stand in for their interior mutability
+unsafe impl Unaligned for AtomicBool { }
+unsafe impl Unaligned for AtomicI8 { }
+unsafe impl Unaligned for AtomicU8 { }
 
 macro_rules! aligned_self {
     ($t:ty) => {
@@ -173,30 +583,31 @@ macro_rules! aligned_self {
 }
 
 macro_rules! aligned_impl {
-    ($t:ident: $s:expr) => {
-        unsafe impl Aligned for $t {
-            type Unaligned = [u8; $s];
-
-            aligned_assert!(Self);
-        }
-    };
-    ($($t:ident: $e:expr),*) => {
+    ($($t:ty),*) => {
         $(
-            aligned_impl!($t: $e);
+            unsafe impl Aligned for $t {
+                type Unaligned = UnalignedBytes<$t>;
+            }
+
+            unsafe impl Packed for UnalignedBytes<$t> { }
         )*
     };
 }
 
 aligned_impl! {
-    char: 4,
-    f32: 4,
-    f64: 8,
-    i16: 2,
-    u16: 2,
-    i32: 4,
-    u32: 4,
-    i64: 8,
-    u64: 8
+    char,
+    f32,
+    f64,
+    i16,
+    u16,
+    i32,
+    u32,
+    i64,
+    u64,
+    i128,
+    u128,
+    isize,
+    usize
 }
 
 aligned_self! {
@@ -206,25 +617,13 @@ aligned_self! {
     bool
 }
 
-#[cfg(target_pointer_width = "32")]
-mod impl32 {
-    use super::Aligned;
-    aligned_impl! { isize: 4, usize: 4 }
-    unsafe impl<T: Sized> Aligned for *const T { type Unaligned = [u8; 4]; aligned_assert!(Self); }
-    unsafe impl<T: Sized> Aligned for *mut T { type Unaligned = [u8; 4]; aligned_assert!(Self); }
-    unsafe impl<'a, T: Sized> Aligned for &'a T { type Unaligned = [u8; 4]; }
-    unsafe impl<'a, T: Sized> Aligned for &'a mut T { type Unaligned = [u8; 4]; }
-}
+unsafe impl<T> Packed for UnalignedBytes<*const T> { }
+unsafe impl<T> Packed for UnalignedBytes<*mut T> { }
 
-#[cfg(target_pointer_width = "64")]
-mod impl64 {
-    use super::Aligned;
-    aligned_impl! { isize: 8, usize: 8 }
-    unsafe impl<T: Sized> Aligned for *const T { type Unaligned = [u8; 8]; aligned_assert!(Self); }
-    unsafe impl<T: Sized> Aligned for *mut T { type Unaligned = [u8; 8]; aligned_assert!(Self); }
-    unsafe impl<'a, T: Sized> Aligned for &'a T { type Unaligned = [u8; 8]; }
-    unsafe impl<'a, T: Sized> Aligned for &'a mut T { type Unaligned = [u8; 8]; }
-}
+unsafe impl<T: Sized> Aligned for *const T { type Unaligned = UnalignedBytes<*const T>; }
+unsafe impl<T: Sized> Aligned for *mut T { type Unaligned = UnalignedBytes<*mut T>; }
+unsafe impl<'a, T: Sized> Aligned for &'a T { type Unaligned = UnalignedBytes<*const T>; }
+unsafe impl<'a, T: Sized> Aligned for &'a mut T { type Unaligned = UnalignedBytes<*mut T>; }
 
 //unsafe impl<T: Unaligned> Aligned for T { type Unaligned = T; }
 
@@ -233,6 +632,9 @@ unsafe impl Packed for i8 { }
 unsafe impl Packed for u8 { }
 unsafe impl Packed for bool { }
 unsafe impl<T> Packed for PhantomData<T> { }
+unsafe impl Packed for AtomicBool { }
+unsafe impl Packed for AtomicI8 { }
+unsafe impl Packed for AtomicU8 { }
 
 macro_rules! packed_def {
     (=> $($($x:ident),*;)*) => {
@@ -264,6 +666,22 @@ unsafe impl<T> Aligned for [T; 0] {
     type Unaligned = ();
 }
 
+macro_rules! aligned_array {
+    ($($x:expr),*) => {
+        $(
+            unsafe impl<T: Aligned> Aligned for [T; $x] {
+                type Unaligned = [T::Unaligned; $x];
+            }
+        )*
+    };
+}
+
+aligned_array! { 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f }
+aligned_array! { 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f }
+aligned_array! { 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f }
+aligned_array! { 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f }
+aligned_array! { 0x40 }
+
 packed_def! { 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f }
 packed_def! { 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f }
 packed_def! { 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f }
@@ -292,5 +710,61 @@ fn assert_packed() {
     is::<u8>();
     is::<i8>();
     is::<bool>();
+    is::<AtomicBool>();
     is_unaligned::<(bool, u8)>();
+
+    assert_eq!(<(bool, u8)>::SIZE, 2);
+    assert_eq!(<[Un<u32>; 4]>::SIZE, 16);
+}
+
+#[test]
+fn packed_union() {
+    #[repr(C)]
+    #[allow(dead_code)]
+    union Overlay {
+        bytes: [u8; 2],
+        value: Un<u16>,
+    }
+
+    unsafe_packed!(union Overlay: [u8; 2], Un<u16>);
+
+    fn is<T: Packed>() { }
+    is::<Overlay>();
+
+    let overlay = Overlay { value: 0x0102u16.unaligned() };
+    assert_eq!(unsafe { overlay.bytes }, 0x0102u16.to_ne_bytes());
+}
+
+#[test]
+fn unaligned_ptr() {
+    let mut data = [0u8; 5];
+    unsafe {
+        let ptr = data[1..].as_mut_ptr() as *mut Un<u32>;
+        u32::write_unaligned(ptr, 0x01020304);
+        assert_eq!(u32::read_unaligned(ptr), 0x01020304);
+    }
+    assert_eq!(data[0], 0);
+}
+
+#[test]
+fn unaligned_bytes() {
+    let a = 5u32.unaligned();
+    let b = UnalignedBytes::new(6u32);
+
+    assert_eq!(format!("{:?}", a), "5");
+    assert!(a < b);
+    assert_eq!(a, UnalignedBytes::from(5));
+    assert_eq!(b.get(), 6);
+    assert_eq!(a.as_bytes(), 5u32.to_ne_bytes());
+    assert_eq!(<Un<u32>>::SIZE, 4);
+}
+
+#[test]
+fn aligned_array() {
+    let table: Un<[u32; 4]> = [1u32, 2, 3, 4].unaligned();
+
+    assert_eq!(size_of::<Un<[u32; 4]>>(), 16);
+    assert_eq!(align_of::<Un<[u32; 4]>>(), 1);
+    assert_eq!(table[2].get(), 3);
+    assert_eq!(unsafe { <[u32; 4]>::from_unaligned(table) }, [1, 2, 3, 4]);
 }
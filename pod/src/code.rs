@@ -1,9 +1,12 @@
 use std::io::{self, Read, Write, BufReader, BufRead, Cursor};
 use std::ffi::{CString, CStr};
-use ::Pod;
+use std::mem::{size_of, MaybeUninit};
+use std::slice::{from_raw_parts, from_raw_parts_mut};
+use std::str;
+use packed::Unaligned;
+use ::CheckedPod;
 
-use uninitialized::UNINITIALIZED;
-use nue_io::ReadExactExt;
+use nue_io::{ReadExactExt, Error};
 
 /// Encodes an value's binary representation to a `Write`.
 ///
@@ -33,7 +36,8 @@ use nue_io::ReadExactExt;
 /// ## `Pod`
 ///
 /// `Pod` types are encoded as their raw in-memory representation. Use `EndianPrimitive` members to
-/// control the byte order.
+/// control the byte order. `CheckedPod` types such as enums are coded the same way, but decoding
+/// fails if the bytes aren't valid according to `CheckedPod::valid_bytes`.
 pub trait Encode {
     /// Options that may be provided to affect how the value is encoded
     type Options: Default;
@@ -65,6 +69,15 @@ pub trait Encode {
     }
 }
 
+/// Computes the number of bytes a value encodes to, without encoding it.
+pub trait EncodedSize: Encode {
+    /// Returns the size encoded with default options
+    fn encoded_size(&self) -> u64 { self.encoded_size_options(Default::default()) }
+
+    /// Returns the size encoded with the provided options
+    fn encoded_size_options(&self, _options: Self::Options) -> u64 { self.encoded_size() }
+}
+
 /// Decodes data from a `Read` into a new value.
 ///
 /// See `Encode` for more details.
@@ -102,6 +115,59 @@ pub trait Decode: Sized {
     fn validate(&self) -> io::Result<()> { Ok(()) }
 }
 
+/// Decodes a value that borrows from a byte slice, rather than copying out of it.
+///
+/// The slice is advanced past the bytes that were decoded.
+pub trait DecodeRef<'a>: Sized {
+    /// Options will affect how the value is decoded
+    type Options: Default;
+
+    /// Decodes from the start of the slice with default options
+    fn decode_ref(data: &mut &'a [u8]) -> io::Result<Self> { Self::decode_ref_options(data, Default::default()) }
+
+    /// Decodes from the start of the slice with the provided options
+    fn decode_ref_options(data: &mut &'a [u8], _options: Self::Options) -> io::Result<Self> { Self::decode_ref(data) }
+}
+
+/// Splits `len` bytes off of the start of `data`.
+fn split_ref<'a>(data: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(Error::UnexpectedEof { needed: Some((len - data.len()) as u64) }.into())
+    }
+
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+/// An extension for decoding values directly from a `Read`.
+pub trait ReadPodExt: Read + Sized {
+    /// Decodes a value with default options
+    fn read_pod<T: Decode>(&mut self) -> io::Result<T> {
+        T::decode(self)
+    }
+
+    /// Decodes `count` consecutive values with default options
+    fn read_pod_vec<T: Decode>(&mut self, count: usize) -> io::Result<Vec<T>> where T::Options: Clone {
+        Vec::decode_options(self, VecDecodeOptions {
+            len: Some(count),
+            options: Default::default(),
+        })
+    }
+}
+
+impl<R: Read> ReadPodExt for R { }
+
+/// An extension for encoding values directly to a `Write`.
+pub trait WritePodExt: Write + Sized {
+    /// Encodes a value with default options
+    fn write_pod<T: Encode + ?Sized>(&mut self, value: &T) -> io::Result<()> {
+        value.encode(self)
+    }
+}
+
+impl<W: Write> WritePodExt for W { }
+
 impl<T: Encode> Encode for Option<T> {
     type Options = T::Options;
 
@@ -126,23 +192,28 @@ impl<T: Decode> Decode for Option<T> {
     }
 }
 
-impl<T: Pod> Encode for T {
+impl<T: CheckedPod> Encode for T {
     type Options = ();
 
     fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
-        w.write_all(self.as_slice())
+        w.write_all(unsafe { from_raw_parts(self as *const T as *const u8, size_of::<T>()) })
     }
 }
 
-impl<T: Pod> Decode for T {
+impl<T: CheckedPod> Decode for T {
     type Options = ();
 
     fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
-        // TODO: Would be nice if we could use [0u8; size_of::<T>()]
-        let mut pod: Self = Pod::zeroed();
-
-        try!(r.read_exact(pod.mut_slice()));
-        Ok(pod)
+        // The bytes aren't a `Self` until they've been validated
+        let mut pod = MaybeUninit::<Self>::zeroed();
+        let bytes = unsafe { from_raw_parts_mut(pod.as_mut_ptr() as *mut u8, size_of::<Self>()) };
+
+        let len = bytes.len();
+        match try!(r.read_exact_eof(bytes)) {
+            read if read < len => Err(Error::UnexpectedEof { needed: Some((len - read) as u64) }.into()),
+            _ if !T::valid_bytes(bytes) => Err(Error::Validation { field: None, message: "invalid bit pattern".to_owned() }.into()),
+            _ => Ok(unsafe { pod.assume_init() }),
+        }
     }
 }
 
@@ -151,15 +222,8 @@ impl Decode for String {
 
     fn decode_options<R: Read>(r: &mut R, options: Self::Options) -> io::Result<Self> {
         if let Some(len) = options.len {
-            let mut vec = if UNINITIALIZED {
-                let mut vec = Vec::with_capacity(len);
-                unsafe { vec.set_len(len); }
-                vec
-            } else {
-                vec![0; len]
-            };
-            try!(r.read_exact(&mut vec[..]));
-            String::from_utf8(vec).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+            let vec = try!(r.read_vec_exact(len));
+            String::from_utf8(vec).map_err(|e| Error::Validation { field: None, message: e.to_string() }.into())
         } else {
             let mut string = String::new();
             try!(r.read_to_string(&mut string));
@@ -196,20 +260,18 @@ impl Decode for CString {
     type Options = CStringDecodeOptions;
 
     fn decode_options<R: Read>(r: &mut R, options: Self::Options) -> io::Result<Self> {
-        if options.require_nul {
-            unimplemented!()
+        let mut buf = Vec::new();
+        for c in r.bytes() {
+            match try!(c) {
+                0 => return Ok(unsafe { CString::from_vec_unchecked(buf) }),
+                c => buf.push(c),
+            }
         }
 
-        unsafe {
-            let mut err = Ok(());
-            let string = CString::from_vec_unchecked(r.bytes().map(|c| match c {
-                Ok(c) => c,
-                Err(e) => {
-                    err = Err(e);
-                    0
-                },
-            }).take_while(|&c| c > 0).collect());
-            err.map(|_| string)
+        if options.require_nul {
+            Err(Error::UnexpectedEof { needed: Some(1) }.into())
+        } else {
+            Ok(unsafe { CString::from_vec_unchecked(buf) })
         }
     }
 }
@@ -302,6 +364,53 @@ impl<'a, T: Encode> Encode for &'a [T] where T::Options: Clone {
     }
 }
 
+impl<'a, T: CheckedPod + Unaligned> DecodeRef<'a> for &'a T {
+    type Options = ();
+
+    fn decode_ref(data: &mut &'a [u8]) -> io::Result<Self> {
+        let bytes = try!(split_ref(data, size_of::<T>()));
+        match T::try_from_slice(bytes) {
+            Some(value) => Ok(value),
+            None => Err(Error::Validation { field: None, message: "invalid bit pattern".to_owned() }.into()),
+        }
+    }
+}
+
+impl<'a, T: CheckedPod + Unaligned> DecodeRef<'a> for &'a [T] {
+    type Options = VecDecodeOptions<()>;
+
+    fn decode_ref_options(data: &mut &'a [u8], options: Self::Options) -> io::Result<Self> {
+        let len = match options.len {
+            Some(len) => match len.checked_mul(size_of::<T>()) {
+                Some(len) => len,
+                None => return Err(Error::Validation { field: None, message: format!("{} items overflow the length of a slice", len) }.into()),
+            },
+            None if size_of::<T>() == 0 || data.len() % size_of::<T>() == 0 => data.len(),
+            None => return Err(Error::Validation { field: None, message: "trailing bytes do not fill an item".to_owned() }.into()),
+        };
+        let bytes = try!(split_ref(data, len));
+        let count = match size_of::<T>() {
+            0 => options.len.unwrap_or(0),
+            size => bytes.len() / size,
+        };
+        if size_of::<T>() == 0 || bytes.chunks(size_of::<T>()).all(T::valid_bytes) {
+            Ok(unsafe { from_raw_parts(bytes.as_ptr() as *const T, count) })
+        } else {
+            Err(Error::Validation { field: None, message: "invalid bit pattern".to_owned() }.into())
+        }
+    }
+}
+
+impl<'a> DecodeRef<'a> for &'a str {
+    type Options = StringDecodeOptions;
+
+    fn decode_ref_options(data: &mut &'a [u8], options: Self::Options) -> io::Result<Self> {
+        let len = options.len.unwrap_or(data.len());
+        let bytes = try!(split_ref(data, len));
+        str::from_utf8(bytes).map_err(|e| Error::Validation { field: None, message: e.to_string() }.into())
+    }
+}
+
 /// Describes how to decode a `Vec<T>`
 #[derive(Clone, Default, Debug)]
 pub struct VecDecodeOptions<T> {
@@ -325,3 +434,163 @@ pub struct CStringDecodeOptions {
     /// When true, errors if EOF is reached before a nul byte is found
     pub require_nul: bool,
 }
+
+/// Describes how to code the discriminant tag of a derived enum
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TagOptions<T> {
+    /// A tag stored outside of the value, such as in an earlier field of a parent struct.
+    ///
+    /// When `Some`, the tag is neither written nor read. Decoding produces the variant
+    /// it selects, and encoding fails if the value is a different variant.
+    pub tag: Option<T>,
+}
+
+impl<T: EncodedSize> EncodedSize for Option<T> {
+    fn encoded_size(&self) -> u64 {
+        self.as_ref().map(|v| v.encoded_size()).unwrap_or(0)
+    }
+
+    fn encoded_size_options(&self, options: Self::Options) -> u64 {
+        self.as_ref().map(|v| v.encoded_size_options(options)).unwrap_or(0)
+    }
+}
+
+impl<T: CheckedPod> EncodedSize for T {
+    fn encoded_size(&self) -> u64 {
+        size_of::<T>() as u64
+    }
+}
+
+impl EncodedSize for String {
+    fn encoded_size(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl EncodedSize for str {
+    fn encoded_size(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<'a> EncodedSize for &'a str {
+    fn encoded_size(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl EncodedSize for CString {
+    fn encoded_size(&self) -> u64 {
+        self.as_bytes_with_nul().len() as u64
+    }
+}
+
+impl EncodedSize for CStr {
+    fn encoded_size(&self) -> u64 {
+        self.to_bytes_with_nul().len() as u64
+    }
+}
+
+impl<'a> EncodedSize for &'a CStr {
+    fn encoded_size(&self) -> u64 {
+        (*self).encoded_size()
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for Vec<T> where T::Options: Clone {
+    fn encoded_size(&self) -> u64 {
+        (**self).encoded_size()
+    }
+
+    fn encoded_size_options(&self, options: Self::Options) -> u64 {
+        (**self).encoded_size_options(options)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for [T] where T::Options: Clone {
+    fn encoded_size(&self) -> u64 {
+        self.iter().map(|v| v.encoded_size()).sum()
+    }
+
+    fn encoded_size_options(&self, options: Self::Options) -> u64 {
+        self.iter().map(|v| v.encoded_size_options(options.clone())).sum()
+    }
+}
+
+impl<'a, T: EncodedSize> EncodedSize for &'a [T] where T::Options: Clone {
+    fn encoded_size(&self) -> u64 {
+        (*self).encoded_size()
+    }
+
+    fn encoded_size_options(&self, options: Self::Options) -> u64 {
+        (*self).encoded_size_options(options)
+    }
+}
+
+#[test]
+fn encoded_size() {
+    use std::ffi::CString;
+
+    let values = (0x1234u16, "nue", CString::new("nue").unwrap(), vec![1u32, 2], Some(1u8), None::<u64>);
+    assert_eq!(values.0.encoded_size(), values.0.encode_vec().unwrap().len() as u64);
+    assert_eq!(values.1.encoded_size(), values.1.encode_vec().unwrap().len() as u64);
+    assert_eq!(values.2.encoded_size(), values.2.encode_vec().unwrap().len() as u64);
+    assert_eq!(values.3.encoded_size(), values.3.encode_vec().unwrap().len() as u64);
+    assert_eq!(values.4.encoded_size(), 1);
+    assert_eq!(values.5.encoded_size(), 0);
+}
+
+#[test]
+fn valid_bytes() {
+    #[repr(u8)]
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    enum Kind {
+        A = 1,
+        B = 2,
+    }
+
+    unsafe impl CheckedPod for Kind {
+        fn valid_bytes(bytes: &[u8]) -> bool {
+            bytes[0] == Kind::A as u8 || bytes[0] == Kind::B as u8
+        }
+    }
+    unsafe impl Unaligned for Kind { }
+
+    assert_eq!(Kind::decode_slice(&[2]).unwrap(), Kind::B);
+    assert_eq!(Kind::decode_slice(&[3]).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(Kind::A.encode_vec().unwrap(), [1]);
+    assert_eq!(Kind::try_copy_from(&[1]), Some(Kind::A));
+    assert_eq!(Kind::try_copy_from(&[0]), None);
+    assert_eq!(<&[Kind]>::decode_ref(&mut &[1, 2][..]).unwrap(), [Kind::A, Kind::B]);
+    assert!(<&[Kind]>::decode_ref(&mut &[1, 0][..]).is_err());
+}
+
+#[test]
+fn decode_ref() {
+    use endian::Be;
+
+    let data = [0u8, 2, b'h', b'i', 1, 2, 3, 4];
+    let mut slice = &data[..];
+    let len: &Be<u16> = DecodeRef::decode_ref(&mut slice).unwrap();
+    let name: &str = DecodeRef::decode_ref_options(&mut slice, StringDecodeOptions { len: Some(len.get() as usize) }).unwrap();
+    let rest: &[u8] = DecodeRef::decode_ref(&mut slice).unwrap();
+    assert_eq!(name, "hi");
+    assert_eq!(rest, [1, 2, 3, 4]);
+    assert_eq!(rest.as_ptr(), data[4..].as_ptr());
+    assert!(slice.is_empty());
+
+    let mut short = &data[..1];
+    assert_eq!(<&Be<u16>>::decode_ref(&mut short).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    let mut odd = &data[..3];
+    assert!(<&[Be<u16>]>::decode_ref(&mut odd).is_err());
+    let options = VecDecodeOptions { len: Some(usize::max_value() / 2 + 1), options: () };
+    assert_eq!(<&[Be<u16>]>::decode_ref_options(&mut odd, options).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn cstring() {
+    let options = CStringDecodeOptions { require_nul: true };
+    assert_eq!(CString::decode_slice(b"nue").unwrap().as_bytes(), b"nue");
+    assert_eq!(CString::decode_slice_options(b"nue\0rest", options).unwrap().as_bytes(), b"nue");
+    assert_eq!(CString::decode_slice_options(b"nue", options).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -16,6 +16,15 @@ pub type Be<T> = EndianPrimitive<BigEndian, T>;
 /// A type alias for unaligned native endian primitives
 pub type Native<T> = EndianPrimitive<NativeEndian, T>;
 
+/// A byte order selected at runtime, such as from a byte order mark
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Endianness {
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
 /// A POD container for a primitive that stores a value in the specified endianness
 /// in memory, and transforms on `get`/`set`
 #[repr(C)]
@@ -145,7 +154,7 @@ macro_rules! endian_impl {
             #[inline]
             fn to<B: ByteOrder>(self) -> Self::Unaligned {
                 let mut s: Self::Unaligned = unsafe { uninitialized() };
-                B::$w(&mut s, self);
+                B::$w(unsafe { s.as_bytes_mut() }, self);
                 s
             }
         }
@@ -161,6 +170,37 @@ endian_impl!(u64: 8 => read_u64, write_u64);
 endian_impl!(f32: 4 => read_f32, write_f32);
 endian_impl!(f64: 8 => read_f64, write_f64);
 
+fn is_big_endian<B: ByteOrder>() -> bool {
+    B::read_u16(&[0, 1]) == 1
+}
+
+macro_rules! endian_impl_128 {
+    ($t:ty) => {
+        impl EndianConvert for $t {
+            #[inline]
+            fn from<B: ByteOrder>(s: &Self::Unaligned) -> Self {
+                let (hi, lo) = if is_big_endian::<B>() { (&s[..8], &s[8..]) } else { (&s[8..], &s[..8]) };
+                ((B::read_u64(hi) as u128) << 64 | B::read_u64(lo) as u128) as $t
+            }
+
+            #[inline]
+            fn to<B: ByteOrder>(self) -> Self::Unaligned {
+                let mut s: Self::Unaligned = unsafe { uninitialized() };
+                {
+                    let (hi, lo) = unsafe { s.as_bytes_mut() }.split_at_mut(8);
+                    let (hi, lo) = if is_big_endian::<B>() { (hi, lo) } else { (lo, hi) };
+                    B::write_u64(hi, (self as u128 >> 64) as u64);
+                    B::write_u64(lo, self as u64);
+                }
+                s
+            }
+        }
+    };
+}
+
+endian_impl_128!(u128);
+endian_impl_128!(i128);
+
 impl EndianConvert for bool {
     #[inline]
     fn from<B: ByteOrder>(s: &Self::Unaligned) -> Self {
@@ -185,6 +225,7 @@ fn endian_size() {
     assert_eq!(size_of::<EndianPrimitive<B, i64>>(), 8);
     assert_eq!(size_of::<EndianPrimitive<B, f32>>(), 4);
     assert_eq!(size_of::<EndianPrimitive<B, f64>>(), 8);
+    assert_eq!(size_of::<EndianPrimitive<B, u128>>(), 16);
 
     assert_eq!(align_of::<EndianPrimitive<B, bool>>(), 1);
     assert_eq!(align_of::<EndianPrimitive<B, i16>>(), 1);
@@ -192,4 +233,18 @@ fn endian_size() {
     assert_eq!(align_of::<EndianPrimitive<B, i64>>(), 1);
     assert_eq!(align_of::<EndianPrimitive<B, f32>>(), 1);
     assert_eq!(align_of::<EndianPrimitive<B, f64>>(), 1);
+    assert_eq!(align_of::<EndianPrimitive<B, u128>>(), 1);
+}
+
+#[test]
+fn endian_128() {
+    let v = 0x0102030405060708090a0b0c0d0e0f10u128;
+
+    let be = EndianPrimitive::<BigEndian, u128>::new(v);
+    assert_eq!(be.raw().as_bytes(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+    assert_eq!(be.get(), v);
+
+    let le = EndianPrimitive::<LittleEndian, i128>::new(-2);
+    assert_eq!(le.raw().as_bytes()[..2], [0xfe, 0xff]);
+    assert_eq!(le.get(), -2);
 }
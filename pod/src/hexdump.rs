@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use layout::{Layout, TypeLayout};
+use ::Pod;
+
+const ROW: usize = 0x10;
+
+/// Dumps the bytes of a POD value as offset, hex, and ASCII columns
+pub fn dump<T: Pod>(value: &T) -> String {
+    dump_bytes(value.as_slice(), None)
+}
+
+/// Dumps the bytes of a POD value, annotating each row with the fields that start in it
+pub fn dump_layout<T: Pod + Layout>(value: &T) -> String {
+    dump_bytes(value.as_slice(), Some(T::layout()))
+}
+
+/// Dumps arbitrary data, such as the output of `Encode::encode_vec`
+pub fn dump_bytes(data: &[u8], layout: Option<&TypeLayout>) -> String {
+    let mut out = Vec::new();
+    dump_to(&mut out, data, layout).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("hexdump output is ASCII")
+}
+
+/// Writes a dump of `data` to `w`, annotated with field names if a layout is provided
+pub fn dump_to<W: Write>(w: &mut W, data: &[u8], layout: Option<&TypeLayout>) -> io::Result<()> {
+    for (i, row) in data.chunks(ROW).enumerate() {
+        let offset = i * ROW;
+        try!(write!(w, "{:08x} ", offset));
+
+        for col in 0..ROW {
+            if col % 8 == 0 {
+                try!(write!(w, " "));
+            }
+
+            match row.get(col) {
+                Some(b) => try!(write!(w, "{:02x} ", b)),
+                None => try!(write!(w, "   ")),
+            }
+        }
+
+        try!(write!(w, " |"));
+        for &b in row {
+            let c = if b >= 0x20 && b < 0x7f { b as char } else { '.' };
+            try!(write!(w, "{}", c));
+        }
+        try!(write!(w, "|"));
+
+        if let Some(layout) = layout {
+            let fields = layout.fields.iter()
+                .filter(|f| f.offset >= offset && f.offset < offset + row.len())
+                .map(|f| f.name)
+                .collect::<Vec<_>>();
+            if !fields.is_empty() {
+                try!(write!(w, "  {}", fields.join(", ")));
+            }
+        }
+
+        try!(writeln!(w));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn hexdump() {
+    use layout::FieldLayout;
+
+    static LAYOUT: TypeLayout = TypeLayout {
+        name: "Data",
+        size: 0x12,
+        fields: &[
+            FieldLayout { name: "magic", type_name: "[u8; 4]", offset: 0, size: 4 },
+            FieldLayout { name: "data", type_name: "[u8; 12]", offset: 4, size: 12 },
+            FieldLayout { name: "crc", type_name: "Le<u16>", offset: 0x10, size: 2 },
+        ],
+    };
+
+    let data = b"nue\0\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\xaa\x55";
+    assert_eq!(dump_bytes(data, Some(&LAYOUT)),
+        "00000000  6e 75 65 00 01 02 03 04  05 06 07 08 09 0a 0b 0c  |nue.............|  magic, data\n\
+         00000010  aa 55                                             |.U|  crc\n");
+    assert_eq!(dump(&0x41424344u32.to_be()), "00000000  41 42 43 44                                       |ABCD|\n");
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+/// Describes a single field within a type's encoded representation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The name of the field
+    pub name: &'static str,
+    /// The name of the field's type
+    pub type_name: &'static str,
+    /// The byte offset of the field from the start of the value
+    pub offset: usize,
+    /// The encoded size of the field in bytes
+    pub size: usize,
+}
+
+impl FieldLayout {
+    /// Returns the byte offset just past the end of the field
+    pub fn end(&self) -> usize {
+        self.offset + self.size
+    }
+}
+
+/// Describes the encoded representation of a type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TypeLayout {
+    /// The name of the type
+    pub name: &'static str,
+    /// The encoded size of the type in bytes
+    pub size: usize,
+    /// The type's fields, in order of increasing offset
+    pub fields: &'static [FieldLayout],
+}
+
+impl TypeLayout {
+    /// Looks up a field by name
+    pub fn field(&self, name: &str) -> Option<&'static FieldLayout> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Returns the field that contains the byte at `offset`, if any
+    pub fn field_at(&self, offset: usize) -> Option<&'static FieldLayout> {
+        self.fields.iter().find(|f| offset >= f.offset && offset < f.end())
+    }
+}
+
+/// A type that can describe its encoded layout at runtime.
+///
+/// Usually implemented by a derive, with the description stored in a `static`.
+pub trait Layout {
+    /// Returns the description of the type's layout
+    fn layout() -> &'static TypeLayout;
+}
+
+/// A collection of type layouts that can be looked up by name.
+///
+/// Tools such as hexdump annotators and schema exporters can use a registry
+/// to work over any described type at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    types: BTreeMap<&'static str, &'static TypeLayout>,
+}
+
+impl Registry {
+    /// Creates a new, empty registry
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registers the layout of `T`
+    ///
+    /// Returns the layout previously registered under the same name, if any.
+    pub fn register<T: Layout>(&mut self) -> Option<&'static TypeLayout> {
+        self.insert(T::layout())
+    }
+
+    /// Registers a layout description
+    ///
+    /// Returns the layout previously registered under the same name, if any.
+    pub fn insert(&mut self, layout: &'static TypeLayout) -> Option<&'static TypeLayout> {
+        self.types.insert(layout.name, layout)
+    }
+
+    /// Looks up a layout by type name
+    pub fn get(&self, name: &str) -> Option<&'static TypeLayout> {
+        self.types.get(name).cloned()
+    }
+
+    /// Iterates over all registered layouts, ordered by name
+    pub fn iter(&self) -> Iter {
+        Iter {
+            inner: self.types.values(),
+        }
+    }
+
+    /// Returns the number of registered layouts
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Returns whether no layouts have been registered
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+/// An iterator over the layouts in a `Registry`.
+pub struct Iter<'a> {
+    inner: btree_map::Values<'a, &'static str, &'static TypeLayout>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'static TypeLayout;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[test]
+fn registry() {
+    struct Header;
+
+    static HEADER: TypeLayout = TypeLayout {
+        name: "Header",
+        size: 8,
+        fields: &[
+            FieldLayout { name: "magic", type_name: "Be<u32>", offset: 0, size: 4 },
+            FieldLayout { name: "len", type_name: "Le<u32>", offset: 4, size: 4 },
+        ],
+    };
+
+    impl Layout for Header {
+        fn layout() -> &'static TypeLayout {
+            &HEADER
+        }
+    }
+
+    let mut registry = Registry::new();
+    assert!(registry.register::<Header>().is_none());
+    assert_eq!(registry.len(), 1);
+
+    let layout = registry.get("Header").unwrap();
+    assert_eq!(layout.field("len").unwrap().offset, 4);
+    assert_eq!(layout.field_at(3).unwrap().name, "magic");
+    assert!(layout.field_at(8).is_none());
+    assert_eq!(registry.iter().map(|l| l.name).collect::<Vec<_>>(), ["Header"]);
+}
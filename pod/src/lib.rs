@@ -42,6 +42,10 @@ extern crate resize_slice;
 extern crate byteorder;
 extern crate packed as nue_packed;
 extern crate nue_io;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
+#[cfg(feature = "proptest")]
+extern crate proptest;
 
 mod pod;
 
@@ -51,9 +55,27 @@ pub mod code;
 /// Containers for primitives
 pub mod endian;
 
-pub use endian::{Le, Be, Native};
-pub use code::{Encode, Decode};
-pub use pod::Pod;
+/// Runtime descriptions of encoded type layouts.
+pub mod layout;
+
+/// Variable-length integers
+pub mod varint;
+
+/// Reusable buffers for decoding without per-message allocations.
+pub mod pool;
+
+/// Annotated hexdumps for debugging binary data.
+pub mod hexdump;
+
+/// Helpers for testing `Encode` and `Decode` implementations.
+pub mod testing;
+
+pub use endian::{Le, Be, Native, Endianness};
+pub use varint::Varint;
+pub use code::{Encode, EncodedSize, Decode, DecodeRef, ReadPodExt, WritePodExt, TagOptions, VecDecodeOptions, StringDecodeOptions};
+pub use pool::{BufferPool, DecodePooled, decode_with_pool};
+pub use pod::{Pod, CheckedPod};
+pub use hexdump::dump;
 
 /// Re-export the `packed` crate
 pub use nue_packed as packed;
@@ -1,7 +1,8 @@
 use std::mem::{size_of, transmute, uninitialized};
 use std::slice::{from_raw_parts, from_raw_parts_mut};
-use resize_slice::SliceExt;
-use packed::{Unaligned, Aligned};
+use std::ptr;
+use std::marker::PhantomData;
+use packed::{Unaligned, Aligned, UnalignedBytes};
 use uninitialized;
 
 use self::unstable::{box_from, box_into};
@@ -37,9 +38,7 @@ pub unsafe trait Pod: Sized {
     #[inline]
     fn copy_from<'a>(slice: &'a [u8]) -> Self {
         assert_eq!(slice.len(), size_of::<Self>());
-        let mut s: Self = unsafe { uninitialized() };
-        s.mut_slice().copy_from(slice);
-        s
+        unsafe { ptr::read_unaligned(slice.as_ptr() as *const Self) }
     }
 
     /// Safely converts an unaligned value to its aligned equivalent
@@ -186,6 +185,53 @@ pub unsafe trait Pod: Sized {
     }
 }
 
+/// Describes a type that can be copied from its bytes, but only once they've
+/// been checked, such as a C-like enum.
+///
+/// Not every bit pattern is a valid instance, so unlike `Pod` this only offers
+/// conversions that validate their input. Every `Pod` type is also `CheckedPod`.
+///
+/// It is unsafe to `impl` this manually, use `#[derive(Pod)]` on the enum instead.
+pub unsafe trait CheckedPod: Sized {
+    /// Whether `bytes` hold a valid instance of the type
+    fn valid_bytes(bytes: &[u8]) -> bool;
+
+    /// Copies a value from a potentially unaligned slice, if its bytes are valid
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slice.len()` is not the same as the type's size
+    #[inline]
+    fn try_copy_from(slice: &[u8]) -> Option<Self> {
+        assert_eq!(slice.len(), size_of::<Self>());
+        if Self::valid_bytes(slice) {
+            Some(unsafe { ptr::read_unaligned(slice.as_ptr() as *const Self) })
+        } else {
+            None
+        }
+    }
+
+    /// Borrows a value from a byte slice, if its bytes are valid
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slice.len()` is not the same as the type's size
+    #[inline]
+    fn try_from_slice<'a>(slice: &'a [u8]) -> Option<&'a Self> where Self: Unaligned {
+        assert_eq!(slice.len(), size_of::<Self>());
+        if Self::valid_bytes(slice) {
+            Some(unsafe { &*(slice.as_ptr() as *const _) })
+        } else {
+            None
+        }
+    }
+}
+
+unsafe impl<T: Pod> CheckedPod for T {
+    #[inline]
+    fn valid_bytes(_bytes: &[u8]) -> bool { true }
+}
+
 unsafe impl Pod for () { }
 unsafe impl Pod for f32 { }
 unsafe impl Pod for f64 { }
@@ -197,10 +243,14 @@ unsafe impl Pod for i32 { }
 unsafe impl Pod for u32 { }
 unsafe impl Pod for i64 { }
 unsafe impl Pod for u64 { }
+unsafe impl Pod for i128 { }
+unsafe impl Pod for u128 { }
 unsafe impl Pod for isize { }
 unsafe impl Pod for usize { }
 unsafe impl<T> Pod for *const T { }
 unsafe impl<T> Pod for *mut T { }
+unsafe impl<T> Pod for PhantomData<T> { }
+unsafe impl<T: Pod> Pod for UnalignedBytes<T> { }
 
 macro_rules! pod_def {
     ($($x:expr),*) => {
@@ -217,6 +267,45 @@ pod_def! { 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2
 pod_def! { 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f }
 pod_def! { 0x40 }
 
+/// Implements `Pod` for a union, asserting that every member is `Pod` and as
+/// large as the union itself, so that no byte of it is ever left uninitialized.
+///
+/// ```
+/// #[macro_use]
+/// extern crate pod;
+/// use pod::{Pod, Be};
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// union Address {
+///     octets: [u8; 4],
+///     value: Be<u32>,
+/// }
+///
+/// unsafe_pod_union!(Address: [u8; 4], Be<u32>);
+///
+/// # fn main() {
+/// let address = Address { octets: [127, 0, 0, 1] };
+/// assert_eq!(unsafe { address.value }.get(), 0x7f000001);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! unsafe_pod_union {
+    ($t:ty: $($f:ty),+) => {
+        const _: () = {
+            $(assert!(::std::mem::size_of::<$f>() == ::std::mem::size_of::<$t>(), concat!(stringify!($f), " does not fill ", stringify!($t)));)+
+        };
+
+        unsafe impl $crate::Pod for $t {
+            fn __assert_pod() {
+                fn assert<T: $crate::Pod>() { }
+
+                $(assert::<$f>();)+
+            }
+        }
+    };
+}
+
 #[cfg(feature = "unstable")]
 mod unstable {
     pub unsafe fn box_from<T: ?Sized>(raw: *mut T) -> Box<T> { Box::from_raw(raw) }
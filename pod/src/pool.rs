@@ -0,0 +1,206 @@
+use std::io::{self, Read, Cursor};
+use std::ffi::CString;
+use nue_io::{ReadExactExt, Error};
+use code::{Decode, VecDecodeOptions, StringDecodeOptions, CStringDecodeOptions};
+use ::CheckedPod;
+
+/// A source of reusable byte buffers for decoding.
+///
+/// Buffers obtained with `take` should be handed back with `give` once they
+/// are no longer needed, including the bytes of decoded `String`s and
+/// `CString`s, so that steady-state decoding doesn't allocate.
+pub trait BufferPool {
+    /// Takes an empty buffer with room for at least `capacity` bytes
+    fn take(&mut self, capacity: usize) -> Vec<u8>;
+
+    /// Returns a buffer to the pool for reuse
+    fn give(&mut self, buf: Vec<u8>);
+}
+
+impl<'a, P: BufferPool + ?Sized> BufferPool for &'a mut P {
+    fn take(&mut self, capacity: usize) -> Vec<u8> {
+        (**self).take(capacity)
+    }
+
+    fn give(&mut self, buf: Vec<u8>) {
+        (**self).give(buf)
+    }
+}
+
+/// A pool that allocates every buffer and drops those it is given.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoPool;
+
+impl BufferPool for NoPool {
+    fn take(&mut self, capacity: usize) -> Vec<u8> {
+        Vec::with_capacity(capacity)
+    }
+
+    fn give(&mut self, _buf: Vec<u8>) { }
+}
+
+/// A bounded free list of buffers.
+#[derive(Clone, Debug)]
+pub struct VecPool {
+    buffers: Vec<Vec<u8>>,
+    max: usize,
+}
+
+impl VecPool {
+    /// Creates a pool that retains at most `max` buffers
+    pub fn new(max: usize) -> Self {
+        VecPool {
+            buffers: Vec::with_capacity(max),
+            max: max,
+        }
+    }
+
+    /// Returns the number of buffers available for reuse
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Returns whether no buffers are available for reuse
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+impl BufferPool for VecPool {
+    fn take(&mut self, capacity: usize) -> Vec<u8> {
+        match self.buffers.pop() {
+            Some(mut buf) => {
+                buf.reserve(capacity);
+                buf
+            },
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    fn give(&mut self, mut buf: Vec<u8>) {
+        if self.buffers.len() < self.max {
+            buf.clear();
+            self.buffers.push(buf);
+        }
+    }
+}
+
+/// A type that can be decoded with buffers obtained from a `BufferPool`.
+pub trait DecodePooled: Decode {
+    /// Decodes a value with the provided options, taking any buffers it needs from `pool`
+    fn decode_pooled<R: Read, P: BufferPool>(r: &mut R, options: Self::Options, pool: &mut P) -> io::Result<Self>;
+}
+
+/// Decodes a value with default options, taking any buffers it needs from `pool`
+pub fn decode_with_pool<T: DecodePooled, R: Read, P: BufferPool>(r: &mut R, pool: &mut P) -> io::Result<T> {
+    T::decode_pooled(r, Default::default(), pool)
+}
+
+/// Decodes a value with the provided options, taking any buffers it needs from `pool`
+pub fn decode_with_pool_options<T: DecodePooled, R: Read, P: BufferPool>(r: &mut R, options: T::Options, pool: &mut P) -> io::Result<T> {
+    T::decode_pooled(r, options, pool)
+}
+
+impl<T: CheckedPod> DecodePooled for T {
+    fn decode_pooled<R: Read, P: BufferPool>(r: &mut R, _options: (), _pool: &mut P) -> io::Result<Self> {
+        T::decode(r)
+    }
+}
+
+/// Reads `len` bytes, or until EOF, into a buffer from `pool`.
+fn read_pooled<R: Read, P: BufferPool>(r: &mut R, len: Option<usize>, pool: &mut P) -> io::Result<Vec<u8>> {
+    let mut buf = pool.take(len.unwrap_or(0));
+    let res = match len {
+        Some(len) => r.read_exact_uninit(&mut buf, len),
+        None => r.read_to_end(&mut buf).map(|_| ()),
+    };
+
+    match res {
+        Ok(()) => Ok(buf),
+        Err(e) => {
+            pool.give(buf);
+            Err(e)
+        },
+    }
+}
+
+impl DecodePooled for String {
+    fn decode_pooled<R: Read, P: BufferPool>(r: &mut R, options: StringDecodeOptions, pool: &mut P) -> io::Result<Self> {
+        let buf = try!(read_pooled(r, options.len, pool));
+        String::from_utf8(buf).map_err(|e| {
+            let message = e.utf8_error().to_string();
+            pool.give(e.into_bytes());
+            Error::Validation { field: None, message: message }.into()
+        })
+    }
+}
+
+impl DecodePooled for CString {
+    fn decode_pooled<R: Read, P: BufferPool>(r: &mut R, options: CStringDecodeOptions, pool: &mut P) -> io::Result<Self> {
+        let (mut buf, mut nul) = (pool.take(0), false);
+        for c in r.bytes() {
+            match c {
+                Ok(0) => {
+                    nul = true;
+                    break
+                },
+                Ok(c) => buf.push(c),
+                Err(e) => {
+                    pool.give(buf);
+                    return Err(e)
+                },
+            }
+        }
+
+        if options.require_nul && !nul {
+            pool.give(buf);
+            return Err(Error::UnexpectedEof { needed: Some(1) }.into())
+        }
+
+        Ok(unsafe { CString::from_vec_unchecked(buf) })
+    }
+}
+
+impl<T: Decode> DecodePooled for Vec<T> where T::Options: Clone {
+    /// Reading until EOF buffers the remaining input in a temporary buffer from `pool`.
+    fn decode_pooled<R: Read, P: BufferPool>(r: &mut R, options: Self::Options, pool: &mut P) -> io::Result<Self> {
+        if options.len.is_some() {
+            return Vec::decode_options(r, options)
+        }
+
+        let buf = try!(read_pooled(r, None, pool));
+        let res = Vec::decode_options(&mut Cursor::new(&buf[..]), VecDecodeOptions {
+            len: None,
+            options: options.options,
+        });
+        pool.give(buf);
+        res
+    }
+}
+
+#[test]
+fn pooled() {
+    let mut pool = VecPool::new(2);
+    pool.give(Vec::with_capacity(0x40));
+    assert_eq!(pool.len(), 1);
+
+    let s: String = decode_with_pool(&mut &b"hello"[..], &mut pool).unwrap();
+    assert_eq!(s, "hello");
+    assert!(s.capacity() >= 0x40);
+    assert!(pool.is_empty());
+    pool.give(s.into_bytes());
+
+    let c: CString = decode_with_pool(&mut &b"nue\0rest"[..], &mut pool).unwrap();
+    assert_eq!(c.as_bytes(), b"nue");
+    let options = CStringDecodeOptions { require_nul: true };
+    let c: CString = decode_with_pool_options(&mut &b"nue\0"[..], options, &mut pool).unwrap();
+    assert_eq!(c.as_bytes(), b"nue");
+    let err = decode_with_pool_options::<CString, _, _>(&mut &b"nue"[..], options, &mut pool).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let v: Vec<u16> = decode_with_pool(&mut &[1, 0, 2, 0][..], &mut pool).unwrap();
+    assert_eq!(v, [u16::from_le(1), u16::from_le(2)]);
+    assert_eq!(pool.len(), 1);
+
+    assert!(decode_with_pool::<String, _, _>(&mut &[0xff][..], &mut NoPool).is_err());
+}
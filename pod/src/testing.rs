@@ -0,0 +1,264 @@
+use std::io::{self, Cursor};
+use std::fmt::{self, Debug};
+use std::error;
+use code::{Encode, Decode};
+use layout::{Layout, TypeLayout};
+use ::Pod;
+
+/// Encodes a value, decodes it again, and asserts that the result is equal.
+///
+/// Also asserts that decoding consumes all of the encoded data.
+///
+/// # Panics
+///
+/// Panics if encoding or decoding fails, or the round trip doesn't match.
+pub fn assert_roundtrip<T: Encode + Decode + PartialEq + Debug>(value: &T) {
+    let data = value.encode_vec().expect("encode failed");
+
+    let mut cursor = Cursor::new(&data[..]);
+    let decoded = T::decode(&mut cursor).expect("decode failed");
+    assert_eq!(&decoded, value, "decoded value differs from the original");
+    assert_eq!(cursor.position() as usize, data.len(), "decode did not consume all {} encoded bytes", data.len());
+}
+
+/// A contiguous range of bytes that differs between golden data and its re-encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The byte offset where the difference starts
+    pub offset: usize,
+    /// The golden bytes in this range, which may be short if the re-encoding is longer
+    pub expected: Vec<u8>,
+    /// The re-encoded bytes in this range, which may be short if the golden data is longer
+    pub actual: Vec<u8>,
+    /// The name of the field containing this range, if layout metadata was available
+    pub field: Option<&'static str>,
+}
+
+/// The ways a golden file check can fail.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The golden data could not be decoded
+    Decode(io::Error),
+    /// The decoded value could not be encoded again
+    Encode(io::Error),
+    /// Decoding stopped before the end of the golden data
+    Trailing {
+        /// The number of bytes consumed by the decode
+        consumed: usize,
+        /// The length of the golden data
+        len: usize,
+    },
+    /// The re-encoded value differs from the golden data
+    Mismatch(Vec<Mismatch>),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GoldenError::Decode(ref err) => write!(f, "decode failed: {}", err),
+            GoldenError::Encode(ref err) => write!(f, "encode failed: {}", err),
+            GoldenError::Trailing { consumed, len } => write!(f, "decode consumed 0x{:x} of 0x{:x} bytes", consumed, len),
+            GoldenError::Mismatch(ref mismatches) => {
+                try!(write!(f, "{} mismatched ranges", mismatches.len()));
+                for m in mismatches {
+                    try!(write!(f, "\n  0x{:08x}", m.offset));
+                    if let Some(field) = m.field {
+                        try!(write!(f, " ({})", field));
+                    }
+                    try!(write!(f, ": expected {:02x?}, got {:02x?}", m.expected, m.actual));
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl error::Error for GoldenError {
+    fn description(&self) -> &str {
+        match *self {
+            GoldenError::Decode(..) => "golden data failed to decode",
+            GoldenError::Encode(..) => "golden value failed to encode",
+            GoldenError::Trailing { .. } => "golden data has trailing bytes",
+            GoldenError::Mismatch(..) => "golden data differs from its re-encoding",
+        }
+    }
+}
+
+/// Decodes `bytes`, encodes the result again, and reports any differences from the original.
+///
+/// Returns the decoded value if the re-encoding matches exactly.
+pub fn check_golden<T: Encode + Decode>(bytes: &[u8]) -> Result<T, GoldenError> {
+    check_golden_with(bytes, None)
+}
+
+/// Like `check_golden`, but names the field containing each mismatch.
+pub fn check_golden_layout<T: Encode + Decode + Layout>(bytes: &[u8]) -> Result<T, GoldenError> {
+    check_golden_with(bytes, Some(T::layout()))
+}
+
+fn check_golden_with<T: Encode + Decode>(bytes: &[u8], layout: Option<&TypeLayout>) -> Result<T, GoldenError> {
+    let mut cursor = Cursor::new(bytes);
+    let value = try!(T::decode(&mut cursor).map_err(GoldenError::Decode));
+    let consumed = cursor.position() as usize;
+    if consumed != bytes.len() {
+        return Err(GoldenError::Trailing { consumed: consumed, len: bytes.len() })
+    }
+
+    let encoded = try!(value.encode_vec().map_err(GoldenError::Encode));
+    let mismatches = diff(bytes, &encoded, layout);
+    if mismatches.is_empty() {
+        Ok(value)
+    } else {
+        Err(GoldenError::Mismatch(mismatches))
+    }
+}
+
+/// Compares two byte strings, grouping differing bytes into ranges that don't cross field boundaries.
+fn diff(expected: &[u8], actual: &[u8], layout: Option<&TypeLayout>) -> Vec<Mismatch> {
+    let field_at = |offset| layout.and_then(|l| l.field_at(offset)).map(|f| f.name);
+    let mut mismatches: Vec<Mismatch> = Vec::new();
+    let mut last = None;
+
+    for offset in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(offset), actual.get(offset));
+        if e == a {
+            continue
+        }
+
+        let field = field_at(offset);
+        match mismatches.last_mut() {
+            Some(m) if last == Some(offset - 1) && m.field == field => {
+                m.expected.extend(e);
+                m.actual.extend(a);
+            },
+            _ => mismatches.push(Mismatch {
+                offset: offset,
+                expected: e.cloned().into_iter().collect(),
+                actual: a.cloned().into_iter().collect(),
+                field: field,
+            }),
+        }
+        last = Some(offset);
+    }
+
+    mismatches
+}
+
+/// Creates a POD value with each byte produced by `f`.
+pub fn pod_from_fn<T: Pod, F: FnMut() -> u8>(mut f: F) -> T {
+    let mut pod = T::zeroed();
+    for b in pod.mut_slice() {
+        *b = f();
+    }
+    pod
+}
+
+/// Creates a POD value filled with pseudo-random bytes derived from `seed`.
+pub fn random_pod<T: Pod>(seed: u64) -> T {
+    let mut state = seed | 1;
+    pod_from_fn(|| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 24) as u8
+    })
+}
+
+/// A POD value that quickcheck can generate by filling its bytes randomly.
+#[cfg(feature = "quickcheck")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ArbitraryPod<T>(pub T);
+
+#[cfg(feature = "quickcheck")]
+impl<T: Pod + Clone + 'static> ::quickcheck::Arbitrary for ArbitraryPod<T> {
+    fn arbitrary(g: &mut ::quickcheck::Gen) -> Self {
+        ArbitraryPod(pod_from_fn(|| <u8 as ::quickcheck::Arbitrary>::arbitrary(g)))
+    }
+}
+
+/// A proptest strategy that generates POD values by filling their bytes randomly.
+#[cfg(feature = "proptest")]
+pub fn any_pod<T: Pod + Debug + 'static>() -> ::proptest::strategy::BoxedStrategy<T> {
+    use std::mem::size_of;
+    use proptest::strategy::Strategy;
+
+    ::proptest::collection::vec(::proptest::num::u8::ANY, size_of::<T>())
+        .prop_map(|bytes| T::copy_from(&bytes))
+        .boxed()
+}
+
+#[test]
+fn roundtrip() {
+    for seed in 0..0x10 {
+        let value: [u32; 4] = random_pod(seed);
+        assert_roundtrip(&value);
+    }
+
+    let mut n = 0;
+    assert_eq!(pod_from_fn::<[u8; 3], _>(|| { n += 1; n }), [1, 2, 3]);
+}
+
+#[test]
+fn golden() {
+    use layout::FieldLayout;
+    use endian::Le;
+
+    #[derive(Debug)]
+    struct Header(u8, Le<u16>);
+
+    static HEADER: TypeLayout = TypeLayout {
+        name: "Header",
+        size: 3,
+        fields: &[
+            FieldLayout { name: "kind", type_name: "u8", offset: 0, size: 1 },
+            FieldLayout { name: "len", type_name: "Le<u16>", offset: 1, size: 2 },
+        ],
+    };
+
+    impl Layout for Header {
+        fn layout() -> &'static TypeLayout { &HEADER }
+    }
+
+    impl Encode for Header {
+        type Options = ();
+
+        fn encode<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+            // Deliberately drops the high bit of the kind
+            try!((self.0 & 0x7f).encode(w));
+            self.1.encode(w)
+        }
+    }
+
+    impl Decode for Header {
+        type Options = ();
+
+        fn decode<R: io::Read>(r: &mut R) -> io::Result<Self> {
+            Ok(Header(try!(Decode::decode(r)), try!(Decode::decode(r))))
+        }
+    }
+
+    assert_eq!(check_golden::<Header>(&[0x01, 0x34, 0x12]).unwrap().1.get(), 0x1234);
+
+    match check_golden::<Header>(&[0x01, 0x34, 0x12, 0x00]) {
+        Err(GoldenError::Trailing { consumed: 3, len: 4 }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    match check_golden_layout::<Header>(&[0x81, 0x34, 0x12]) {
+        Err(GoldenError::Mismatch(m)) => assert_eq!(m, [Mismatch {
+            offset: 0,
+            expected: vec![0x81],
+            actual: vec![0x01],
+            field: Some("kind"),
+        }]),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    assert_eq!(diff(&[1, 2, 3], &[1, 4, 5, 6], None), [Mismatch {
+        offset: 1,
+        expected: vec![2, 3],
+        actual: vec![4, 5, 6],
+        field: None,
+    }]);
+}
@@ -0,0 +1,213 @@
+use std::io::{self, Read, Write};
+use std::fmt;
+use nue_io::{ReadExactExt, Error};
+use code::{Encode, EncodedSize, Decode};
+
+/// A container for an integer coded in the variable-length LEB128 format.
+///
+/// Each byte holds seven bits of the value, least significant first, with the high bit
+/// set on all but the last byte. Signed integers are sign extended from the final byte.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Varint<T>(pub T);
+
+impl<T: Copy> Varint<T> {
+    /// Creates a new value
+    #[inline]
+    pub fn new(v: T) -> Self {
+        Varint(v)
+    }
+
+    /// Gets the inner value
+    #[inline]
+    pub fn get(&self) -> T {
+        self.0
+    }
+
+    /// Sets the inner value
+    #[inline]
+    pub fn set(&mut self, v: T) {
+        self.0 = v
+    }
+}
+
+impl<T> From<T> for Varint<T> {
+    #[inline]
+    fn from(v: T) -> Self {
+        Varint(v)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Varint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        <T as fmt::Debug>::fmt(&self.0, f)
+    }
+}
+
+/// Describes an integer that can be coded as a `Varint`.
+pub trait VarintConvert: Copy {
+    /// Writes the value in LEB128
+    fn write_varint<W: Write>(self, w: &mut W) -> io::Result<()>;
+
+    /// Reads a LEB128 value, failing if it doesn't fit in `Self`
+    fn read_varint<R: Read>(r: &mut R) -> io::Result<Self>;
+
+    /// The number of bytes the value is written as
+    fn varint_size(self) -> u64;
+}
+
+/// Writes an unsigned LEB128 integer.
+pub fn write_uleb128<W: Write>(w: &mut W, value: u64) -> io::Result<()> {
+    let mut value = value;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return w.write_all(&[byte])
+        }
+        try!(w.write_all(&[byte | 0x80]));
+    }
+}
+
+/// Writes a signed LEB128 integer.
+pub fn write_sleb128<W: Write>(w: &mut W, value: i64) -> io::Result<()> {
+    let mut value = value;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            return w.write_all(&[byte])
+        }
+        try!(w.write_all(&[byte | 0x80]));
+    }
+}
+
+/// Reads the bytes of a LEB128 integer, returning its low 64 bits and the number of bits read.
+///
+/// Fails if the value doesn't fit in 64 bits, as unsigned or sign extended.
+fn read_leb128<R: Read>(r: &mut R, signed: bool) -> io::Result<(u64, u32)> {
+    let (mut value, mut shift) = (0u64, 0u32);
+    loop {
+        let mut byte = [0u8; 1];
+        if try!(r.read_exact_eof(&mut byte)) == 0 {
+            return Err(Error::UnexpectedEof { needed: Some(1) }.into())
+        }
+        let byte = byte[0];
+
+        // Only the lowest bit of a tenth byte is part of the value, and the rest must extend it
+        let payload = byte & 0x7f;
+        if shift >= 64 || (shift == 63 && payload != 0 && payload != if signed { 0x7f } else { 1 }) {
+            return Err(Error::Validation {
+                field: None,
+                message: "varint overflows 64 bits".to_owned(),
+            }.into())
+        }
+
+        value |= (payload as u64) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok((value, shift))
+        }
+    }
+}
+
+/// Reads an unsigned LEB128 integer.
+pub fn read_uleb128<R: Read>(r: &mut R) -> io::Result<u64> {
+    read_leb128(r, false).map(|(value, _)| value)
+}
+
+/// Reads a signed LEB128 integer.
+pub fn read_sleb128<R: Read>(r: &mut R) -> io::Result<i64> {
+    let (value, shift) = try!(read_leb128(r, true));
+    let value = value as i64;
+    Ok(if shift < 64 {
+        value << (64 - shift) >> (64 - shift)
+    } else {
+        value
+    })
+}
+
+fn out_of_range(ty: &str) -> io::Error {
+    Error::Validation {
+        field: None,
+        message: format!("varint out of range for {}", ty),
+    }.into()
+}
+
+macro_rules! varint_impl {
+    ($($t:ty),* => $wide:ty, $write:ident, $read:ident) => {
+        $(
+            impl VarintConvert for $t {
+                #[inline]
+                fn write_varint<W: Write>(self, w: &mut W) -> io::Result<()> {
+                    $write(w, self as $wide)
+                }
+
+                fn read_varint<R: Read>(r: &mut R) -> io::Result<Self> {
+                    let value = try!($read(r));
+                    if value as $t as $wide == value {
+                        Ok(value as $t)
+                    } else {
+                        Err(out_of_range(stringify!($t)))
+                    }
+                }
+
+                fn varint_size(self) -> u64 {
+                    let mut data = [0u8; 10];
+                    let mut w = &mut data[..];
+                    $write(&mut w, self as $wide).unwrap();
+                    10 - w.len() as u64
+                }
+            }
+        )*
+    };
+}
+
+varint_impl!(u8, u16, u32, u64, usize => u64, write_uleb128, read_uleb128);
+varint_impl!(i8, i16, i32, i64, isize => i64, write_sleb128, read_sleb128);
+
+impl<T: VarintConvert> Encode for Varint<T> {
+    type Options = ();
+
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.write_varint(w)
+    }
+}
+
+impl<T: VarintConvert> EncodedSize for Varint<T> {
+    fn encoded_size(&self) -> u64 {
+        self.0.varint_size()
+    }
+}
+
+impl<T: VarintConvert> Decode for Varint<T> {
+    type Options = ();
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        T::read_varint(r).map(Varint)
+    }
+}
+
+#[test]
+fn varint() {
+    fn round_trip<T: VarintConvert + PartialEq + fmt::Debug>(value: T, data: &[u8]) {
+        assert_eq!(Varint(value).encode_vec().unwrap(), data);
+        assert_eq!(Varint(value).encoded_size(), data.len() as u64);
+        assert_eq!(Varint::<T>::decode_slice(data).unwrap().get(), value);
+    }
+
+    round_trip(0u32, &[0]);
+    round_trip(127u8, &[0x7f]);
+    round_trip(300u16, &[0xac, 0x02]);
+    round_trip(624485u32, &[0xe5, 0x8e, 0x26]);
+    round_trip(!0u64, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+    round_trip(-1i32, &[0x7f]);
+    round_trip(63i8, &[0x3f]);
+    round_trip(-64i16, &[0x40]);
+    round_trip(-123456i64, &[0xc0, 0xbb, 0x78]);
+    round_trip(::std::i64::MIN, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f]);
+
+    assert!(Varint::<u8>::decode_slice(&[0x80, 0x02]).is_err());
+    assert!(Varint::<i8>::decode_slice(&[0x80, 0x01]).is_err());
+    assert!(Varint::<u64>::decode_slice(&[0xff; 11]).is_err());
+    assert!(Varint::<u32>::decode_slice(&[0x80]).is_err());
+}
@@ -1,6 +1,7 @@
+#[macro_use]
 extern crate pod;
 
-use pod::{Pod, Le, Be, Encode, Decode};
+use pod::{Pod, Le, Be, Encode, Decode, ReadPodExt, WritePodExt};
 use pod::packed::{Packed, Aligned, Un};
 use std::io::{Cursor, Seek, SeekFrom};
 
@@ -68,3 +69,31 @@ fn pod_box() {
     let boxed = POD::from_vec(vec);
     assert_eq!(*boxed, sample());
 }
+
+#[test]
+fn pod_ext() {
+    let mut buffer = Cursor::new(Vec::new());
+    buffer.write_pod(&sample()).unwrap();
+    buffer.write_pod(&[sample(), sample()][..]).unwrap();
+
+    buffer.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(buffer.read_pod::<POD>().unwrap(), sample());
+    assert_eq!(buffer.read_pod_vec::<POD>(2).unwrap(), [sample(), sample()]);
+    assert!(buffer.read_pod::<POD>().is_err());
+}
+
+#[test]
+fn pod_union() {
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    union Overlay {
+        pod: POD,
+        bytes: [u8; 9],
+    }
+
+    unsafe_pod_union!(Overlay: POD, [u8; 9]);
+
+    let overlay = Overlay::decode_slice(&POD_BYTES).unwrap();
+    assert_eq!(unsafe { overlay.pod }, sample());
+    assert_eq!(overlay.as_slice(), &POD_BYTES);
+}
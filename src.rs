@@ -4,11 +4,76 @@
 //!
 //! Re-exports items from the `pod` and `nue_io` crates. See `nue_macros`
 //! for more examples and usage.
+//!
+//! Commonly used traits can be imported all at once with `use nue::prelude::*`,
+//! and each subsystem is available under its own module.
+//!
+//! # Features
+//!
+//! The `io`, `pod`, and `packed` features select which subsystems are included,
+//! and are all enabled by default. `macros-syntex` re-exports `nue_codegen` as
+//! `nue::codegen` for use from build scripts on stable Rust, while `derive`
+//! re-exports the procedural derives of `nue_derive` instead.
 
+#[cfg(feature = "io")]
 extern crate nue_io;
+#[cfg(feature = "packed")]
 extern crate packed as nue_packed;
+#[cfg(feature = "pod")]
 extern crate pod;
+#[cfg(feature = "macros-syntex")]
+extern crate nue_codegen;
+#[cfg(feature = "derive")]
+extern crate nue_derive;
 
+#[cfg(feature = "pod")]
 pub use pod::*;
+#[cfg(feature = "io")]
 pub use nue_io::*;
+#[cfg(feature = "packed")]
 pub use nue_packed::*;
+
+/// Re-export the `nue_codegen` crate
+#[cfg(feature = "macros-syntex")]
+pub use nue_codegen as codegen;
+
+#[cfg(feature = "derive")]
+pub use nue_derive::{Packed, Pod, NueEncode, NueDecode, NueEncodedSize, NueDecodeRef};
+
+/// The traits needed for most encoding, decoding, and seeking.
+pub mod prelude {
+    #[cfg(feature = "pod")]
+    pub use pod::{Encode, Decode, Pod, ReadPodExt, WritePodExt};
+    #[cfg(feature = "pod")]
+    pub use pod::endian::EndianConvert;
+    #[cfg(feature = "packed")]
+    pub use nue_packed::{Aligned, Unaligned, Packed};
+    #[cfg(feature = "io")]
+    pub use nue_io::{SeekForward, SeekBackward, SeekRewind, SeekAbsolute, SeekEnd, Tell};
+    #[cfg(feature = "io")]
+    pub use nue_io::{ReadExactExt, WriteAllExt, SeekAlignExt, TellExt};
+}
+
+/// Stream wrappers and the individual seek traits.
+#[cfg(feature = "io")]
+pub mod io {
+    pub use nue_io::*;
+}
+
+/// Byte order aware primitive containers.
+#[cfg(feature = "pod")]
+pub mod endian {
+    pub use pod::endian::*;
+}
+
+/// Runtime descriptions of encoded type layouts.
+#[cfg(feature = "pod")]
+pub mod layout {
+    pub use pod::layout::*;
+}
+
+/// Safe handling of `#[repr(packed)]` and unaligned data.
+#[cfg(feature = "packed")]
+pub mod packed {
+    pub use nue_packed::*;
+}